@@ -1,6 +1,8 @@
 use crate::analyze::{ProjectType, SymbolInfo};
+use crate::config::CacheKeyMode;
 use crate::error::{QuickctxError, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::hash_map::DefaultHasher;
 use std::fs;
 use std::hash::{Hash, Hasher};
@@ -14,6 +16,9 @@ struct SymbolCacheEntry {
     mtime_secs: u64,
     mtime_nanos: u32,
     file_size: u64,
+    /// SHA-256 of the file, recorded with `CacheKeyMode::Content`
+    #[serde(default)]
+    content_hash: Option<String>,
     symbols: Vec<SymbolInfo>,
     project_type: ProjectType,
 }
@@ -25,6 +30,9 @@ struct ExternalTypeCacheEntry {
     mtime_secs: u64,
     mtime_nanos: u32,
     file_size: u64,
+    /// SHA-256 of the file, recorded with `CacheKeyMode::Content`
+    #[serde(default)]
+    content_hash: Option<String>,
     symbols: Vec<SymbolInfo>,
 }
 
@@ -33,6 +41,7 @@ pub struct SymbolCache {
     cache_root: PathBuf,
     symbols_dir: PathBuf,
     external_dir: PathBuf,
+    key_mode: CacheKeyMode,
 }
 
 impl SymbolCache {
@@ -58,6 +67,34 @@ impl SymbolCache {
             cache_root,
             symbols_dir,
             external_dir,
+            key_mode: CacheKeyMode::default(),
+        })
+    }
+
+    /// Validate entries by file contents instead of modification time
+    pub fn with_key_mode(mut self, key_mode: CacheKeyMode) -> Self {
+        self.key_mode = key_mode;
+        self
+    }
+
+    /// Hex SHA-256 of a file's contents
+    fn content_hash(file_path: &Path) -> Result<String> {
+        let bytes = fs::read(file_path).map_err(QuickctxError::Io)?;
+        Ok(format!("{:x}", Sha256::digest(&bytes)))
+    }
+
+    /// Content hash to store in a new entry, only computed in content mode
+    fn entry_hash(&self, file_path: &Path) -> Result<Option<String>> {
+        match self.key_mode {
+            CacheKeyMode::Content => Self::content_hash(file_path).map(Some),
+            CacheKeyMode::Mtime => Ok(None),
+        }
+    }
+
+    /// Whether the file still hashes to `cached` (an entry without a hash never matches)
+    fn content_unchanged(file_path: &Path, cached: Option<&str>) -> bool {
+        cached.is_some_and(|cached| {
+            Self::content_hash(file_path).is_ok_and(|current| current == cached)
         })
     }
 
@@ -145,6 +182,7 @@ impl SymbolCache {
             mtime_secs: mtime.as_secs(),
             mtime_nanos: mtime.subsec_nanos(),
             file_size: metadata.len(),
+            content_hash: self.entry_hash(file_path)?,
             symbols,
             project_type,
         };
@@ -226,6 +264,7 @@ impl SymbolCache {
             mtime_secs: mtime.as_secs(),
             mtime_nanos: mtime.subsec_nanos(),
             file_size: metadata.len(),
+            content_hash: self.entry_hash(file_path)?,
             symbols,
         };
 
@@ -296,6 +335,14 @@ impl SymbolCache {
             return Ok(false);
         }
 
+        if self.key_mode == CacheKeyMode::Content {
+            let unchanged = Self::content_unchanged(file_path, entry.content_hash.as_deref());
+            if !unchanged {
+                tracing::debug!("Cache invalid: content hash changed");
+            }
+            return Ok(unchanged);
+        }
+
         // Check modification time
         let current_mtime = metadata
             .modified()
@@ -343,6 +390,14 @@ impl SymbolCache {
             return Ok(false);
         }
 
+        if self.key_mode == CacheKeyMode::Content {
+            let unchanged = Self::content_unchanged(file_path, entry.content_hash.as_deref());
+            if !unchanged {
+                tracing::debug!("External cache invalid: content hash changed");
+            }
+            return Ok(unchanged);
+        }
+
         // Check modification time
         let current_mtime = metadata
             .modified()
@@ -437,6 +492,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_content_mode_survives_touch() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.rs");
+        fs::write(&test_file, "fn main() {}\n").unwrap();
+        let touch = || {
+            filetime::set_file_mtime(&test_file, filetime::FileTime::from_unix_time(1, 0)).unwrap()
+        };
+
+        let by_mtime = SymbolCache::new(Some(temp_dir.path().join("mtime")))?;
+        by_mtime.save_symbols(&test_file, vec![], ProjectType::Rust)?;
+        let by_content = SymbolCache::new(Some(temp_dir.path().join("content")))?
+            .with_key_mode(CacheKeyMode::Content);
+        by_content.save_symbols(&test_file, vec![], ProjectType::Rust)?;
+
+        // Same bytes, new mtime: only the content-keyed cache still hits
+        touch();
+        assert!(
+            by_mtime
+                .get_symbols(&test_file, ProjectType::Rust)?
+                .is_none()
+        );
+        assert!(
+            by_content
+                .get_symbols(&test_file, ProjectType::Rust)?
+                .is_some()
+        );
+
+        // Same size, different bytes
+        fs::write(&test_file, "fn mian() {}\n").unwrap();
+        touch();
+        assert!(
+            by_content
+                .get_symbols(&test_file, ProjectType::Rust)?
+                .is_none()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_external_cache_roundtrip() -> Result<()> {
         let temp_dir = TempDir::new().unwrap();
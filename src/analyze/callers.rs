@@ -0,0 +1,133 @@
+use super::path_types::RelativePath;
+use super::uri_utils::uri_to_file_path;
+use crate::error::{QuickctxError, Result};
+use lsp_types::CallHierarchyIncomingCall;
+use serde::Serialize;
+use std::path::Path;
+
+/// JSON-RPC error code for a method the server does not implement
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// One caller of the `--callers` target symbol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CallerRow {
+    /// Name of the calling function or method
+    pub name: String,
+    /// File containing the call, relative to the project root when inside it
+    pub file: String,
+    /// 1-based line of the first call site
+    pub line: u32,
+}
+
+/// Error returned when the language server cannot answer call hierarchy requests
+pub fn call_hierarchy_unsupported(code: i32, message: &str) -> QuickctxError {
+    if code == METHOD_NOT_FOUND {
+        QuickctxError::InvalidArgument(
+            "the language server does not support call hierarchy (unsupported)".to_string(),
+        )
+    } else {
+        QuickctxError::InvalidArgument(format!("call hierarchy request failed: {message}"))
+    }
+}
+
+/// Parse a `callHierarchy/incomingCalls` result (`null` means no callers)
+pub fn parse_incoming_calls(result: serde_json::Value) -> Result<Vec<CallHierarchyIncomingCall>> {
+    serde_json::from_value::<Option<Vec<CallHierarchyIncomingCall>>>(result)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| {
+            QuickctxError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to parse incomingCalls result: {}", e),
+            ))
+        })
+}
+
+/// Turn incoming calls into caller rows sorted by file and line
+///
+/// Files are shown relative to the first of `bases` that contains them. The
+/// line is taken from the first call site, falling back to the caller's own
+/// position when the server sends no ranges.
+pub fn caller_rows(calls: &[CallHierarchyIncomingCall], bases: &[&Path]) -> Vec<CallerRow> {
+    let mut rows: Vec<CallerRow> = calls
+        .iter()
+        .map(|call| {
+            let file = uri_to_file_path(&call.from.uri)
+                .map(|path| RelativePath::under_first(&path, bases).to_string())
+                .unwrap_or_else(|_| call.from.uri.as_str().to_string());
+            let position = call
+                .from_ranges
+                .first()
+                .map_or(call.from.selection_range.start, |range| range.start);
+
+            CallerRow {
+                name: call.from.name.clone(),
+                file,
+                line: position.line + 1,
+            }
+        })
+        .collect();
+
+    rows.sort_by(|a, b| (&a.file, a.line, &a.name).cmp(&(&b.file, b.line, &b.name)));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(name: &str, uri: &str, line: u32) -> serde_json::Value {
+        let range = json!({
+            "start": {"line": line, "character": 0},
+            "end": {"line": line + 3, "character": 1}
+        });
+        json!({"name": name, "kind": 12, "uri": uri, "range": range, "selectionRange": range})
+    }
+
+    #[test]
+    fn test_incoming_calls_become_caller_rows() {
+        let response = json!([
+            {
+                "from": item("run", "file:///p/src/main.rs", 10),
+                "fromRanges": [
+                    {"start": {"line": 14, "character": 4}, "end": {"line": 14, "character": 9}},
+                    {"start": {"line": 20, "character": 4}, "end": {"line": 20, "character": 9}}
+                ]
+            },
+            {
+                "from": item("helper", "file:///p/src/lib.rs", 2),
+                "fromRanges": []
+            }
+        ]);
+
+        let calls = parse_incoming_calls(response).unwrap();
+        let rows = caller_rows(&calls, &[Path::new("/p")]);
+
+        assert_eq!(
+            rows,
+            vec![
+                CallerRow {
+                    name: "helper".to_string(),
+                    file: "src/lib.rs".to_string(),
+                    line: 3,
+                },
+                CallerRow {
+                    name: "run".to_string(),
+                    file: "src/main.rs".to_string(),
+                    line: 15,
+                },
+            ]
+        );
+        assert!(
+            parse_incoming_calls(serde_json::Value::Null)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_method_not_found_is_reported_as_unsupported() {
+        let err = call_hierarchy_unsupported(METHOD_NOT_FOUND, "Unhandled method");
+        assert!(err.to_string().contains("does not support call hierarchy"));
+    }
+}
@@ -0,0 +1,27 @@
+use crate::copy::changed_files_since;
+use crate::error::{QuickctxError, Result};
+use camino::Utf8Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Keep only the files that differ from git revision `rev`, preserving order
+///
+/// `cwd` must be inside a git repository; paths are compared after
+/// canonicalization so relative and absolute inputs both match.
+pub fn retain_changed_files(files: Vec<PathBuf>, cwd: &Path, rev: &str) -> Result<Vec<PathBuf>> {
+    let cwd = Utf8Path::from_path(cwd)
+        .ok_or_else(|| QuickctxError::InvalidUtfPath(cwd.display().to_string()))?;
+
+    let changed: HashSet<PathBuf> = changed_files_since(cwd, rev)?
+        .into_iter()
+        .filter_map(|path| path.as_std_path().canonicalize().ok())
+        .collect();
+
+    Ok(files
+        .into_iter()
+        .filter(|file| {
+            file.canonicalize()
+                .is_ok_and(|canonical| changed.contains(&canonical))
+        })
+        .collect())
+}
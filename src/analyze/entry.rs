@@ -0,0 +1,236 @@
+use super::SymbolInfo;
+use super::type_resolver::TypeResolution;
+use crate::error::{QuickctxError, Result};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// A `FILE:SYMBOL` entry point selecting a single symbol to analyze
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    pub file: PathBuf,
+    pub symbol: String,
+}
+
+impl EntryPoint {
+    /// Parse an entry spec, splitting on the last `:` so paths may contain colons
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (file, symbol) = spec
+            .rsplit_once(':')
+            .filter(|(file, symbol)| !file.is_empty() && !symbol.trim().is_empty())
+            .ok_or_else(|| {
+                QuickctxError::InvalidArgument(format!(
+                    "invalid entry '{spec}': expected FILE:SYMBOL"
+                ))
+            })?;
+
+        Ok(Self {
+            file: PathBuf::from(file),
+            symbol: symbol.trim().to_string(),
+        })
+    }
+}
+
+/// Find a symbol by name anywhere in a symbol tree (depth-first)
+pub fn find_symbol<'a>(symbols: &'a [SymbolInfo], name: &str) -> Option<&'a SymbolInfo> {
+    symbols.iter().find_map(|symbol| {
+        if symbol.name == name {
+            Some(symbol)
+        } else {
+            find_symbol(&symbol.children, name)
+        }
+    })
+}
+
+/// Reduce analyzed files to an entry symbol plus the local types it depends on
+///
+/// Starting from `entry` in `entry_file`, local type dependencies are followed
+/// breadth-first up to `max_depth` hops. Files keep their input order and only
+/// contain the selected symbols; files with nothing selected are dropped.
+pub fn collect_entry_subgraph(
+    file_symbols: &[(PathBuf, Vec<SymbolInfo>)],
+    entry_file: &Path,
+    entry: &str,
+    max_depth: usize,
+) -> Result<Vec<(PathBuf, Vec<SymbolInfo>)>> {
+    let symbols_in = |file: &Path| {
+        file_symbols
+            .iter()
+            .find(|(path, _)| path == file)
+            .map(|(_, symbols)| symbols.as_slice())
+    };
+
+    let entry_symbol = symbols_in(entry_file)
+        .and_then(|symbols| find_symbol(symbols, entry))
+        .ok_or_else(|| {
+            QuickctxError::InvalidArgument(format!(
+                "symbol '{}' not found in {}",
+                entry,
+                entry_file.display()
+            ))
+        })?;
+
+    let mut selected: HashSet<(PathBuf, String)> = HashSet::new();
+    let mut queue = VecDeque::new();
+    selected.insert((entry_file.to_path_buf(), entry.to_string()));
+    queue.push_back((entry_symbol, 0));
+
+    while let Some((symbol, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+
+        for dependency in symbol.type_dependencies.iter().flatten() {
+            let TypeResolution::Local { file_path, .. } = &dependency.resolution else {
+                continue;
+            };
+
+            let key = (
+                file_path.as_path_buf().clone(),
+                dependency.type_name.clone(),
+            );
+            if selected.contains(&key) {
+                continue;
+            }
+
+            if let Some(definition) = symbols_in(file_path.as_path_buf())
+                .and_then(|symbols| find_symbol(symbols, &dependency.type_name))
+            {
+                selected.insert(key);
+                queue.push_back((definition, depth + 1));
+            }
+        }
+    }
+
+    let subgraph = file_symbols
+        .iter()
+        .filter_map(|(path, symbols)| {
+            let kept: Vec<SymbolInfo> = select_symbols(symbols, path, &selected);
+            (!kept.is_empty()).then(|| (path.clone(), kept))
+        })
+        .collect();
+
+    Ok(subgraph)
+}
+
+/// Keep selected symbols in their original order, searching nested children too
+fn select_symbols(
+    symbols: &[SymbolInfo],
+    file: &Path,
+    selected: &HashSet<(PathBuf, String)>,
+) -> Vec<SymbolInfo> {
+    let mut kept = Vec::new();
+    for symbol in symbols {
+        if selected.contains(&(file.to_path_buf(), symbol.name.clone())) {
+            kept.push(symbol.clone());
+        } else {
+            kept.extend(select_symbols(&symbol.children, file, selected));
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::path_types::FilePath;
+    use crate::analyze::type_extractor::TypeContext;
+    use crate::analyze::type_resolver::ResolvedType;
+    use lsp_types::{Range, SymbolKind};
+
+    fn symbol(name: &str, kind: SymbolKind, deps: Vec<(&str, &str)>) -> SymbolInfo {
+        let type_dependencies = deps
+            .into_iter()
+            .map(|(type_name, file)| ResolvedType {
+                type_name: type_name.to_string(),
+                context: TypeContext::FunctionParameter,
+                resolution: TypeResolution::Local {
+                    file_path: FilePath::from_absolute_unchecked(PathBuf::from(file)),
+                    line: 0,
+                    kind: "Struct".to_string(),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        SymbolInfo {
+            name: name.to_string(),
+            kind,
+            detail: None,
+            documentation: None,
+            range: Range::default(),
+            selection_range: Range::default(),
+            children: vec![],
+            type_dependencies: (!type_dependencies.is_empty()).then_some(type_dependencies),
+        }
+    }
+
+    fn project() -> Vec<(PathBuf, Vec<SymbolInfo>)> {
+        vec![
+            (
+                PathBuf::from("/p/src/lib.rs"),
+                vec![
+                    symbol(
+                        "process",
+                        SymbolKind::FUNCTION,
+                        vec![("Config", "/p/src/config.rs")],
+                    ),
+                    symbol("Unrelated", SymbolKind::STRUCT, vec![]),
+                ],
+            ),
+            (
+                PathBuf::from("/p/src/config.rs"),
+                vec![
+                    symbol(
+                        "Config",
+                        SymbolKind::STRUCT,
+                        vec![("Inner", "/p/src/config.rs")],
+                    ),
+                    symbol("Inner", SymbolKind::STRUCT, vec![]),
+                ],
+            ),
+        ]
+    }
+
+    fn names(subgraph: &[(PathBuf, Vec<SymbolInfo>)]) -> Vec<String> {
+        subgraph
+            .iter()
+            .flat_map(|(_, symbols)| symbols.iter().map(|s| s.name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_entry_point() {
+        let entry = EntryPoint::parse("src/lib.rs:process").unwrap();
+        assert_eq!(entry.file, PathBuf::from("src/lib.rs"));
+        assert_eq!(entry.symbol, "process");
+
+        assert!(EntryPoint::parse("src/lib.rs").is_err());
+        assert!(EntryPoint::parse("src/lib.rs:").is_err());
+        assert!(EntryPoint::parse(":process").is_err());
+    }
+
+    #[test]
+    fn test_entry_pulls_in_parameter_type_but_not_unrelated() {
+        let subgraph =
+            collect_entry_subgraph(&project(), Path::new("/p/src/lib.rs"), "process", 1).unwrap();
+
+        assert_eq!(names(&subgraph), vec!["process", "Config"]);
+    }
+
+    #[test]
+    fn test_entry_depth_limits_transitive_dependencies() {
+        let files = project();
+
+        let deep =
+            collect_entry_subgraph(&files, Path::new("/p/src/lib.rs"), "process", 2).unwrap();
+        assert_eq!(names(&deep), vec!["process", "Config", "Inner"]);
+
+        let shallow =
+            collect_entry_subgraph(&files, Path::new("/p/src/lib.rs"), "process", 0).unwrap();
+        assert_eq!(names(&shallow), vec!["process"]);
+    }
+
+    #[test]
+    fn test_entry_missing_symbol_errors() {
+        assert!(collect_entry_subgraph(&project(), Path::new("/p/src/lib.rs"), "nope", 1).is_err());
+    }
+}
@@ -1,8 +1,11 @@
 use crate::analyze::lsp_client::LspClient;
+use crate::analyze::project_root::ProjectType;
 use crate::analyze::type_resolver::ResolvedType;
 use crate::error::Result;
 use lsp_types::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SymbolInfo {
@@ -104,6 +107,85 @@ fn markup_to_string(markup: MarkedString) -> String {
     }
 }
 
+/// Source of hover information, implemented by [`LspClient`]
+pub trait HoverProvider {
+    fn hover(&mut self, uri: &Uri, position: Position) -> Result<Option<Hover>>;
+}
+
+impl HoverProvider for LspClient {
+    fn hover(&mut self, uri: &Uri, position: Position) -> Result<Option<Hover>> {
+        LspClient::hover(self, uri, position)
+    }
+}
+
+/// Fill in missing `detail` signatures from hover text, returning how many were enriched
+///
+/// The hover already stored in `documentation` is tried first. Only when it
+/// has no code-like line is a new hover requested at the symbol's
+/// `selection_range.start`, and at most `hover_budget` such requests are made.
+pub fn enrich_signatures<H: HoverProvider>(
+    client: &mut H,
+    uri: &Uri,
+    symbols: &mut [SymbolInfo],
+    hover_budget: &mut usize,
+) -> usize {
+    let mut enriched = 0;
+
+    for symbol in symbols {
+        if symbol.detail.as_deref().is_none_or(|d| d.trim().is_empty()) {
+            let mut signature = symbol
+                .documentation
+                .as_deref()
+                .and_then(signature_from_hover);
+            if signature.is_none() && *hover_budget > 0 {
+                *hover_budget -= 1;
+                signature = client
+                    .hover(uri, symbol.selection_range.start)
+                    .ok()
+                    .flatten()
+                    .and_then(extract_hover_docs)
+                    .as_deref()
+                    .and_then(signature_from_hover);
+            }
+            if signature.is_some() {
+                symbol.detail = signature;
+                enriched += 1;
+            }
+        }
+
+        enriched += enrich_signatures(client, uri, &mut symbol.children, hover_budget);
+    }
+
+    enriched
+}
+
+/// Pick the first code-like line from hover text as a signature
+///
+/// Prefers the first line of a fenced code block; otherwise the first line
+/// containing `(` or `:` is used. A leading `(function) `-style label is removed.
+fn signature_from_hover(hover: &str) -> Option<String> {
+    let mut lines = hover.lines().map(str::trim);
+    let line = if hover.contains("```") {
+        lines
+            .skip_while(|line| !line.starts_with("```"))
+            .skip(1)
+            .find(|line| !line.is_empty())
+            .filter(|line| !line.starts_with("```"))
+    } else {
+        lines.find(|line| line.contains('(') || line.contains(':'))
+    }?;
+
+    let line = match line
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(") "))
+    {
+        Some((label, rest)) if !label.contains(' ') => rest,
+        _ => line,
+    };
+
+    Some(line.to_string())
+}
+
 /// Filter symbols by kind (e.g., only functions, only types, etc.)
 pub fn filter_symbols_by_kind(symbols: &[SymbolInfo], kinds: &[SymbolKind]) -> Vec<SymbolInfo> {
     symbols
@@ -137,6 +219,124 @@ pub fn get_variables(symbols: &[SymbolInfo]) -> Vec<SymbolInfo> {
     filter_symbols_by_kind(symbols, &[SymbolKind::VARIABLE, SymbolKind::CONSTANT])
 }
 
+/// Declaration keywords that mark a Rust `detail` as a private item when not preceded by `pub`
+const RUST_DECLARATION_KEYWORDS: &[&str] = &[
+    "fn", "struct", "enum", "trait", "type", "const", "static", "mod", "union", "async", "unsafe",
+    "extern",
+];
+
+/// Declaration keywords that mark a TypeScript/JavaScript `detail` as module-private when not
+/// preceded by `export`
+const TS_DECLARATION_KEYWORDS: &[&str] = &[
+    "function",
+    "class",
+    "interface",
+    "type",
+    "enum",
+    "const",
+    "let",
+    "var",
+    "namespace",
+    "abstract",
+    "async",
+    "declare",
+];
+
+/// Visibility of a symbol as far as its `detail` (or, for Go, its name) tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// `pub`/`export` in the detail, or an exported (capitalized) Go name
+    Public,
+    /// A bare declaration keyword, a restricted `pub(...)` or an unexported Go name
+    Private,
+    /// No usable evidence either way
+    Unknown,
+}
+
+/// Heuristically classify a symbol's visibility
+///
+/// Only a detail that starts with a recognised keyword counts as evidence;
+/// missing or type-only details and unsupported languages are `Unknown`.
+pub fn symbol_visibility(symbol: &SymbolInfo, project_type: ProjectType) -> Visibility {
+    let first_word = symbol
+        .detail
+        .as_deref()
+        .and_then(|detail| detail.split_whitespace().next())
+        .unwrap_or("");
+
+    match project_type {
+        ProjectType::Rust if first_word == "pub" => Visibility::Public,
+        // `pub(crate)` and friends are not part of the public API
+        ProjectType::Rust
+            if RUST_DECLARATION_KEYWORDS.contains(&first_word)
+                || first_word.starts_with("pub(") =>
+        {
+            Visibility::Private
+        }
+        ProjectType::TypeScript | ProjectType::JavaScript if first_word == "export" => {
+            Visibility::Public
+        }
+        ProjectType::TypeScript | ProjectType::JavaScript
+            if TS_DECLARATION_KEYWORDS.contains(&first_word) =>
+        {
+            Visibility::Private
+        }
+        ProjectType::Go if symbol.name.starts_with(|c: char| c.is_uppercase()) => {
+            Visibility::Public
+        }
+        ProjectType::Go if !symbol.name.is_empty() => Visibility::Private,
+        _ => Visibility::Unknown,
+    }
+}
+
+/// Drop private symbols at every level, including private members of public types
+pub fn retain_public_symbols(
+    symbols: Vec<SymbolInfo>,
+    project_type: ProjectType,
+) -> Vec<SymbolInfo> {
+    // Only Rust and TypeScript/JavaScript default to public items
+    if !matches!(
+        project_type,
+        ProjectType::Rust | ProjectType::TypeScript | ProjectType::JavaScript
+    ) {
+        return symbols;
+    }
+
+    symbols
+        .into_iter()
+        .filter(|symbol| symbol_visibility(symbol, project_type) != Visibility::Private)
+        .map(|mut symbol| {
+            symbol.children = retain_public_symbols(symbol.children, project_type);
+            symbol
+        })
+        .collect()
+}
+
+/// Files whose top-level symbol count exceeds `max_symbols`, in input order
+pub fn files_over_symbol_limit(
+    counts: &[(String, usize)],
+    max_symbols: usize,
+) -> Vec<(String, usize)> {
+    counts
+        .iter()
+        .filter(|(_, count)| *count > max_symbols)
+        .cloned()
+        .collect()
+}
+
+/// Keep the first definition of each `(file, name)` pair, preserving order
+///
+/// External types are looked up per referencing file, so the same definition
+/// can be fetched more than once within a project.
+pub fn dedup_external_symbols(symbols: Vec<(PathBuf, SymbolInfo)>) -> Vec<SymbolInfo> {
+    let mut seen = HashSet::new();
+    symbols
+        .into_iter()
+        .filter(|(file, symbol)| seen.insert((file.clone(), symbol.name.clone())))
+        .map(|(_, symbol)| symbol)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +400,158 @@ mod tests {
         assert_eq!(types.len(), 1);
         assert_eq!(types[0].name, "Bar");
     }
+
+    /// Hover provider returning a fixed response and counting requests
+    struct MockHover {
+        response: &'static str,
+        calls: usize,
+    }
+
+    impl HoverProvider for MockHover {
+        fn hover(&mut self, _uri: &Uri, _position: Position) -> Result<Option<Hover>> {
+            self.calls += 1;
+            Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: self.response.to_string(),
+                }),
+                range: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn test_dedup_external_symbols_by_file_and_name() {
+        let shared = PathBuf::from("/deps/types.rs");
+        // Two analyzed files both pulled in `Shared` from the same external file
+        let fetched = vec![
+            (
+                shared.clone(),
+                symbol_with_detail("Shared", Some("pub struct Shared")),
+            ),
+            (
+                shared.clone(),
+                symbol_with_detail("Other", Some("pub struct Other")),
+            ),
+            (
+                shared.clone(),
+                symbol_with_detail("Shared", Some("pub struct Shared")),
+            ),
+            (
+                PathBuf::from("/deps/more.rs"),
+                symbol_with_detail("Shared", Some("pub struct Shared")),
+            ),
+        ];
+
+        let names: Vec<String> = dedup_external_symbols(fetched)
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert_eq!(names, vec!["Shared", "Other", "Shared"]);
+    }
+
+    #[test]
+    fn test_enrich_signatures_from_hover() {
+        let mut client = MockHover {
+            response: "```python\n(function) def load(path: str) -> Config\n```\n---\nLoad a config.",
+            calls: 0,
+        };
+        let uri: Uri = "file:///p/app.py".parse().unwrap();
+        let mut symbols = vec![
+            symbol_with_detail("load", None),
+            symbol_with_detail("save", Some("def save()")),
+        ];
+
+        let mut budget = 1;
+        let enriched = enrich_signatures(&mut client, &uri, &mut symbols, &mut budget);
+
+        assert_eq!(enriched, 1);
+        assert_eq!(client.calls, 1);
+        assert_eq!(
+            symbols[0].detail.as_deref(),
+            Some("def load(path: str) -> Config")
+        );
+        assert_eq!(symbols[1].detail.as_deref(), Some("def save()"));
+
+        // With the budget spent, further symbols are left alone
+        let mut more = vec![symbol_with_detail("other", None)];
+        assert_eq!(
+            enrich_signatures(&mut client, &uri, &mut more, &mut budget),
+            0
+        );
+        assert_eq!(client.calls, 1);
+        assert_eq!(more[0].detail, None);
+    }
+
+    fn symbol_with_detail(name: &str, detail: Option<&str>) -> SymbolInfo {
+        SymbolInfo {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            detail: detail.map(str::to_string),
+            documentation: None,
+            range: Range::default(),
+            selection_range: Range::default(),
+            children: vec![],
+            type_dependencies: None,
+        }
+    }
+
+    #[test]
+    fn test_retain_public_symbols_rust() {
+        let mut parent = symbol_with_detail("Api", Some("pub struct Api"));
+        parent.children = vec![
+            symbol_with_detail("helper", Some("fn helper()")),
+            symbol_with_detail("new", Some("pub fn new()")),
+        ];
+
+        let symbols = vec![
+            symbol_with_detail("foo", Some("fn foo()")),
+            symbol_with_detail("bar", Some("pub fn bar()")),
+            symbol_with_detail("baz", Some("pub(crate) fn baz()")),
+            symbol_with_detail("unknown", None),
+            parent,
+        ];
+
+        let public = retain_public_symbols(symbols, ProjectType::Rust);
+        let names: Vec<&str> = public.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["bar", "unknown", "Api"]);
+        let children: Vec<&str> = public[2].children.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(children, vec!["new"]);
+    }
+
+    #[test]
+    fn test_retain_public_symbols_typescript() {
+        let symbols = vec![
+            symbol_with_detail("internal", Some("function internal()")),
+            symbol_with_detail("api", Some("export function api()")),
+        ];
+
+        let public = retain_public_symbols(symbols, ProjectType::TypeScript);
+        assert_eq!(public.len(), 1);
+        assert_eq!(public[0].name, "api");
+    }
+
+    #[test]
+    fn test_retain_public_symbols_keeps_everything_without_markers() {
+        let symbols = vec![symbol_with_detail("_helper", Some("def _helper()"))];
+
+        let public = retain_public_symbols(symbols, ProjectType::Python);
+        assert_eq!(public.len(), 1);
+    }
+
+    #[test]
+    fn test_files_over_symbol_limit() {
+        let counts = vec![
+            ("src/small.rs".to_string(), 3),
+            ("src/exact.rs".to_string(), 5),
+            ("src/big.rs".to_string(), 9),
+        ];
+
+        assert_eq!(
+            files_over_symbol_limit(&counts, 5),
+            vec![("src/big.rs".to_string(), 9)]
+        );
+        assert!(files_over_symbol_limit(&counts, 9).is_empty());
+        assert_eq!(files_over_symbol_limit(&counts, 0).len(), 3);
+    }
 }
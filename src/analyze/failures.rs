@@ -0,0 +1,149 @@
+use crate::error::{QuickctxError, Result};
+use serde::Serialize;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+
+/// Broad category of an analysis failure, for tooling that reads `--errors-json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The language server could not be started
+    Spawn,
+    /// The server or a file took too long to answer
+    Timeout,
+    /// A server response could not be decoded
+    Parse,
+    /// Anything else
+    Error,
+}
+
+impl FailureKind {
+    /// Categorize an error from the LSP client
+    pub fn of(err: &QuickctxError) -> Self {
+        match err {
+            QuickctxError::Io(io) => match io.kind() {
+                ErrorKind::TimedOut => Self::Timeout,
+                ErrorKind::InvalidData => Self::Parse,
+                _ => Self::Error,
+            },
+            QuickctxError::LspSpawn { .. } => Self::Spawn,
+            _ => Self::Error,
+        }
+    }
+}
+
+/// One project or file that analyze skipped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailureRecord {
+    /// Project name as shown in the report
+    pub project: String,
+    /// Project root directory
+    pub root: String,
+    /// File as shown in the report (see `--paths-relative-to`), absent when the whole
+    /// project failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl FailureRecord {
+    /// Record a project whose analysis stopped with `err`
+    pub fn project(project: &str, root: &Path, err: &QuickctxError) -> Self {
+        Self {
+            project: project.to_string(),
+            root: root.display().to_string(),
+            file: None,
+            kind: FailureKind::of(err),
+            message: err.to_string(),
+        }
+    }
+
+    /// Record a file skipped by `--file-timeout`, given its report display path
+    pub fn file_timed_out(project: &str, root: &Path, file: String, elapsed: Duration) -> Self {
+        Self {
+            project: project.to_string(),
+            root: root.display().to_string(),
+            file: Some(file),
+            kind: FailureKind::Timeout,
+            message: format!(
+                "symbol extraction took {:.1}s, over --file-timeout",
+                elapsed.as_secs_f64()
+            ),
+        }
+    }
+}
+
+/// Write failure records to `path` as a JSON array (empty when nothing failed)
+pub fn write_failures(path: &Path, records: &[FailureRecord]) -> Result<()> {
+    let json = serde_json::to_string_pretty(records).map_err(|e| {
+        QuickctxError::InvalidArgument(format!(
+            "{}: failed to serialize failures: {e}",
+            path.display()
+        ))
+    })?;
+    std::fs::write(path, json + "\n").map_err(QuickctxError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::RelativePath;
+
+    #[test]
+    fn test_failure_kind_from_client_errors() {
+        let spawn = QuickctxError::LspSpawn {
+            server: "nope".to_string(),
+            source: std::io::Error::new(ErrorKind::NotFound, "No such file or directory"),
+        };
+        // Only the spawn site's variant counts, not an I/O error that reads the same
+        let lookalike = QuickctxError::Io(std::io::Error::other(spawn.to_string()));
+        let timeout = QuickctxError::Io(std::io::Error::new(ErrorKind::TimedOut, "slow"));
+        let parse = QuickctxError::Io(std::io::Error::new(ErrorKind::InvalidData, "bad json"));
+
+        assert_eq!(FailureKind::of(&spawn), FailureKind::Spawn);
+        assert_eq!(FailureKind::of(&lookalike), FailureKind::Error);
+        assert_eq!(
+            spawn.to_string(),
+            "Failed to spawn LSP server 'nope': No such file or directory"
+        );
+        assert_eq!(FailureKind::of(&timeout), FailureKind::Timeout);
+        assert_eq!(FailureKind::of(&parse), FailureKind::Parse);
+        assert_eq!(
+            FailureKind::of(&QuickctxError::Config("x".into())),
+            FailureKind::Error
+        );
+    }
+
+    #[test]
+    fn test_file_timeout_record_is_relative_to_root() {
+        let file = Path::new("/p/src/big.rs");
+        let record = FailureRecord::file_timed_out(
+            "demo",
+            Path::new("/p"),
+            RelativePath::under_first(file, &[Path::new("/p")]).to_string(),
+            Duration::from_millis(2500),
+        );
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["file"], "src/big.rs");
+        assert_eq!(json["kind"], "timeout");
+        assert!(json["message"].as_str().unwrap().contains("2.5s"));
+    }
+
+    #[test]
+    fn test_file_timeout_record_follows_paths_relative_to() {
+        let root = Path::new("/repo/services/api");
+        let file = Path::new("/repo/services/api/src/big.rs");
+        let record = FailureRecord::file_timed_out(
+            "api",
+            root,
+            RelativePath::under_first(file, &[Path::new("/repo"), root]).to_string(),
+            Duration::from_secs(3),
+        );
+
+        assert_eq!(record.file.as_deref(), Some("services/api/src/big.rs"));
+        assert_eq!(record.root, "/repo/services/api");
+    }
+}
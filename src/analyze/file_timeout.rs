@@ -0,0 +1,88 @@
+use crate::error::Result;
+use std::time::{Duration, Instant};
+
+/// Recorded in the output in place of a file's symbols when it hit `--file-timeout`
+pub const TIMED_OUT_NOTE: &str = "analysis timed out";
+
+/// Outcome of analyzing one file under a wall-clock budget
+#[derive(Debug, PartialEq, Eq)]
+pub enum FileAnalysis<T> {
+    Done(T),
+    /// The file took longer than the budget; any partial result was discarded
+    TimedOut(Duration),
+}
+
+/// Run `analyze` for one file, reporting `TimedOut` when it overruns `timeout`
+///
+/// Requests cannot be interrupted mid-flight, so `analyze` receives the
+/// deadline and is expected to stop issuing work once it passes. Whatever it
+/// returns after the deadline, including an error, counts as a timeout.
+pub fn analyze_within<T>(
+    timeout: Option<Duration>,
+    analyze: impl FnOnce(Option<Instant>) -> Result<T>,
+) -> Result<FileAnalysis<T>> {
+    let start = Instant::now();
+    let result = analyze(timeout.map(|timeout| start + timeout));
+    let elapsed = start.elapsed();
+
+    match timeout {
+        Some(timeout) if elapsed > timeout => Ok(FileAnalysis::TimedOut(elapsed)),
+        _ => result.map(FileAnalysis::Done),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::QuickctxError;
+
+    /// Stands in for an LSP client whose requests take a fixed time per file
+    struct SleepyClient {
+        delays: Vec<(&'static str, Duration)>,
+    }
+
+    impl SleepyClient {
+        fn symbols(&self, file: &str, deadline: Option<Instant>) -> Result<Vec<String>> {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(QuickctxError::Aborted("deadline passed".into()));
+            }
+            let (_, delay) = self.delays.iter().find(|(name, _)| *name == file).unwrap();
+            std::thread::sleep(*delay);
+            Ok(vec![format!("{file}::item")])
+        }
+    }
+
+    #[test]
+    fn test_slow_file_is_skipped_and_others_processed() {
+        let client = SleepyClient {
+            delays: vec![
+                ("a.rs", Duration::ZERO),
+                ("slow.rs", Duration::from_millis(300)),
+                ("b.rs", Duration::ZERO),
+            ],
+        };
+        let timeout = Some(Duration::from_millis(100));
+
+        let outcomes: Vec<_> = ["a.rs", "slow.rs", "b.rs"]
+            .into_iter()
+            .map(|file| analyze_within(timeout, |deadline| client.symbols(file, deadline)).unwrap())
+            .collect();
+
+        assert_eq!(
+            outcomes[0],
+            FileAnalysis::Done(vec!["a.rs::item".to_string()])
+        );
+        assert!(matches!(outcomes[1], FileAnalysis::TimedOut(_)));
+        assert_eq!(
+            outcomes[2],
+            FileAnalysis::Done(vec!["b.rs::item".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_no_timeout_passes_errors_through() {
+        let result: Result<FileAnalysis<()>> =
+            analyze_within(None, |_| Err(QuickctxError::Aborted("boom".into())));
+        assert!(result.is_err());
+    }
+}
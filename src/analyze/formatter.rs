@@ -1,9 +1,17 @@
 use crate::analyze::ProjectType;
-use crate::analyze::extractor::{SymbolInfo, get_functions, get_types, get_variables};
+use crate::analyze::callers::CallerRow;
+use crate::analyze::extractor::{
+    SymbolInfo, Visibility, get_functions, get_types, get_variables, symbol_visibility,
+};
+use crate::analyze::lsp_config::extension_to_project_type;
 use crate::analyze::path_types::RelativePath;
 use crate::analyze::type_resolver::{ResolvedType, TypeResolution};
+use camino::Utf8Path;
 use lsp_types::SymbolKind;
 use serde::Serialize;
+use serde::ser::{SerializeMap, Serializer};
+use std::collections::BTreeSet;
+use std::io::Write;
 
 /// A file with its path and associated symbols
 type FileSymbols = (String, Vec<SymbolInfo>);
@@ -35,11 +43,69 @@ pub struct ProjectDiagnostics {
     pub files: Vec<FileDiagnostics>,
 }
 
+/// Diagnostic totals by severity, printed as a single summary line for CI logs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiagnosticCounts {
+    pub errors: usize,
+    pub warnings: usize,
+    pub info: usize,
+    pub hints: usize,
+}
+
+impl DiagnosticCounts {
+    /// Count diagnostics across all projects; diagnostics without a severity are not counted
+    pub fn from_projects(projects: &[ProjectDiagnostics]) -> Self {
+        let mut counts = Self::default();
+        let diagnostics = projects
+            .iter()
+            .flat_map(|project| &project.files)
+            .flat_map(|file| &file.diagnostics);
+        for diagnostic in diagnostics {
+            match diagnostic.severity {
+                Some(lsp_types::DiagnosticSeverity::ERROR) => counts.errors += 1,
+                Some(lsp_types::DiagnosticSeverity::WARNING) => counts.warnings += 1,
+                Some(lsp_types::DiagnosticSeverity::INFORMATION) => counts.info += 1,
+                Some(lsp_types::DiagnosticSeverity::HINT) => counts.hints += 1,
+                _ => {}
+            }
+        }
+        counts
+    }
+}
+
+impl std::fmt::Display for DiagnosticCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "DIAGNOSTICS errors={} warnings={} info={} hints={}",
+            self.errors, self.warnings, self.info, self.hints
+        )
+    }
+}
+
+/// Type references made by one symbol of a file
+///
+/// Members report under their enclosing type, so a struct's fields and
+/// methods all count as dependencies of that struct.
+#[derive(Debug, Clone)]
+pub struct SymbolTypeDependencies {
+    pub symbol_name: String,
+    pub symbol_kind: SymbolKind,
+    pub types: Vec<ResolvedType>,
+}
+
 /// Type dependencies for a single file
 #[derive(Debug, Clone)]
 pub struct FileTypeDependencies {
     pub file_path: RelativePath,
-    pub types: Vec<ResolvedType>,
+    pub symbols: Vec<SymbolTypeDependencies>,
+}
+
+impl FileTypeDependencies {
+    /// Every type reference in the file, in symbol order
+    pub fn types(&self) -> impl Iterator<Item = &ResolvedType> {
+        self.symbols.iter().flat_map(|s| &s.types)
+    }
 }
 
 /// Type dependencies for a project
@@ -55,10 +121,57 @@ pub trait Formatter {
     fn format_multiple(&self, files: &[FileSymbols]) -> String;
     fn format_by_projects(&self, projects: &[ProjectSymbols]) -> String;
     fn format_diagnostics(&self, projects: &[ProjectDiagnostics]) -> String;
+    /// Symbol report followed by the diagnostics report, as one document (`--with-diagnostics`)
+    fn format_symbols_with_diagnostics(
+        &self,
+        projects: &[ProjectSymbols],
+        diagnostics: &[ProjectDiagnostics],
+    ) -> String {
+        format!(
+            "{}\n{}",
+            self.format_by_projects(projects),
+            self.format_diagnostics(diagnostics)
+        )
+    }
     fn format_type_dependencies(&self, projects: &[ProjectTypeDependencies]) -> String;
+    /// Sorted, deduplicated unresolved type names (`--unresolved-only`)
+    fn format_unresolved_types(&self, names: &[String]) -> String;
+    fn format_callers(&self, target: &str, callers: &[CallerRow]) -> String;
+}
+
+/// Options that tweak formatter output
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatterOptions {
+    /// Prepend a mermaid class diagram to markdown type-dependency reports
+    pub mermaid: bool,
+    /// Reduce type-dependency reports to the unresolved type names
+    pub unresolved_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownFormatter {
+    mermaid: bool,
+    unresolved_only: bool,
+}
+
+impl MarkdownFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include a mermaid `classDiagram` in type-dependency reports
+    pub fn with_mermaid(mut self, mermaid: bool) -> Self {
+        self.mermaid = mermaid;
+        self
+    }
+
+    /// Emit only the sorted, deduplicated unresolved type names in type-dependency reports
+    pub fn with_unresolved_only(mut self, unresolved_only: bool) -> Self {
+        self.unresolved_only = unresolved_only;
+        self
+    }
 }
 
-pub struct MarkdownFormatter;
 pub struct JsonFormatter;
 pub struct CsvFormatter;
 pub struct CompactFormatter;
@@ -316,37 +429,45 @@ impl Formatter for MarkdownFormatter {
     fn format_type_dependencies(&self, projects: &[ProjectTypeDependencies]) -> String {
         use crate::analyze::type_extractor::TypeContext;
 
+        if self.unresolved_only {
+            return self.format_unresolved_types(&unresolved_type_names(projects));
+        }
+
         let mut output = String::new();
 
         // Header
         output.push_str("# Type Dependencies Report\n\n");
 
+        if self.mermaid {
+            output.push_str(&format_mermaid_class_diagram(projects));
+        }
+
         // Count totals
         let total_files: usize = projects.iter().map(|p| p.files.len()).sum();
         let total_types: usize = projects
             .iter()
             .flat_map(|p| &p.files)
-            .map(|f| f.types.len())
+            .map(|f| f.types().count())
             .sum();
 
         let local_types: usize = projects
             .iter()
             .flat_map(|p| &p.files)
-            .flat_map(|f| &f.types)
+            .flat_map(|f| f.types())
             .filter(|t| matches!(t.resolution, TypeResolution::Local { .. }))
             .count();
 
         let external_types: usize = projects
             .iter()
             .flat_map(|p| &p.files)
-            .flat_map(|f| &f.types)
+            .flat_map(|f| f.types())
             .filter(|t| matches!(t.resolution, TypeResolution::External { .. }))
             .count();
 
         let unresolved_types: usize = projects
             .iter()
             .flat_map(|p| &p.files)
-            .flat_map(|f| &f.types)
+            .flat_map(|f| f.types())
             .filter(|t| matches!(t.resolution, TypeResolution::Unresolved))
             .count();
 
@@ -370,7 +491,7 @@ impl Formatter for MarkdownFormatter {
             ));
 
             for file in &project.files {
-                if file.types.is_empty() {
+                if file.symbols.is_empty() {
                     continue;
                 }
 
@@ -378,28 +499,23 @@ impl Formatter for MarkdownFormatter {
 
                 // Group by context
                 let params: Vec<_> = file
-                    .types
-                    .iter()
+                    .types()
                     .filter(|t| matches!(t.context, TypeContext::FunctionParameter))
                     .collect();
                 let returns: Vec<_> = file
-                    .types
-                    .iter()
+                    .types()
                     .filter(|t| matches!(t.context, TypeContext::FunctionReturn))
                     .collect();
                 let fields: Vec<_> = file
-                    .types
-                    .iter()
+                    .types()
                     .filter(|t| matches!(t.context, TypeContext::StructField))
                     .collect();
                 let aliases: Vec<_> = file
-                    .types
-                    .iter()
+                    .types()
                     .filter(|t| matches!(t.context, TypeContext::TypeAlias))
                     .collect();
                 let traits: Vec<_> = file
-                    .types
-                    .iter()
+                    .types()
                     .filter(|t| matches!(t.context, TypeContext::TraitBound))
                     .collect();
 
@@ -448,6 +564,109 @@ impl Formatter for MarkdownFormatter {
 
         output
     }
+
+    fn format_unresolved_types(&self, names: &[String]) -> String {
+        let mut output = String::from("# Unresolved Types\n\n");
+        if names.is_empty() {
+            output.push_str("All referenced types were resolved.\n");
+        }
+        for name in names {
+            output.push_str(&format!("- `{}`\n", name));
+        }
+        output
+    }
+
+    fn format_callers(&self, target: &str, callers: &[CallerRow]) -> String {
+        let mut output = format!("# Callers of `{}`\n\n", target);
+        if callers.is_empty() {
+            output.push_str("No callers found.\n");
+            return output;
+        }
+
+        output.push_str("| Caller | File | Line |\n");
+        output.push_str("|--------|------|------|\n");
+        for caller in callers {
+            output.push_str(&format!(
+                "| `{}` | {} | {} |\n",
+                caller.name, caller.file, caller.line
+            ));
+        }
+        output
+    }
+}
+
+/// Render local types and their dependency edges as a mermaid class diagram
+///
+/// Each type symbol gets an edge to every resolved type it references itself
+/// (through its fields and methods); free functions only contribute nodes.
+fn format_mermaid_class_diagram(projects: &[ProjectTypeDependencies]) -> String {
+    let mut nodes = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+
+    for symbol in projects
+        .iter()
+        .flat_map(|p| &p.files)
+        .flat_map(|f| &f.symbols)
+    {
+        let owner = is_type_symbol(symbol.symbol_kind)
+            .then(|| mermaid_type_name(&symbol.symbol_name))
+            .filter(|name| !name.is_empty());
+        if let Some(owner) = &owner {
+            nodes.insert(owner.clone());
+        }
+
+        for typ in &symbol.types {
+            let name = mermaid_type_name(&typ.type_name);
+            if name.is_empty() {
+                continue;
+            }
+
+            match typ.resolution {
+                TypeResolution::Local { .. } => {
+                    nodes.insert(name.clone());
+                }
+                TypeResolution::External { .. } => {}
+                TypeResolution::Unresolved => continue,
+            }
+
+            if let Some(owner) = &owner
+                && *owner != name
+            {
+                edges.insert((owner.clone(), name.clone()));
+            }
+        }
+    }
+
+    if nodes.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("```mermaid\nclassDiagram\n");
+    for node in &nodes {
+        output.push_str(&format!("    class {}\n", node));
+    }
+    for (from, to) in &edges {
+        output.push_str(&format!("    {} ..> {}\n", from, to));
+    }
+    output.push_str("```\n\n");
+    output
+}
+
+/// Symbols that define a type, and so own the references of their members
+fn is_type_symbol(kind: SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::STRUCT | SymbolKind::CLASS | SymbolKind::INTERFACE | SymbolKind::ENUM
+    )
+}
+
+/// Reduce a type name to a mermaid-safe identifier (generics and paths stripped)
+fn mermaid_type_name(type_name: &str) -> String {
+    let base = type_name.split('<').next().unwrap_or(type_name);
+    let base = base.rsplit("::").next().unwrap_or(base);
+    base.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
 }
 
 fn format_resolved_type(resolved: &ResolvedType) -> String {
@@ -624,16 +843,18 @@ fn symbol_kind_to_string(kind: SymbolKind) -> &'static str {
     }
 }
 
+/// Fields are declared alphabetically so serializing directly (when streaming)
+/// matches the key order of a `serde_json::Value` round-trip
 #[derive(Debug, Serialize)]
 struct JsonSymbol {
-    name: String,
-    kind: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<JsonSymbol>,
     detail: Option<String>,
     documentation: Option<String>,
-    line_start: u32,
+    kind: String,
     line_end: u32,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    children: Vec<JsonSymbol>,
+    line_start: u32,
+    name: String,
 }
 
 impl From<&SymbolInfo> for JsonSymbol {
@@ -650,6 +871,74 @@ impl From<&SymbolInfo> for JsonSymbol {
     }
 }
 
+impl JsonFormatter {
+    /// Stream the `format_by_projects` document to `writer` without building it in memory
+    ///
+    /// The output is byte-identical to `format_by_projects`: objects there come from
+    /// `serde_json::json!`, which orders keys alphabetically, so keys are written in
+    /// that same order here.
+    pub fn write_by_projects<W: Write>(
+        &self,
+        writer: W,
+        projects: &[ProjectSymbols],
+    ) -> std::io::Result<()> {
+        let mut serializer = serde_json::Serializer::pretty(writer);
+        let mut root = serializer.serialize_map(Some(1))?;
+        root.serialize_entry("projects", &StreamedProjects(projects))?;
+        root.end()?;
+        Ok(())
+    }
+}
+
+struct StreamedProjects<'a>(&'a [ProjectSymbols]);
+
+impl Serialize for StreamedProjects<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(StreamedProject))
+    }
+}
+
+struct StreamedProject<'a>(&'a ProjectSymbols);
+
+impl Serialize for StreamedProject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (project_name, project_type, files) = self.0;
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("files", &StreamedFiles(files))?;
+        map.serialize_entry("name", project_name)?;
+        map.serialize_entry("type", &format!("{:?}", project_type))?;
+        map.end()
+    }
+}
+
+struct StreamedFiles<'a>(&'a [FileSymbols]);
+
+impl Serialize for StreamedFiles<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(StreamedFile))
+    }
+}
+
+struct StreamedFile<'a>(&'a FileSymbols);
+
+impl Serialize for StreamedFile<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (file_path, symbols) = self.0;
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("file", file_path)?;
+        map.serialize_entry("symbols", &StreamedSymbols(symbols))?;
+        map.end()
+    }
+}
+
+struct StreamedSymbols<'a>(&'a [SymbolInfo]);
+
+impl Serialize for StreamedSymbols<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(JsonSymbol::from))
+    }
+}
+
 impl Formatter for JsonFormatter {
     fn format(&self, symbols: &[SymbolInfo], file_path: &str) -> String {
         let json_symbols: Vec<JsonSymbol> = symbols.iter().map(JsonSymbol::from).collect();
@@ -778,8 +1067,7 @@ impl Formatter for JsonFormatter {
 
             for file in &project.files {
                 let types_json: Vec<_> = file
-                    .types
-                    .iter()
+                    .types()
                     .map(|t| {
                         let (resolution_type, resolution_data) = match &t.resolution {
                             TypeResolution::Local {
@@ -849,6 +1137,36 @@ impl Formatter for JsonFormatter {
         serde_json::to_string_pretty(&output)
             .unwrap_or_else(|e| format!("{{\"error\": \"Failed to serialize: {}\"}}", e))
     }
+
+    fn format_unresolved_types(&self, names: &[String]) -> String {
+        let output = serde_json::json!({ "unresolved_types": names });
+        serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    fn format_callers(&self, target: &str, callers: &[CallerRow]) -> String {
+        let output = serde_json::json!({
+            "target": target,
+            "callers": callers,
+        });
+        serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    fn format_symbols_with_diagnostics(
+        &self,
+        projects: &[ProjectSymbols],
+        diagnostics: &[ProjectDiagnostics],
+    ) -> String {
+        // Two documents side by side would not be valid JSON, so nest them
+        let parse = |json: String| {
+            serde_json::from_str::<serde_json::Value>(&json).unwrap_or(serde_json::Value::Null)
+        };
+        let output = serde_json::json!({
+            "symbols": parse(self.format_by_projects(projects)),
+            "diagnostics": parse(self.format_diagnostics(diagnostics)),
+        });
+        serde_json::to_string_pretty(&output)
+            .unwrap_or_else(|e| format!("{{\"error\": \"Failed to serialize: {}\"}}", e))
+    }
 }
 
 impl Formatter for CsvFormatter {
@@ -965,7 +1283,7 @@ impl Formatter for CsvFormatter {
         // CSV rows
         for project in projects {
             for file in &project.files {
-                for typ in &file.types {
+                for typ in file.types() {
                     let context = match typ.context {
                         crate::analyze::type_extractor::TypeContext::FunctionParameter => {
                             "FunctionParameter"
@@ -1011,6 +1329,27 @@ impl Formatter for CsvFormatter {
 
         output
     }
+
+    fn format_unresolved_types(&self, names: &[String]) -> String {
+        let mut output = String::from("type_name\n");
+        for name in names {
+            output.push_str(&format!("{}\n", csv_escape(name)));
+        }
+        output
+    }
+
+    fn format_callers(&self, _target: &str, callers: &[CallerRow]) -> String {
+        let mut output = String::from("caller,file,line\n");
+        for caller in callers {
+            output.push_str(&format!(
+                "{},{},{}\n",
+                csv_escape(&caller.name),
+                csv_escape(&caller.file),
+                caller.line
+            ));
+        }
+        output
+    }
 }
 
 fn format_symbol_csv(symbol: &SymbolInfo, file_path: &str) -> String {
@@ -1078,9 +1417,13 @@ impl Formatter for CompactFormatter {
         output.push_str(&format!("{} ({} lines)\n", file_path, max_line));
 
         // Format symbols as tree
+        let project_type = Utf8Path::new(file_path)
+            .extension()
+            .and_then(extension_to_project_type)
+            .unwrap_or(ProjectType::Unknown);
         for (idx, symbol) in symbols.iter().enumerate() {
             let is_last = idx == symbols.len() - 1;
-            format_symbol_tree(&mut output, symbol, "", is_last);
+            format_symbol_tree(&mut output, symbol, "", is_last, project_type);
         }
 
         output
@@ -1166,7 +1509,7 @@ impl Formatter for CompactFormatter {
             for file in &project.files {
                 output.push_str(&format!("{}\n", file.file_path));
 
-                for typ in &file.types {
+                for typ in file.types() {
                     let location = match &typ.resolution {
                         TypeResolution::Local {
                             file_path, line, ..
@@ -1191,24 +1534,48 @@ impl Formatter for CompactFormatter {
 
         output
     }
+
+    fn format_unresolved_types(&self, names: &[String]) -> String {
+        names.iter().map(|name| format!("{}\n", name)).collect()
+    }
+
+    fn format_callers(&self, target: &str, callers: &[CallerRow]) -> String {
+        let mut output = format!("# Callers of {}\n\n", target);
+        for caller in callers {
+            output.push_str(&format!(
+                "{}:{} {}\n",
+                caller.file, caller.line, caller.name
+            ));
+        }
+        output
+    }
+}
+
+/// Visibility prefix for the compact tree
+///
+/// Only symbols that [`symbol_visibility`] classifies as public get `pub `;
+/// restricted (`pub(crate)`), private and ambiguous symbols get no prefix.
+fn compact_visibility(symbol: &SymbolInfo, project_type: ProjectType) -> &'static str {
+    match symbol_visibility(symbol, project_type) {
+        Visibility::Public => "pub ",
+        Visibility::Private | Visibility::Unknown => "",
+    }
 }
 
 /// Format a symbol and its children in tree format
-fn format_symbol_tree(output: &mut String, symbol: &SymbolInfo, prefix: &str, is_last: bool) {
+fn format_symbol_tree(
+    output: &mut String,
+    symbol: &SymbolInfo,
+    prefix: &str,
+    is_last: bool,
+    project_type: ProjectType,
+) {
     // Tree characters
     let branch = if is_last { "└─ " } else { "├─ " };
     let extension = if is_last { "   " } else { "│  " };
 
     // Format the symbol line: ├─ visibility name signature :line
-    let visibility = match symbol.kind {
-        SymbolKind::MODULE
-        | SymbolKind::FUNCTION
-        | SymbolKind::STRUCT
-        | SymbolKind::ENUM
-        | SymbolKind::INTERFACE
-        | SymbolKind::CLASS => "pub ",
-        _ => "",
-    };
+    let visibility = compact_visibility(symbol, project_type);
 
     let kind_prefix = match symbol.kind {
         SymbolKind::MODULE => "mod ",
@@ -1227,6 +1594,7 @@ fn format_symbol_tree(output: &mut String, symbol: &SymbolInfo, prefix: &str, is
         // Extract just the signature part (remove leading keywords that we'll add)
         let clean_detail = detail
             .trim_start_matches("pub ")
+            .trim_start_matches("export ")
             .trim_start_matches("fn ")
             .trim_start_matches("struct ")
             .trim_start_matches("enum ")
@@ -1260,7 +1628,7 @@ fn format_symbol_tree(output: &mut String, symbol: &SymbolInfo, prefix: &str, is
 
         for (idx, child) in symbol.children.iter().enumerate() {
             let is_last_child = idx == symbol.children.len() - 1;
-            format_symbol_tree(output, child, &child_prefix, is_last_child);
+            format_symbol_tree(output, child, &child_prefix, is_last_child, project_type);
         }
     }
 }
@@ -1318,14 +1686,45 @@ impl Formatter for SymbolListFormatter {
         String::from("# Diagnostics output not supported in symbol-list format\n")
     }
 
+    fn format_symbols_with_diagnostics(
+        &self,
+        projects: &[ProjectSymbols],
+        _diagnostics: &[ProjectDiagnostics],
+    ) -> String {
+        self.format_by_projects(projects)
+    }
+
     fn format_type_dependencies(&self, _projects: &[ProjectTypeDependencies]) -> String {
         String::from("# Type dependencies output not supported in symbol-list format\n")
     }
+
+    fn format_unresolved_types(&self, names: &[String]) -> String {
+        names.iter().map(|name| format!("{}\n", name)).collect()
+    }
+
+    fn format_callers(&self, _target: &str, callers: &[CallerRow]) -> String {
+        let names: BTreeSet<&str> = callers.iter().map(|caller| caller.name.as_str()).collect();
+        names
+            .into_iter()
+            .map(|name| format!("{}\n", name))
+            .collect()
+    }
 }
 
 pub fn get_formatter(format: OutputFormat) -> Box<dyn Formatter> {
+    get_formatter_with_options(format, &FormatterOptions::default())
+}
+
+pub fn get_formatter_with_options(
+    format: OutputFormat,
+    options: &FormatterOptions,
+) -> Box<dyn Formatter> {
     match format {
-        OutputFormat::Markdown => Box::new(MarkdownFormatter),
+        OutputFormat::Markdown => Box::new(
+            MarkdownFormatter::new()
+                .with_mermaid(options.mermaid)
+                .with_unresolved_only(options.unresolved_only),
+        ),
         OutputFormat::Json => Box::new(JsonFormatter),
         OutputFormat::Csv => Box::new(CsvFormatter),
         OutputFormat::Compact => Box::new(CompactFormatter),
@@ -1333,6 +1732,116 @@ pub fn get_formatter(format: OutputFormat) -> Box<dyn Formatter> {
     }
 }
 
+/// Unresolved type names across all projects, sorted and deduplicated
+pub fn unresolved_type_names(projects: &[ProjectTypeDependencies]) -> Vec<String> {
+    projects
+        .iter()
+        .flat_map(|p| &p.files)
+        .flat_map(|f| f.types())
+        .filter(|t| matches!(t.resolution, TypeResolution::Unresolved))
+        .map(|t| t.type_name.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Type references gathered from each file's symbols (and their children)
+///
+/// References stay with the symbol that made them; members of a type report
+/// under that type. Files without any resolved type references, such as the
+/// external dependency listings, are left out of the report.
+pub fn type_dependencies_by_projects(projects: &[ProjectSymbols]) -> Vec<ProjectTypeDependencies> {
+    fn collect(
+        symbols: &[SymbolInfo],
+        owner: Option<usize>,
+        out: &mut Vec<SymbolTypeDependencies>,
+    ) {
+        for symbol in symbols {
+            let deps = symbol.type_dependencies.iter().flatten().cloned();
+            let owner = match owner {
+                Some(index) if !is_type_symbol(symbol.kind) => {
+                    out[index].types.extend(deps);
+                    Some(index)
+                }
+                _ => {
+                    out.push(SymbolTypeDependencies {
+                        symbol_name: symbol.name.clone(),
+                        symbol_kind: symbol.kind,
+                        types: deps.collect(),
+                    });
+                    is_type_symbol(symbol.kind).then_some(out.len() - 1)
+                }
+            };
+            collect(&symbol.children, owner, out);
+        }
+    }
+
+    projects
+        .iter()
+        .map(|(name, project_type, files)| ProjectTypeDependencies {
+            project_name: name.clone(),
+            project_type: *project_type,
+            files: files
+                .iter()
+                .filter_map(|(path, symbols)| {
+                    let mut owners = Vec::new();
+                    collect(symbols, None, &mut owners);
+                    owners.retain(|s| !s.types.is_empty());
+                    (!owners.is_empty()).then(|| FileTypeDependencies {
+                        file_path: RelativePath::from_string(path.clone()),
+                        symbols: owners,
+                    })
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Prefix of the pseudo-file listing a project's fetched external type definitions
+pub const EXTERNAL_DEPENDENCIES_PREFIX: &str = "_external_dependencies_";
+
+/// Sort projects by name and each project's files by relative path, so
+/// reports built from hash-ordered groups are stable between runs
+///
+/// The external dependencies pseudo-file always comes last. The sort is
+/// stable, so projects sharing a name keep the order they were processed in
+/// (by root path).
+pub fn sort_project_symbols(projects: &mut [ProjectSymbols]) {
+    projects.sort_by(|a, b| a.0.cmp(&b.0));
+    for (_, _, files) in projects.iter_mut() {
+        files.sort_by(|a, b| {
+            let key = |path: &str| {
+                (
+                    path.starts_with(EXTERNAL_DEPENDENCIES_PREFIX),
+                    path.to_owned(),
+                )
+            };
+            key(&a.0).cmp(&key(&b.0))
+        });
+    }
+}
+
+/// Diagnostics counterpart of [`sort_project_symbols`]
+pub fn sort_project_diagnostics(projects: &mut [ProjectDiagnostics]) {
+    projects.sort_by(|a, b| a.project_name.cmp(&b.project_name));
+    for project in projects.iter_mut() {
+        project
+            .files
+            .sort_by(|a, b| a.file_path.as_path_buf().cmp(b.file_path.as_path_buf()));
+    }
+}
+
+/// Keep only error diagnostics, dropping files left without any (`--only-errors`)
+pub fn retain_errors(projects: &mut [ProjectDiagnostics]) {
+    for project in projects.iter_mut() {
+        for file in project.files.iter_mut() {
+            file.diagnostics
+                .retain(|d| d.severity == Some(lsp_types::DiagnosticSeverity::ERROR));
+        }
+        project.files.retain(|file| !file.diagnostics.is_empty());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1358,7 +1867,7 @@ mod tests {
             create_test_symbol("Bar", SymbolKind::STRUCT),
         ];
 
-        let formatter = MarkdownFormatter;
+        let formatter = MarkdownFormatter::new();
         let output = formatter.format(&symbols, "src/test.rs");
 
         assert!(output.contains("Code Analysis"));
@@ -1420,4 +1929,444 @@ mod tests {
         assert!(output.contains("\"fn test(a: String, b: i32)\""));
         assert!(output.contains("\"A \"\"quoted\"\" description\""));
     }
+
+    #[test]
+    fn test_markdown_type_dependencies_mermaid() {
+        use crate::analyze::path_types::FilePath;
+        use crate::analyze::type_extractor::TypeContext;
+        use std::path::PathBuf;
+
+        let resolved = |name: &str, resolution: TypeResolution| ResolvedType {
+            type_name: name.to_string(),
+            context: TypeContext::StructField,
+            resolution,
+        };
+        let local = |path: &str| TypeResolution::Local {
+            file_path: FilePath::from_absolute_unchecked(PathBuf::from(path)),
+            line: 1,
+            kind: "Struct".to_string(),
+        };
+
+        let owner = |name: &str, types| SymbolTypeDependencies {
+            symbol_name: name.to_string(),
+            symbol_kind: SymbolKind::STRUCT,
+            types,
+        };
+
+        // A uses X and B uses Y: sharing a file must not link A to Y or B to X
+        let projects = vec![ProjectTypeDependencies {
+            project_name: "demo".to_string(),
+            project_type: ProjectType::Rust,
+            files: vec![
+                FileTypeDependencies {
+                    file_path: RelativePath::from_string("src/lib.rs".to_string()),
+                    symbols: vec![owner(
+                        "Config",
+                        vec![
+                            resolved("Cache<String>", local("/demo/src/cache.rs")),
+                            resolved(
+                                "std::path::PathBuf",
+                                TypeResolution::External {
+                                    file_path: None,
+                                    line: None,
+                                },
+                            ),
+                        ],
+                    )],
+                },
+                FileTypeDependencies {
+                    file_path: RelativePath::from_string("src/pair.rs".to_string()),
+                    symbols: vec![
+                        owner("A", vec![resolved("X", local("/demo/src/x.rs"))]),
+                        owner("B", vec![resolved("Y", local("/demo/src/y.rs"))]),
+                    ],
+                },
+            ],
+        }];
+
+        let plain = MarkdownFormatter::new().format_type_dependencies(&projects);
+        assert!(!plain.contains("```mermaid"));
+
+        let output = MarkdownFormatter::new()
+            .with_mermaid(true)
+            .format_type_dependencies(&projects);
+        assert!(output.contains("```mermaid\nclassDiagram\n"));
+        assert!(output.contains("    class Config\n"));
+        assert!(output.contains("    class Cache\n"));
+        assert!(output.contains("    Config ..> Cache\n"));
+        assert!(output.contains("    Config ..> PathBuf\n"));
+
+        assert!(output.contains("    A ..> X\n"));
+        assert!(output.contains("    B ..> Y\n"));
+        for cross in ["A ..> Y", "B ..> X", "A ..> B", "B ..> A"] {
+            assert!(
+                !output.contains(cross),
+                "unexpected edge {cross}:\n{output}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_type_dependencies_by_projects_collects_nested_symbols() {
+        use crate::analyze::type_extractor::TypeContext;
+
+        let resolved = |name: &str| ResolvedType {
+            type_name: name.to_string(),
+            context: TypeContext::FunctionParameter,
+            resolution: TypeResolution::Unresolved,
+        };
+        let mut method = create_test_symbol("run", SymbolKind::METHOD);
+        method.type_dependencies = Some(vec![resolved("Job")]);
+        let mut parent = create_test_symbol("Runner", SymbolKind::STRUCT);
+        parent.type_dependencies = Some(vec![resolved("Queue")]);
+        parent.children = vec![method];
+
+        let projects = vec![(
+            "demo".to_string(),
+            ProjectType::Rust,
+            vec![
+                ("src/lib.rs".to_string(), vec![parent]),
+                (
+                    "_external_dependencies_demo".to_string(),
+                    vec![create_test_symbol("Queue", SymbolKind::STRUCT)],
+                ),
+            ],
+        )];
+
+        let report = type_dependencies_by_projects(&projects);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].files.len(), 1);
+        let owners = &report[0].files[0].symbols;
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[0].symbol_name, "Runner");
+        let names: Vec<_> = owners[0]
+            .types
+            .iter()
+            .map(|t| t.type_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Queue", "Job"]);
+    }
+
+    #[test]
+    fn test_unresolved_only_lists_each_type_once() {
+        use crate::analyze::type_extractor::TypeContext;
+
+        let unresolved = |name: &str| ResolvedType {
+            type_name: name.to_string(),
+            context: TypeContext::FunctionParameter,
+            resolution: TypeResolution::Unresolved,
+        };
+        let file = |path: &str, types| FileTypeDependencies {
+            file_path: RelativePath::from_string(path.to_string()),
+            symbols: vec![SymbolTypeDependencies {
+                symbol_name: "main".to_string(),
+                symbol_kind: SymbolKind::FUNCTION,
+                types,
+            }],
+        };
+
+        let projects = vec![
+            ProjectTypeDependencies {
+                project_name: "api".to_string(),
+                project_type: ProjectType::Rust,
+                files: vec![
+                    file("src/a.rs", vec![unresolved("Widget"), unresolved("Gadget")]),
+                    file("src/b.rs", vec![unresolved("Widget")]),
+                ],
+            },
+            ProjectTypeDependencies {
+                project_name: "web".to_string(),
+                project_type: ProjectType::TypeScript,
+                files: vec![file("src/c.ts", vec![unresolved("Gadget")])],
+            },
+        ];
+
+        assert_eq!(unresolved_type_names(&projects), vec!["Gadget", "Widget"]);
+
+        let output = MarkdownFormatter::new()
+            .with_unresolved_only(true)
+            .format_type_dependencies(&projects);
+        assert_eq!(output, "# Unresolved Types\n\n- `Gadget`\n- `Widget`\n");
+    }
+
+    #[test]
+    fn test_unresolved_types_in_every_format() {
+        let names = vec!["Gadget".to_string(), "Widget".to_string()];
+
+        assert_eq!(
+            CompactFormatter.format_unresolved_types(&names),
+            "Gadget\nWidget\n"
+        );
+        assert_eq!(
+            SymbolListFormatter.format_unresolved_types(&names),
+            "Gadget\nWidget\n"
+        );
+        assert_eq!(
+            CsvFormatter.format_unresolved_types(&names),
+            "type_name\nGadget\nWidget\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&JsonFormatter.format_unresolved_types(&names)).unwrap();
+        assert_eq!(
+            json["unresolved_types"],
+            serde_json::json!(["Gadget", "Widget"])
+        );
+    }
+
+    #[test]
+    fn test_compact_formatter_visibility_from_detail() {
+        let mut private_fn = create_test_symbol("foo", SymbolKind::FUNCTION);
+        private_fn.detail = Some("fn foo()".to_string());
+        let mut public_fn = create_test_symbol("bar", SymbolKind::FUNCTION);
+        public_fn.detail = Some("pub fn bar()".to_string());
+
+        let output = CompactFormatter.format(&[private_fn, public_fn], "src/lib.rs");
+
+        assert!(output.contains("├─ fn foo() :1"));
+        assert!(output.contains("└─ pub fn bar() :1"));
+    }
+
+    #[test]
+    fn test_compact_visibility_agrees_with_public_filter() {
+        let mut restricted = create_test_symbol("baz", SymbolKind::FUNCTION);
+        restricted.detail = Some("pub(crate) fn baz()".to_string());
+
+        let output = CompactFormatter.format(std::slice::from_ref(&restricted), "src/lib.rs");
+        assert!(output.contains("└─ fn baz :1"));
+        assert!(
+            crate::analyze::retain_public_symbols(vec![restricted], ProjectType::Rust).is_empty()
+        );
+
+        let exported = create_test_symbol("Serve", SymbolKind::FUNCTION);
+        let unexported = create_test_symbol("serve", SymbolKind::FUNCTION);
+        let output = CompactFormatter.format(&[exported, unexported], "main.go");
+        assert!(output.contains("├─ pub fn Serve() :1"));
+        assert!(output.contains("└─ fn serve() :1"));
+    }
+
+    #[test]
+    fn test_json_streaming_matches_in_memory_output() {
+        let mut parent = create_test_symbol("Parser", SymbolKind::STRUCT);
+        parent.children = vec![create_test_symbol("parse", SymbolKind::METHOD)];
+        let projects = vec![
+            (
+                "core".to_string(),
+                ProjectType::Rust,
+                vec![
+                    ("src/lib.rs".to_string(), vec![parent]),
+                    ("src/empty.rs".to_string(), vec![]),
+                ],
+            ),
+            ("web".to_string(), ProjectType::TypeScript, vec![]),
+        ];
+
+        let mut streamed = Vec::new();
+        JsonFormatter
+            .write_by_projects(&mut streamed, &projects)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(streamed).unwrap(),
+            JsonFormatter.format_by_projects(&projects)
+        );
+    }
+
+    #[test]
+    fn test_sorted_projects_format_identically_regardless_of_input_order() {
+        let file = |path: &str| {
+            (
+                path.to_string(),
+                vec![create_test_symbol("f", SymbolKind::FUNCTION)],
+            )
+        };
+        let project =
+            |name: &str, files: Vec<FileSymbols>| (name.to_string(), ProjectType::Rust, files);
+
+        let mut first = vec![
+            project("zeta", vec![file("src/b.rs"), file("src/a.rs")]),
+            project("alpha", vec![file("lib.rs")]),
+        ];
+        let mut second = vec![
+            project("alpha", vec![file("lib.rs")]),
+            project("zeta", vec![file("src/a.rs"), file("src/b.rs")]),
+        ];
+        sort_project_symbols(&mut first);
+        sort_project_symbols(&mut second);
+
+        let formatter = MarkdownFormatter::new();
+        let output = formatter.format_by_projects(&first);
+        assert_eq!(output, formatter.format_by_projects(&second));
+        assert!(output.find("alpha").unwrap() < output.find("zeta").unwrap());
+        assert!(output.find("src/a.rs").unwrap() < output.find("src/b.rs").unwrap());
+
+        // External definitions stay after the project's own files; same-name projects keep their order
+        let mut pinned = vec![
+            project(
+                "app",
+                vec![file("_external_dependencies_app"), file("src/main.rs")],
+            ),
+            project("app", vec![file("other.rs")]),
+        ];
+        sort_project_symbols(&mut pinned);
+        let paths: Vec<_> = pinned[0].2.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["src/main.rs", "_external_dependencies_app"]);
+        assert_eq!(pinned[1].2[0].0, "other.rs");
+
+        let diagnostics = |name: &str, paths: &[&str]| ProjectDiagnostics {
+            project_name: name.to_string(),
+            project_type: ProjectType::Rust,
+            files: paths
+                .iter()
+                .map(|path| FileDiagnostics {
+                    file_path: RelativePath::from_string(path.to_string()),
+                    diagnostics: vec![],
+                })
+                .collect(),
+        };
+        let mut first = vec![
+            diagnostics("zeta", &["b.rs", "a.rs"]),
+            diagnostics("alpha", &["c.rs"]),
+        ];
+        let mut second = vec![
+            diagnostics("alpha", &["c.rs"]),
+            diagnostics("zeta", &["a.rs", "b.rs"]),
+        ];
+        sort_project_diagnostics(&mut first);
+        sort_project_diagnostics(&mut second);
+        assert_eq!(
+            formatter.format_diagnostics(&first),
+            formatter.format_diagnostics(&second)
+        );
+    }
+
+    #[test]
+    fn test_symbols_with_diagnostics_has_both_sections() {
+        let symbols = vec![(
+            "demo".to_string(),
+            ProjectType::Rust,
+            vec![(
+                "src/lib.rs".to_string(),
+                vec![create_test_symbol("run", SymbolKind::FUNCTION)],
+            )],
+        )];
+        let diagnostics = vec![ProjectDiagnostics {
+            project_name: "demo".to_string(),
+            project_type: ProjectType::Rust,
+            files: vec![FileDiagnostics {
+                file_path: RelativePath::from_string("src/lib.rs".to_string()),
+                diagnostics: vec![lsp_types::Diagnostic {
+                    severity: Some(lsp_types::DiagnosticSeverity::WARNING),
+                    message: "unused variable".to_string(),
+                    ..Default::default()
+                }],
+            }],
+        }];
+
+        let markdown =
+            MarkdownFormatter::new().format_symbols_with_diagnostics(&symbols, &diagnostics);
+        let code = markdown.find("# Code Analysis").unwrap();
+        let report = markdown.find("# Diagnostics Report").unwrap();
+        assert!(code < report);
+        assert!(markdown[..report].contains("run"));
+        assert!(markdown[report..].contains("### File: `src/lib.rs`"));
+        assert!(markdown[report..].contains("unused variable"));
+
+        let json: serde_json::Value = serde_json::from_str(
+            &JsonFormatter.format_symbols_with_diagnostics(&symbols, &diagnostics),
+        )
+        .unwrap();
+        assert_eq!(
+            json["symbols"]["projects"][0]["files"][0]["file"],
+            "src/lib.rs"
+        );
+        assert_eq!(
+            json["diagnostics"]["projects"][0]["files"][0]["diagnostics"][0]["message"],
+            "unused variable"
+        );
+    }
+
+    #[test]
+    fn test_retain_errors_drops_other_severities() {
+        let diagnostic = |severity, message: &str| lsp_types::Diagnostic {
+            severity: Some(severity),
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let mut projects = vec![ProjectDiagnostics {
+            project_name: "demo".to_string(),
+            project_type: ProjectType::Rust,
+            files: vec![
+                FileDiagnostics {
+                    file_path: RelativePath::from_string("src/lib.rs".to_string()),
+                    diagnostics: vec![
+                        diagnostic(lsp_types::DiagnosticSeverity::ERROR, "mismatched types"),
+                        diagnostic(lsp_types::DiagnosticSeverity::WARNING, "unused variable"),
+                        diagnostic(lsp_types::DiagnosticSeverity::HINT, "consider this"),
+                    ],
+                },
+                FileDiagnostics {
+                    file_path: RelativePath::from_string("src/util.rs".to_string()),
+                    diagnostics: vec![diagnostic(
+                        lsp_types::DiagnosticSeverity::WARNING,
+                        "dead code",
+                    )],
+                },
+            ],
+        }];
+
+        retain_errors(&mut projects);
+        let output = MarkdownFormatter::new().format_diagnostics(&projects);
+
+        assert!(output.contains("#### Errors"));
+        assert!(output.contains("mismatched types"));
+        assert!(!output.contains("#### Warnings"));
+        assert!(!output.contains("#### Hints"));
+        assert!(!output.contains("src/util.rs"));
+    }
+
+    #[test]
+    fn test_diagnostic_counts_summary_line() {
+        let diagnostic = |severity| lsp_types::Diagnostic {
+            severity,
+            message: "msg".to_string(),
+            ..Default::default()
+        };
+        let file = |path: &str, diagnostics| FileDiagnostics {
+            file_path: RelativePath::from_string(path.to_string()),
+            diagnostics,
+        };
+        let projects = vec![
+            ProjectDiagnostics {
+                project_name: "a".to_string(),
+                project_type: ProjectType::Rust,
+                files: vec![
+                    file(
+                        "src/lib.rs",
+                        vec![
+                            diagnostic(Some(lsp_types::DiagnosticSeverity::ERROR)),
+                            diagnostic(Some(lsp_types::DiagnosticSeverity::WARNING)),
+                            diagnostic(Some(lsp_types::DiagnosticSeverity::WARNING)),
+                        ],
+                    ),
+                    file("src/main.rs", vec![diagnostic(None)]),
+                ],
+            },
+            ProjectDiagnostics {
+                project_name: "b".to_string(),
+                project_type: ProjectType::Python,
+                files: vec![file(
+                    "app.py",
+                    vec![
+                        diagnostic(Some(lsp_types::DiagnosticSeverity::ERROR)),
+                        diagnostic(Some(lsp_types::DiagnosticSeverity::INFORMATION)),
+                    ],
+                )],
+            },
+        ];
+
+        assert_eq!(
+            DiagnosticCounts::from_projects(&projects).to_string(),
+            "DIAGNOSTICS errors=2 warnings=2 info=1 hints=0"
+        );
+    }
 }
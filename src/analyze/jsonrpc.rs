@@ -3,10 +3,14 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::process::{ChildStdin, ChildStdout};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long `Drop` waits for the reader thread to observe EOF before detaching it
+const READER_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Serialize)]
 pub struct JsonRpcRequest {
@@ -77,7 +81,8 @@ pub enum ProgressState {
 
 /// JSON-RPC 2.0 transport over stdio with background reader thread
 pub struct JsonRpcTransport {
-    stdin: Arc<Mutex<ChildStdin>>,
+    // Shared with the reader thread; taken on drop so the child sees EOF on its stdin
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
     request_id: AtomicU64,
     diagnostics: Arc<Mutex<HashMap<String, Vec<lsp_types::Diagnostic>>>>,
     progress: Arc<Mutex<HashMap<String, ProgressState>>>,
@@ -87,11 +92,14 @@ pub struct JsonRpcTransport {
     pending_receivers: Arc<Mutex<HashMap<u64, Receiver<JsonRpcResponse>>>>,
     // Background reader thread handle
     reader_thread: Option<JoinHandle<()>>,
+    // Set on drop; the reader thread exits at the next message boundary
+    shutdown: Arc<AtomicBool>,
 }
 
 impl JsonRpcTransport {
     pub fn new(stdin: ChildStdin, stdout: ChildStdout) -> Self {
-        let stdin = Arc::new(Mutex::new(stdin));
+        let stdin = Arc::new(Mutex::new(Some(stdin)));
+        let shutdown = Arc::new(AtomicBool::new(false));
         let diagnostics = Arc::new(Mutex::new(HashMap::new()));
         let progress = Arc::new(Mutex::new(HashMap::new()));
         let pending_responses = Arc::new(Mutex::new(HashMap::new()));
@@ -102,6 +110,7 @@ impl JsonRpcTransport {
         let diagnostics_clone = Arc::clone(&diagnostics);
         let progress_clone = Arc::clone(&progress);
         let pending_responses_clone = Arc::clone(&pending_responses);
+        let shutdown_clone = Arc::clone(&shutdown);
 
         // Spawn background reader thread
         let reader_thread = thread::spawn(move || {
@@ -111,6 +120,7 @@ impl JsonRpcTransport {
                 diagnostics_clone,
                 progress_clone,
                 pending_responses_clone,
+                shutdown_clone,
             );
         });
 
@@ -122,18 +132,25 @@ impl JsonRpcTransport {
             pending_responses,
             pending_receivers,
             reader_thread: Some(reader_thread),
+            shutdown,
         }
     }
 
     /// Background reader thread that continuously processes messages from stdout
     fn reader_thread_main(
         mut stdout: BufReader<ChildStdout>,
-        stdin: Arc<Mutex<ChildStdin>>,
+        stdin: Arc<Mutex<Option<ChildStdin>>>,
         diagnostics: Arc<Mutex<HashMap<String, Vec<lsp_types::Diagnostic>>>>,
         progress: Arc<Mutex<HashMap<String, ProgressState>>>,
         pending_responses: Arc<Mutex<HashMap<u64, Sender<JsonRpcResponse>>>>,
+        shutdown: Arc<AtomicBool>,
     ) {
         loop {
+            if shutdown.load(Ordering::SeqCst) {
+                tracing::debug!("Reader thread: shutdown requested, exiting");
+                break;
+            }
+
             // Read message headers
             let headers = match Self::read_headers_static(&mut stdout) {
                 Ok(h) => h,
@@ -191,6 +208,8 @@ impl JsonRpcTransport {
             }
         }
 
+        // Release our end of the pipe right away rather than when the closure unwinds
+        drop(stdout);
         tracing::debug!("Reader thread exiting");
     }
 
@@ -203,7 +222,12 @@ impl JsonRpcTransport {
 
         loop {
             line.clear();
-            reader.read_line(&mut line)?;
+            if reader.read_line(&mut line)? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "stdout closed while reading headers",
+                ));
+            }
 
             if line == "\r\n" || line == "\n" {
                 break;
@@ -221,7 +245,7 @@ impl JsonRpcTransport {
     fn handle_notification_or_request(
         json: serde_json::Value,
         method: String,
-        stdin: &Arc<Mutex<ChildStdin>>,
+        stdin: &Arc<Mutex<Option<ChildStdin>>>,
         diagnostics: &Arc<Mutex<HashMap<String, Vec<lsp_types::Diagnostic>>>>,
         progress: &Arc<Mutex<HashMap<String, ProgressState>>>,
     ) {
@@ -250,8 +274,10 @@ impl JsonRpcTransport {
                         "id": id,
                         "result": null
                     });
-                    if let Ok(mut stdin_lock) = stdin.lock() {
-                        let _ = Self::write_message_static(&mut stdin_lock, &response);
+                    if let Ok(mut stdin_lock) = stdin.lock()
+                        && let Some(writer) = stdin_lock.as_mut()
+                    {
+                        let _ = Self::write_message_static(writer, &response);
                     }
                 } else {
                     tracing::debug!("Reader thread: notification '{}' (ignoring)", method);
@@ -504,7 +530,13 @@ impl JsonRpcTransport {
 
         let content = format!("Content-Length: {}\r\n\r\n{}", json.len(), json);
 
-        let mut stdin = self.stdin.lock().unwrap();
+        let mut stdin_lock = self.stdin.lock().unwrap();
+        let stdin = stdin_lock.as_mut().ok_or_else(|| {
+            QuickctxError::Io(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "JSON-RPC transport is closed",
+            ))
+        })?;
         stdin
             .write_all(content.as_bytes())
             .map_err(QuickctxError::Io)?;
@@ -542,18 +574,24 @@ impl JsonRpcTransport {
 
 impl Drop for JsonRpcTransport {
     fn drop(&mut self) {
-        // The reader thread should exit when stdout reaches EOF
-        // (which happens when the child process terminates)
+        // Ask the reader to stop at the next message boundary, then close our end
+        // of stdin. A well-behaved server exits on stdin EOF, which closes its
+        // stdout and unblocks the reader's pending read.
+        self.shutdown.store(true, Ordering::SeqCst);
+        if let Ok(mut stdin) = self.stdin.lock() {
+            stdin.take();
+        }
+
         if let Some(handle) = self.reader_thread.take() {
-            tracing::debug!("Waiting for reader thread to exit (timeout: 500ms)");
+            tracing::debug!(
+                "Waiting for reader thread to exit (timeout: {:?})",
+                READER_JOIN_TIMEOUT
+            );
 
-            // Give the thread 500ms to exit gracefully
-            // Since we kill the child process explicitly, this should be plenty
-            let timeout = std::time::Duration::from_millis(500);
             let start = std::time::Instant::now();
 
             // Poll the thread with a short sleep
-            while start.elapsed() < timeout {
+            while start.elapsed() < READER_JOIN_TIMEOUT {
                 if handle.is_finished() {
                     if let Err(e) = handle.join() {
                         tracing::warn!("Reader thread panicked: {:?}", e);
@@ -562,7 +600,7 @@ impl Drop for JsonRpcTransport {
                     }
                     return;
                 }
-                std::thread::sleep(std::time::Duration::from_millis(10));
+                std::thread::sleep(Duration::from_millis(10));
             }
 
             tracing::warn!("Reader thread did not exit within timeout, detaching");
@@ -1,11 +1,14 @@
+use crate::analyze::callers::{call_hierarchy_unsupported, parse_incoming_calls};
 use crate::analyze::jsonrpc::JsonRpcTransport;
 use crate::analyze::lsp_config::get_language_id;
 use crate::analyze::project_root::ProjectType;
 use crate::analyze::uri_utils::uri_from_file_path;
 use crate::error::{QuickctxError, Result};
 use lsp_types::*;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::time::Instant;
 
 pub struct LspClient {
     transport: JsonRpcTransport,
@@ -13,6 +16,10 @@ pub struct LspClient {
     root_uri: Uri,
     project_type: ProjectType,
     initialized: bool,
+    /// Once passed, symbol and hover requests fail instead of being sent
+    deadline: Option<Instant>,
+    /// Files sent with `didOpen`
+    opened: HashSet<PathBuf>,
 }
 
 impl LspClient {
@@ -34,38 +41,31 @@ impl LspClient {
         project_type: ProjectType,
         bin_paths: &[String],
     ) -> Result<Self> {
-        tracing::info!("Spawning LSP server: {} {:?}", server_cmd, args);
-
-        let mut command = Command::new(server_cmd);
-        command
-            .args(args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        // Extend PATH if bin_paths are provided
-        if !bin_paths.is_empty() {
-            let current_path = std::env::var("PATH").unwrap_or_default();
-            let expanded_paths: Vec<String> = bin_paths
-                .iter()
-                .map(|p| shellexpand::tilde(p).to_string())
-                .collect();
-
-            let new_path = if current_path.is_empty() {
-                expanded_paths.join(":")
-            } else {
-                format!("{}:{}", expanded_paths.join(":"), current_path)
-            };
+        Self::new_with_env(
+            server_cmd,
+            args,
+            root_path,
+            project_type,
+            bin_paths,
+            &HashMap::new(),
+        )
+    }
 
-            tracing::debug!("Extended PATH with: {:?}", expanded_paths);
-            command.env("PATH", new_path);
-        }
+    /// Create a new LSP client with custom PATH extensions and extra environment variables
+    pub fn new_with_env(
+        server_cmd: &str,
+        args: &[String],
+        root_path: &Path,
+        project_type: ProjectType,
+        bin_paths: &[String],
+        env: &HashMap<String, String>,
+    ) -> Result<Self> {
+        tracing::info!("Spawning LSP server: {} {:?}", server_cmd, args);
 
-        let mut child = command.spawn().map_err(|e| {
-            QuickctxError::Io(std::io::Error::other(format!(
-                "Failed to spawn LSP server '{}': {}",
-                server_cmd, e
-            )))
+        let mut command = server_command(server_cmd, args, bin_paths, env);
+        let mut child = command.spawn().map_err(|source| QuickctxError::LspSpawn {
+            server: server_cmd.to_string(),
+            source,
         })?;
 
         let stdin = child
@@ -88,6 +88,8 @@ impl LspClient {
             root_uri,
             project_type,
             initialized: false,
+            deadline: None,
+            opened: HashSet::new(),
         })
     }
 
@@ -110,6 +112,9 @@ impl LspClient {
                         hierarchical_document_symbol_support: Some(true),
                         ..Default::default()
                     }),
+                    call_hierarchy: Some(CallHierarchyClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
                     ..Default::default()
                 }),
                 window: Some(WindowClientCapabilities {
@@ -190,12 +195,35 @@ impl LspClient {
 
         self.transport
             .send_notification("textDocument/didOpen", params_value)?;
+        self.opened.insert(file_path.to_path_buf());
 
         tracing::debug!("Opened document: {:?}", file_path);
 
         Ok(())
     }
 
+    /// Whether `did_open` has been sent for `file_path`
+    pub fn is_open(&self, file_path: &Path) -> bool {
+        self.opened.contains(file_path)
+    }
+
+    /// Stop issuing symbol and hover requests once `deadline` passes (`None` clears it)
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    fn check_deadline(&self) -> Result<()> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(QuickctxError::Aborted(
+                "per-file analysis deadline passed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Get document symbols with retry logic
     pub fn document_symbols(&mut self, uri: &Uri) -> Result<DocumentSymbolResponse> {
         // Retry several times with delays to give LSP time to process the document
@@ -204,6 +232,7 @@ impl LspClient {
         let retry_delay = std::time::Duration::from_millis(1000);
 
         for attempt in 0..max_retries {
+            self.check_deadline()?;
             let params = DocumentSymbolParams {
                 text_document: TextDocumentIdentifier { uri: uri.clone() },
                 work_done_progress_params: Default::default(),
@@ -299,6 +328,7 @@ impl LspClient {
 
     /// Get hover information at a position
     pub fn hover(&mut self, uri: &Uri, position: Position) -> Result<Option<Hover>> {
+        self.check_deadline()?;
         let params = HoverParams {
             text_document_position_params: TextDocumentPositionParams {
                 text_document: TextDocumentIdentifier { uri: uri.clone() },
@@ -399,11 +429,109 @@ impl LspClient {
         }
     }
 
+    /// Resolve the call hierarchy item(s) at a position
+    ///
+    /// Servers without call hierarchy support yield an "unsupported" error.
+    pub fn prepare_call_hierarchy(
+        &mut self,
+        uri: &Uri,
+        position: Position,
+    ) -> Result<Vec<CallHierarchyItem>> {
+        let params = CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                position,
+            },
+            work_done_progress_params: Default::default(),
+        };
+
+        let params_value = serde_json::to_value(params).map_err(|e| {
+            QuickctxError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to serialize prepareCallHierarchy params: {}", e),
+            ))
+        })?;
+
+        let id = self
+            .transport
+            .send_request("textDocument/prepareCallHierarchy", params_value)?;
+        let response = self.transport.read_response(id)?;
+
+        if let Some(error) = response.error {
+            return Err(call_hierarchy_unsupported(error.code, &error.message));
+        }
+
+        let result = response.result.unwrap_or(serde_json::Value::Null);
+        serde_json::from_value::<Option<Vec<CallHierarchyItem>>>(result)
+            .map(Option::unwrap_or_default)
+            .map_err(|e| {
+                QuickctxError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Failed to parse prepareCallHierarchy result: {}", e),
+                ))
+            })
+    }
+
+    /// List the callers of a call hierarchy item
+    pub fn incoming_calls(
+        &mut self,
+        item: &CallHierarchyItem,
+    ) -> Result<Vec<CallHierarchyIncomingCall>> {
+        let params = CallHierarchyIncomingCallsParams {
+            item: item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let params_value = serde_json::to_value(params).map_err(|e| {
+            QuickctxError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to serialize incomingCalls params: {}", e),
+            ))
+        })?;
+
+        let id = self
+            .transport
+            .send_request("callHierarchy/incomingCalls", params_value)?;
+        let response = self.transport.read_response(id)?;
+
+        if let Some(error) = response.error {
+            return Err(call_hierarchy_unsupported(error.code, &error.message));
+        }
+
+        parse_incoming_calls(response.result.unwrap_or(serde_json::Value::Null))
+    }
+
     /// Get type definition at a position
     pub fn type_definition(
         &mut self,
         uri: &Uri,
         position: Position,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        self.goto(
+            "textDocument/typeDefinition",
+            "typeDefinition",
+            uri,
+            position,
+        )
+    }
+
+    /// Get the definition of the symbol at a position
+    pub fn definition(
+        &mut self,
+        uri: &Uri,
+        position: Position,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        self.goto("textDocument/definition", "definition", uri, position)
+    }
+
+    /// Send a goto-style request, treating an error response as no result
+    fn goto(
+        &mut self,
+        method: &str,
+        label: &str,
+        uri: &Uri,
+        position: Position,
     ) -> Result<Option<GotoDefinitionResponse>> {
         if !self.initialized {
             return Err(QuickctxError::Io(std::io::Error::other(
@@ -423,17 +551,15 @@ impl LspClient {
         let params_value = serde_json::to_value(params).map_err(|e| {
             QuickctxError::Io(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
-                format!("Failed to serialize typeDefinition params: {}", e),
+                format!("Failed to serialize {} params: {}", label, e),
             ))
         })?;
 
-        let id = self
-            .transport
-            .send_request("textDocument/typeDefinition", params_value)?;
+        let id = self.transport.send_request(method, params_value)?;
         let response = self.transport.read_response(id)?;
 
         if let Some(error) = response.error {
-            tracing::debug!("typeDefinition error at {:?}: {}", position, error.message);
+            tracing::debug!("{} error at {:?}: {}", label, position, error.message);
             return Ok(None);
         }
 
@@ -447,7 +573,7 @@ impl LspClient {
                 serde_json::from_value(result).map_err(|e| {
                     QuickctxError::Io(std::io::Error::new(
                         std::io::ErrorKind::InvalidData,
-                        format!("Failed to parse typeDefinition result: {}", e),
+                        format!("Failed to parse {} result: {}", label, e),
                     ))
                 })?;
 
@@ -591,10 +717,10 @@ impl LspClient {
             last_diag_count = current_diag_count;
         }
 
-        // Finish progress bar
-        if let Some(pb) = progress_bar {
+        // Finish progress bar; the stage line honors --plain
+        if let (Some(pb), Some(display)) = (progress_bar, progress_display) {
             pb.finish_and_clear();
-            eprintln!("[3/3] ✓ Collecting diagnostics");
+            display.stage_done("[3/3]", "Collecting diagnostics");
         }
 
         // Take all diagnostics that arrived
@@ -694,3 +820,60 @@ impl Drop for LspClient {
         }
     }
 }
+
+/// Command spawning the server, with `bin_paths` prepended to PATH and `env` set on top
+fn server_command(
+    server_cmd: &str,
+    args: &[String],
+    bin_paths: &[String],
+    env: &HashMap<String, String>,
+) -> Command {
+    let mut command = Command::new(server_cmd);
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // Extend PATH if bin_paths are provided
+    if !bin_paths.is_empty() {
+        let current_path = std::env::var("PATH").unwrap_or_default();
+        let expanded_paths: Vec<String> = bin_paths
+            .iter()
+            .map(|p| shellexpand::tilde(p).to_string())
+            .collect();
+
+        let new_path = if current_path.is_empty() {
+            expanded_paths.join(":")
+        } else {
+            format!("{}:{}", expanded_paths.join(":"), current_path)
+        };
+
+        tracing::debug!("Extended PATH with: {:?}", expanded_paths);
+        command.env("PATH", new_path);
+    }
+
+    if !env.is_empty() {
+        tracing::debug!("Setting LSP server environment: {:?}", env.keys());
+        command.envs(env);
+    }
+
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn test_server_command_sets_configured_env() {
+        let env = HashMap::from([("RA_LOG".to_string(), "info".to_string())]);
+        let command = server_command("rust-analyzer", &[], &["/opt/lsp/bin".to_string()], &env);
+
+        let envs: HashMap<&OsStr, Option<&OsStr>> = command.get_envs().collect();
+        assert_eq!(envs[OsStr::new("RA_LOG")], Some(OsStr::new("info")));
+        let path = envs[OsStr::new("PATH")].unwrap().to_str().unwrap();
+        assert!(path.starts_with("/opt/lsp/bin"));
+    }
+}
@@ -1,5 +1,8 @@
 use super::project_root::ProjectType;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
 
 #[derive(Debug, Clone)]
 pub struct LspServerConfig {
@@ -116,10 +119,83 @@ pub fn has_lsp_support(path: &std::path::Path) -> bool {
         .is_some()
 }
 
+/// Map a `#!` interpreter line to a ProjectType
+/// e.g., "#!/usr/bin/env python3" → Python, "#!/usr/local/bin/node" → JavaScript
+pub fn shebang_to_project_type(line: &str) -> Option<ProjectType> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+
+    // `env` runs the first argument that is not an option (e.g. `env -S node --flag`)
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+
+    let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match name {
+        "python" | "pypy" => Some(ProjectType::Python),
+        "node" | "nodejs" => Some(ProjectType::JavaScript),
+        "deno" | "ts-node" | "tsx" | "bun" => Some(ProjectType::TypeScript),
+        _ => None,
+    }
+}
+
+/// Determine a file's ProjectType from its extension, falling back to a
+/// shebang line for extensionless scripts
+pub fn file_project_type(path: &Path) -> Option<ProjectType> {
+    match path.extension() {
+        Some(ext) => ext.to_str().and_then(extension_to_project_type),
+        None => read_first_line(path)
+            .as_deref()
+            .and_then(shebang_to_project_type),
+    }
+}
+
+/// Read at most the first 256 bytes of a file's first line
+fn read_first_line(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut line = String::new();
+    BufReader::new(file.take(256)).read_line(&mut line).ok()?;
+    Some(line)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_shebang_to_project_type() {
+        assert_eq!(
+            shebang_to_project_type("#!/usr/bin/env python3"),
+            Some(ProjectType::Python)
+        );
+        assert_eq!(
+            shebang_to_project_type("#!/usr/bin/python3.12 -u"),
+            Some(ProjectType::Python)
+        );
+        assert_eq!(
+            shebang_to_project_type("#!/usr/bin/env -S node --no-warnings"),
+            Some(ProjectType::JavaScript)
+        );
+        assert_eq!(shebang_to_project_type("#!/bin/bash"), None);
+        assert_eq!(shebang_to_project_type("import os"), None);
+    }
+
+    #[test]
+    fn test_file_project_type_sniffs_extensionless_shebang() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("manage");
+        std::fs::write(&script, "#!/usr/bin/env python3\nprint('hi')\n").unwrap();
+        let plain = dir.path().join("NOTES");
+        std::fs::write(&plain, "just text\n").unwrap();
+
+        assert_eq!(file_project_type(&script), Some(ProjectType::Python));
+        assert_eq!(file_project_type(&plain), None);
+        assert_eq!(
+            file_project_type(Path::new("src/main.rs")),
+            Some(ProjectType::Rust)
+        );
+    }
+
     #[test]
     fn test_rust_config() {
         let config = get_lsp_server(ProjectType::Rust);
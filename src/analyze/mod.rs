@@ -1,5 +1,10 @@
 pub mod cache;
+pub mod callers;
+pub mod changed;
+pub mod entry;
 pub mod extractor;
+pub mod failures;
+pub mod file_timeout;
 pub mod formatter;
 pub mod jsonrpc;
 pub mod lsp_client;
@@ -7,27 +12,45 @@ pub mod lsp_config;
 pub mod path_types;
 pub mod progress;
 pub mod project_root;
+pub mod skip;
 pub mod symbol_index;
+pub mod timings;
 pub mod type_extractor;
 pub mod type_resolver;
 pub mod uri_utils;
 
 pub use cache::SymbolCache;
-pub use extractor::{SymbolInfo, extract_symbols};
+pub use callers::{CallerRow, caller_rows};
+pub use changed::retain_changed_files;
+pub use entry::{EntryPoint, collect_entry_subgraph};
+pub use extractor::{
+    HoverProvider, SymbolInfo, Visibility, dedup_external_symbols, enrich_signatures,
+    extract_symbols, files_over_symbol_limit, retain_public_symbols, symbol_visibility,
+};
+pub use failures::{FailureKind, FailureRecord, write_failures};
+pub use file_timeout::{FileAnalysis, TIMED_OUT_NOTE, analyze_within};
 pub use formatter::{
-    FileDiagnostics, FileTypeDependencies, Formatter, JsonFormatter, MarkdownFormatter,
-    OutputFormat, ProjectDiagnostics, ProjectTypeDependencies, get_formatter,
+    DiagnosticCounts, EXTERNAL_DEPENDENCIES_PREFIX, FileDiagnostics, FileTypeDependencies,
+    Formatter, FormatterOptions, JsonFormatter, MarkdownFormatter, OutputFormat,
+    ProjectDiagnostics, ProjectTypeDependencies, SymbolTypeDependencies, get_formatter,
+    get_formatter_with_options, retain_errors, sort_project_diagnostics, sort_project_symbols,
+    type_dependencies_by_projects, unresolved_type_names,
 };
 pub use lsp_client::LspClient;
 pub use lsp_config::{
-    LspServerConfig, extension_to_project_type, get_lsp_server, get_lsp_server_with_config,
-    has_lsp_support,
+    LspServerConfig, extension_to_project_type, file_project_type, get_lsp_server,
+    get_lsp_server_with_config, has_lsp_support, shebang_to_project_type,
 };
 pub use path_types::{FilePath, RelativePath};
-pub use project_root::{ProjectType, detect_project_root, extract_project_name};
+pub use project_root::{
+    ProjectType, detect_project_root, extract_project_name, project_manifest, resolve_project_root,
+    workspace_root,
+};
+pub use skip::retain_unskipped;
 pub use symbol_index::{SymbolIndex, SymbolLocation};
+pub use timings::{StageTimings, TimingStage};
 pub use type_extractor::{TypeContext, TypeExtractor, TypeReference};
-pub use type_resolver::{ResolvedType, TypeResolution, TypeResolver};
+pub use type_resolver::{DefinitionProvider, ResolvedType, TypeResolution, TypeResolver};
 
 // Re-export for convenience
 pub use crate::config::AnalyzeSection;
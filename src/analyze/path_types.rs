@@ -84,6 +84,17 @@ impl RelativePath {
         Self(PathBuf::from(s))
     }
 
+    /// Relative form of `path` under the first of `bases` that contains it
+    ///
+    /// Falls back to `path` itself when no base contains it.
+    pub fn under_first(path: &Path, bases: &[&Path]) -> Self {
+        let relative = bases
+            .iter()
+            .find_map(|base| path.strip_prefix(base).ok())
+            .unwrap_or(path);
+        Self(relative.to_path_buf())
+    }
+
     /// Create a RelativePath from a relative path without validation
     ///
     /// # Safety
@@ -157,6 +168,22 @@ mod tests {
         assert!(RelativePath::new(path).is_err());
     }
 
+    #[test]
+    fn test_under_first_prefers_earlier_bases() {
+        let monorepo = Path::new("/repo");
+        let project = Path::new("/repo/services/api");
+
+        let inside = RelativePath::under_first(
+            Path::new("/repo/services/api/src/main.rs"),
+            &[monorepo, project],
+        );
+        assert_eq!(inside.to_string(), "services/api/src/main.rs");
+
+        let outside =
+            RelativePath::under_first(Path::new("/other/lib.rs"), &[Path::new("/repo/web")]);
+        assert_eq!(outside.to_string(), "/other/lib.rs");
+    }
+
     #[test]
     fn test_display() {
         let file_path = FilePath::from_absolute_unchecked(PathBuf::from("/test/file.rs"));
@@ -10,6 +10,7 @@ use super::jsonrpc::ProgressState;
 pub struct ProgressDisplay {
     multi: MultiProgress,
     enabled: bool,
+    plain: bool,
 }
 
 impl ProgressDisplay {
@@ -28,7 +29,33 @@ impl ProgressDisplay {
             multi.set_draw_target(ProgressDrawTarget::hidden());
         }
 
-        Self { multi, enabled }
+        Self {
+            multi,
+            enabled,
+            plain: false,
+        }
+    }
+
+    /// Suppress progress bars and stage completion lines entirely, leaving
+    /// only log output
+    pub fn with_plain(mut self, plain: bool) -> Self {
+        if plain {
+            self.plain = true;
+            self.enabled = false;
+            self.multi.set_draw_target(ProgressDrawTarget::hidden());
+        }
+        self
+    }
+
+    /// Report a finished stage on stderr, e.g. `[2/4] ✓ Extracting symbols`
+    pub fn stage_done(&self, step: &str, msg: &str) {
+        if let Some(line) = self.stage_line(step, msg) {
+            eprintln!("{line}");
+        }
+    }
+
+    fn stage_line(&self, step: &str, msg: &str) -> Option<String> {
+        (!self.plain).then(|| format!("{step} ✓ {msg}"))
     }
 
     /// Check if progress display is enabled
@@ -199,3 +226,23 @@ impl LspProgressManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_mode_suppresses_stage_lines() {
+        let progress = ProgressDisplay::new(0);
+        assert_eq!(
+            progress
+                .stage_line("[1/4]", "Finding source files")
+                .as_deref(),
+            Some("[1/4] ✓ Finding source files")
+        );
+
+        let plain = ProgressDisplay::new(0).with_plain(true);
+        assert!(!plain.is_enabled());
+        assert_eq!(plain.stage_line("[1/4]", "Finding source files"), None);
+    }
+}
@@ -1,4 +1,6 @@
+use super::lsp_config::file_project_type;
 use crate::error::{QuickctxError, Result};
+use globset::GlobBuilder;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -38,9 +40,11 @@ pub fn detect_project_root(file_path: &Path) -> Result<(PathBuf, ProjectType)> {
 
         // Move up one directory
         if !current.pop() {
-            // Reached filesystem root, try to detect from file extension
+            // Reached filesystem root, try to detect from file extension or shebang
             let project_type = if canonical_path.is_file() {
-                detect_type_from_extension(&canonical_path).unwrap_or(ProjectType::Unknown)
+                detect_type_from_extension(&canonical_path)
+                    .or_else(|| file_project_type(&canonical_path))
+                    .unwrap_or(ProjectType::Unknown)
             } else {
                 ProjectType::Unknown
             };
@@ -49,6 +53,225 @@ pub fn detect_project_root(file_path: &Path) -> Result<(PathBuf, ProjectType)> {
     }
 }
 
+/// Resolve the project a file belongs to, honouring an explicit `--project-root`
+///
+/// Without an explicit root, a detected project that is a member of a
+/// workspace resolves to the [`workspace_root`], so files across members share
+/// one language server. The explicit root is canonicalized just like detected
+/// roots and the paths of opened documents, so a root reached through a
+/// symlink still contains its files. Its type is only known when it is itself
+/// a detected project root.
+pub fn resolve_project_root(
+    file_path: &Path,
+    explicit_root: Option<&Path>,
+) -> Result<(PathBuf, ProjectType)> {
+    let Some(root) = explicit_root else {
+        let (root, project_type) = detect_project_root(file_path)?;
+        let root = workspace_root(&root, project_type).unwrap_or(root);
+        return Ok((root, project_type));
+    };
+
+    let root = root.canonicalize().map_err(QuickctxError::Io)?;
+    let (detected_root, detected_type) = detect_project_root(&root)?;
+    let project_type = if detected_root == root {
+        detected_type
+    } else {
+        ProjectType::Unknown
+    };
+    Ok((root, project_type))
+}
+
+/// Root of the workspace the project at `project_root` belongs to, if any
+///
+/// The project itself and its ancestors are searched for, by project type:
+/// - Rust: a `Cargo.toml` with a `[workspace]` whose `members` globs cover the
+///   project (and whose `exclude` does not)
+/// - TypeScript/JavaScript: a `pnpm-workspace.yaml` whose `packages` globs, or a
+///   `package.json` whose `workspaces` globs, cover the project (`!` globs exclude)
+/// - Go: a `go.work` whose `use` directives list the project
+///
+/// The nearest match wins. Python and unknown projects never resolve to a workspace.
+pub fn workspace_root(project_root: &Path, project_type: ProjectType) -> Option<PathBuf> {
+    project_root
+        .ancestors()
+        .find(|dir| {
+            let Ok(member) = project_root.strip_prefix(dir) else {
+                return false;
+            };
+            match project_type {
+                ProjectType::Rust => cargo_workspace_covers(dir, member),
+                ProjectType::TypeScript | ProjectType::JavaScript => {
+                    pnpm_workspace_covers(dir, member) || package_workspaces_cover(dir, member)
+                }
+                ProjectType::Go => go_work_covers(dir, member),
+                ProjectType::Python | ProjectType::Unknown => false,
+            }
+        })
+        .map(Path::to_path_buf)
+}
+
+fn cargo_workspace_covers(dir: &Path, member: &Path) -> bool {
+    let Some(manifest) = std::fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|text| text.parse::<toml::Table>().ok())
+    else {
+        return false;
+    };
+    let Some(workspace) = manifest.get("workspace").and_then(|w| w.as_table()) else {
+        return false;
+    };
+    // A package that declares the workspace is always one of its members
+    if member.as_os_str().is_empty() {
+        return true;
+    }
+
+    let patterns = |key: &str| -> Vec<&str> {
+        workspace
+            .get(key)
+            .and_then(|value| value.as_array())
+            .map(|items| items.iter().filter_map(|item| item.as_str()).collect())
+            .unwrap_or_default()
+    };
+    matches_any(&patterns("members"), member) && !matches_any(&patterns("exclude"), member)
+}
+
+fn package_workspaces_cover(dir: &Path, member: &Path) -> bool {
+    let Some(package) = std::fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+    else {
+        return false;
+    };
+    // `workspaces` is either a list of globs or `{ "packages": [...] }` (Yarn)
+    let workspaces = &package["workspaces"];
+    let globs = workspaces
+        .as_array()
+        .or_else(|| workspaces["packages"].as_array());
+    let Some(globs) = globs else {
+        return false;
+    };
+    if member.as_os_str().is_empty() {
+        return true;
+    }
+
+    let patterns: Vec<&str> = globs.iter().filter_map(|glob| glob.as_str()).collect();
+    matches_any(&patterns, member)
+}
+
+fn pnpm_workspace_covers(dir: &Path, member: &Path) -> bool {
+    let Ok(text) = std::fs::read_to_string(dir.join("pnpm-workspace.yaml")) else {
+        return false;
+    };
+    if member.as_os_str().is_empty() {
+        return true;
+    }
+
+    let patterns = pnpm_packages(&text);
+    let (excludes, includes): (Vec<&str>, Vec<&str>) = patterns
+        .iter()
+        .map(String::as_str)
+        .partition(|pattern| pattern.starts_with('!'));
+    let excludes: Vec<&str> = excludes.iter().map(|p| &p[1..]).collect();
+    matches_any(&includes, member) && !matches_any(&excludes, member)
+}
+
+/// Entries of the top-level `packages:` list, in block (`- glob`) or flow (`[...]`) style
+fn pnpm_packages(text: &str) -> Vec<String> {
+    let unquote = |item: &str| {
+        item.trim()
+            .trim_matches(|c| c == '\'' || c == '"')
+            .to_string()
+    };
+    let mut lines = text.lines().map(|line| match line.find(" #") {
+        Some(comment) => &line[..comment],
+        None => line,
+    });
+
+    let Some(inline) = lines.find_map(|line| line.strip_prefix("packages:")) else {
+        return Vec::new();
+    };
+    if let Some(flow) = inline.trim().strip_prefix('[') {
+        return flow
+            .trim_end_matches(']')
+            .split(',')
+            .map(unquote)
+            .filter(|item| !item.is_empty())
+            .collect();
+    }
+
+    lines
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        // The list ends at the next top-level key
+        .take_while(|line| line.starts_with(char::is_whitespace) || line.starts_with('-'))
+        .filter_map(|line| line.trim_start().strip_prefix('-'))
+        .map(unquote)
+        .collect()
+}
+
+fn go_work_covers(dir: &Path, member: &Path) -> bool {
+    let Ok(text) = std::fs::read_to_string(dir.join("go.work")) else {
+        return false;
+    };
+
+    let uses = go_work_uses(&text);
+    if member.as_os_str().is_empty() {
+        return uses.iter().any(|path| path == "." || path == "./");
+    }
+    let patterns: Vec<&str> = uses.iter().map(String::as_str).collect();
+    matches_any(&patterns, member)
+}
+
+/// Module directories listed by `use ./dir` and `use ( ... )` directives
+fn go_work_uses(text: &str) -> Vec<String> {
+    let mut uses = Vec::new();
+    let mut in_block = false;
+    for line in text.lines() {
+        let line = match line.find("//") {
+            Some(comment) => &line[..comment],
+            None => line,
+        }
+        .trim();
+
+        let path = if in_block {
+            if line == ")" {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if let Some(rest) = line
+            .strip_prefix("use")
+            .filter(|rest| rest.starts_with([' ', '\t', '(']))
+        {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+                continue;
+            }
+            rest
+        } else {
+            continue;
+        };
+
+        let path = path.trim_matches('"');
+        if !path.is_empty() {
+            uses.push(path.to_string());
+        }
+    }
+    uses
+}
+
+/// Whether a workspace-relative member path matches one of the member globs
+fn matches_any(patterns: &[&str], member: &Path) -> bool {
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .is_ok_and(|glob| glob.compile_matcher().is_match(member))
+    })
+}
+
 /// Detect project type from file extension
 fn detect_type_from_extension(file_path: &Path) -> Option<ProjectType> {
     file_path.extension().and_then(|ext| match ext.to_str()? {
@@ -94,6 +317,21 @@ fn check_project_markers(dir: &Path) -> Option<ProjectType> {
     None
 }
 
+/// Primary manifest of a project root (`Cargo.toml`, `package.json`, ...), if present
+pub fn project_manifest(root_path: &Path, project_type: ProjectType) -> Option<PathBuf> {
+    let candidates: &[&str] = match project_type {
+        ProjectType::Rust => &["Cargo.toml"],
+        ProjectType::Python => &["pyproject.toml", "setup.py"],
+        ProjectType::TypeScript | ProjectType::JavaScript => &["package.json"],
+        ProjectType::Go => &["go.mod"],
+        ProjectType::Unknown => &[],
+    };
+    candidates
+        .iter()
+        .map(|name| root_path.join(name))
+        .find(|path| path.is_file())
+}
+
 /// Extract the project name from project files
 /// Falls back to directory basename if extraction fails
 pub fn extract_project_name(root_path: &Path, project_type: ProjectType) -> String {
@@ -207,4 +445,152 @@ mod tests {
         assert_eq!(detected_root, root);
         assert_eq!(proj_type, ProjectType::Python);
     }
+
+    #[test]
+    fn test_cargo_workspace_members_share_the_workspace_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skipped\"]\n",
+        )
+        .unwrap();
+        for member in ["crates/core", "crates/cli", "crates/skipped"] {
+            fs::create_dir_all(root.join(member).join("src")).unwrap();
+            fs::write(root.join(member).join("Cargo.toml"), "[package]\n").unwrap();
+            fs::write(root.join(member).join("src/lib.rs"), "").unwrap();
+        }
+
+        let core = resolve_project_root(&root.join("crates/core/src/lib.rs"), None).unwrap();
+        let cli = resolve_project_root(&root.join("crates/cli/src/lib.rs"), None).unwrap();
+        assert_eq!(core, (root.clone(), ProjectType::Rust));
+        assert_eq!(cli, core);
+
+        // Excluded crates keep their own root, as does an explicit --project-root
+        let skipped = root.join("crates/skipped");
+        assert_eq!(
+            resolve_project_root(&skipped.join("src/lib.rs"), None).unwrap(),
+            (skipped, ProjectType::Rust)
+        );
+        let core_root = root.join("crates/core");
+        assert_eq!(
+            resolve_project_root(&core_root.join("src/lib.rs"), Some(&core_root))
+                .unwrap()
+                .0,
+            core_root
+        );
+    }
+
+    #[test]
+    fn test_js_and_go_workspace_markers() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": {"packages": ["packages/*"]}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("packages/web")).unwrap();
+        fs::create_dir_all(root.join("tools/lint")).unwrap();
+        fs::create_dir_all(root.join("go/svc")).unwrap();
+        fs::write(root.join("go/go.work"), "go 1.22\n\nuse ./svc\n").unwrap();
+        fs::create_dir_all(root.join("go/scratch")).unwrap();
+
+        assert_eq!(
+            workspace_root(&root.join("packages/web"), ProjectType::TypeScript),
+            Some(root.clone())
+        );
+        assert_eq!(
+            workspace_root(&root.join("tools/lint"), ProjectType::JavaScript),
+            None
+        );
+        assert_eq!(
+            workspace_root(&root.join("go/svc"), ProjectType::Go),
+            Some(root.join("go"))
+        );
+        assert_eq!(
+            workspace_root(&root.join("go/scratch"), ProjectType::Go),
+            None
+        );
+        assert_eq!(
+            workspace_root(&root.join("go/svc"), ProjectType::Python),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pnpm_workspace_packages_and_exclusions() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'packages/*'\n  - \"apps/web\" # the site\n  - '!packages/legacy'\n\ncatalog:\n  react: ^18\n",
+        )
+        .unwrap();
+        for dir in ["packages/ui", "packages/legacy", "apps/web", "apps/docs"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let workspace = |dir: &str| workspace_root(&root.join(dir), ProjectType::TypeScript);
+        assert_eq!(workspace("packages/ui"), Some(root.clone()));
+        assert_eq!(workspace("apps/web"), Some(root.clone()));
+        assert_eq!(workspace("packages/legacy"), None);
+        assert_eq!(workspace("apps/docs"), None);
+
+        assert_eq!(
+            pnpm_packages("packages: ['a/*', \"b\"]\n"),
+            vec!["a/*".to_string(), "b".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_go_work_use_directives() {
+        let text = "go 1.22\n\nuse ./tools // linters\n\nuse (\n\t./api\n\t\"./svc/billing\"\n\t.\n)\n\nreplace x => ./y\n";
+        assert_eq!(
+            go_work_uses(text),
+            vec!["./tools", "./api", "./svc/billing", "."]
+        );
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::write(root.join("go.work"), text).unwrap();
+        for dir in ["api", "svc/billing", "y"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        assert_eq!(
+            workspace_root(&root.join("svc/billing"), ProjectType::Go),
+            Some(root.clone())
+        );
+        assert_eq!(workspace_root(&root, ProjectType::Go), Some(root.clone()));
+        assert_eq!(workspace_root(&root.join("y"), ProjectType::Go), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_explicit_root_through_symlink_is_canonical() {
+        let temp = tempfile::tempdir().unwrap();
+        let real = temp.path().join("real");
+        fs::create_dir_all(real.join("src")).unwrap();
+        fs::write(real.join("Cargo.toml"), "").unwrap();
+        fs::write(real.join("src/lib.rs"), "").unwrap();
+        let link = temp.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let file = link.join("src/lib.rs");
+        let (root, project_type) = resolve_project_root(&file, Some(&link)).unwrap();
+
+        assert_eq!(root, real.canonicalize().unwrap());
+        assert_eq!(project_type, ProjectType::Rust);
+        // Documents are opened by canonical path, which must sit under the root
+        let canonical_file = file.canonicalize().unwrap();
+        assert_eq!(
+            canonical_file.strip_prefix(&root).unwrap(),
+            Path::new("src/lib.rs")
+        );
+        assert_eq!(
+            resolve_project_root(&file, None).unwrap(),
+            (root, ProjectType::Rust)
+        );
+    }
 }
@@ -0,0 +1,66 @@
+use crate::error::{QuickctxError, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::PathBuf;
+
+/// Drop files matching any of the `skip` glob patterns, preserving order
+///
+/// Patterns are matched against the paths as walked, so `**/generated/**`
+/// applies at any depth whether inputs were given as relative or absolute paths.
+pub fn retain_unskipped(files: Vec<PathBuf>, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    if patterns.is_empty() {
+        return Ok(files);
+    }
+    let skip = build_skip_set(patterns)?;
+
+    Ok(files
+        .into_iter()
+        .filter(|file| {
+            let skipped = skip.is_match(file)
+                || file
+                    .strip_prefix("./")
+                    .is_ok_and(|rest| skip.is_match(rest));
+            if skipped {
+                tracing::debug!("Skipping file matched by skip pattern: {}", file.display());
+            }
+            !skipped
+        })
+        .collect())
+}
+
+fn build_skip_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|err| {
+            QuickctxError::InvalidArgument(format!("invalid skip pattern {pattern}: {err}"))
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|err| QuickctxError::InvalidArgument(format!("invalid skip patterns: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_globs_drop_matching_files() {
+        let files = vec![
+            PathBuf::from("./api/user_pb2.py"),
+            PathBuf::from("./api/user.py"),
+            PathBuf::from("/repo/src/generated/schema.rs"),
+            PathBuf::from("/repo/src/lib.rs"),
+        ];
+        let patterns = vec!["**/*_pb2.py".to_string(), "**/generated/**".to_string()];
+
+        assert_eq!(
+            retain_unskipped(files, &patterns).unwrap(),
+            vec![
+                PathBuf::from("./api/user.py"),
+                PathBuf::from("/repo/src/lib.rs")
+            ]
+        );
+        assert!(retain_unskipped(vec![], &["[".to_string()]).is_err());
+    }
+}
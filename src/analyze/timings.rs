@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// Part of an analyze run whose wall-clock time is reported by `--timings`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimingStage {
+    FindFiles,
+    LspStartup,
+    SymbolExtraction,
+    TypeResolution,
+    ExternalFetch,
+    Diagnostics,
+}
+
+impl TimingStage {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::FindFiles => "finding files",
+            Self::LspStartup => "LSP startup",
+            Self::SymbolExtraction => "symbol extraction",
+            Self::TypeResolution => "type resolution",
+            Self::ExternalFetch => "external type fetch",
+            Self::Diagnostics => "diagnostics collection",
+        }
+    }
+}
+
+/// Wall-clock time spent per stage, summed over every project in the run
+#[derive(Debug, Default)]
+pub struct StageTimings {
+    totals: RefCell<BTreeMap<TimingStage, Duration>>,
+}
+
+impl StageTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `elapsed` to the stage's total
+    pub fn record(&self, stage: TimingStage, elapsed: Duration) {
+        *self.totals.borrow_mut().entry(stage).or_default() += elapsed;
+    }
+
+    /// Run `f`, adding its duration to the stage's total
+    pub fn time<R>(&self, stage: TimingStage, f: impl FnOnce() -> R) -> R {
+        let started = Instant::now();
+        let result = f();
+        self.record(stage, started.elapsed());
+        result
+    }
+
+    /// Total recorded for a stage, if it ran at all
+    pub fn get(&self, stage: TimingStage) -> Option<Duration> {
+        self.totals.borrow().get(&stage).copied()
+    }
+
+    /// One line per stage that ran, in pipeline order
+    pub fn report(&self) -> String {
+        let totals = self.totals.borrow();
+        let mut report = String::from("Timings:\n");
+        for (stage, elapsed) in totals.iter() {
+            let _ = writeln!(
+                report,
+                "  {:<24}{:>9.3}s",
+                stage.label(),
+                elapsed.as_secs_f64()
+            );
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_recorded_stages_in_order() {
+        let timings = StageTimings::new();
+        timings.record(TimingStage::TypeResolution, Duration::from_millis(250));
+        let symbols = timings.time(TimingStage::SymbolExtraction, || 3);
+        timings.record(TimingStage::TypeResolution, Duration::from_millis(250));
+
+        assert_eq!(symbols, 3);
+        assert_eq!(
+            timings.get(TimingStage::TypeResolution),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(timings.get(TimingStage::Diagnostics), None);
+
+        let report = timings.report();
+        let extraction = report.find("symbol extraction").unwrap();
+        let resolution = report.find("type resolution").unwrap();
+        assert!(extraction < resolution);
+        assert!(report.contains("0.500s"));
+        assert!(!report.contains("diagnostics"));
+    }
+}
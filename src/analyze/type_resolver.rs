@@ -3,6 +3,8 @@ use super::path_types::FilePath;
 use super::symbol_index::{SymbolIndex, SymbolLocation};
 use super::type_extractor::{TypeContext, TypeReference};
 use super::uri_utils::uri_to_file_path;
+use crate::error::Result;
+use lsp_types::{GotoDefinitionResponse, Position, Uri};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -33,6 +35,39 @@ pub enum TypeResolution {
     Unresolved,
 }
 
+/// Source of goto-definition answers, implemented by [`LspClient`]
+pub trait DefinitionProvider {
+    fn type_definition(
+        &mut self,
+        uri: &Uri,
+        position: Position,
+    ) -> Result<Option<GotoDefinitionResponse>>;
+
+    fn definition(
+        &mut self,
+        uri: &Uri,
+        position: Position,
+    ) -> Result<Option<GotoDefinitionResponse>>;
+}
+
+impl DefinitionProvider for LspClient {
+    fn type_definition(
+        &mut self,
+        uri: &Uri,
+        position: Position,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        LspClient::type_definition(self, uri, position)
+    }
+
+    fn definition(
+        &mut self,
+        uri: &Uri,
+        position: Position,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        LspClient::definition(self, uri, position)
+    }
+}
+
 /// Resolves type references to their definitions
 pub struct TypeResolver<'a> {
     symbol_index: &'a SymbolIndex,
@@ -51,7 +86,7 @@ impl<'a> TypeResolver<'a> {
     pub fn resolve_types(
         &self,
         type_refs: &[TypeReference],
-        mut lsp_client: Option<&mut LspClient>,
+        mut lsp_client: Option<&mut dyn DefinitionProvider>,
     ) -> Vec<ResolvedType> {
         let mut resolved = Vec::new();
         let mut lsp_cache: HashMap<String, Vec<lsp_types::SymbolInformation>> = HashMap::new();
@@ -71,10 +106,13 @@ impl<'a> TypeResolver<'a> {
     }
 
     /// Resolve a single type reference using typeDefinition LSP request
+    ///
+    /// Servers without type-definition support answer null or with an error,
+    /// in which case a plain `definition` request is tried at the same position.
     fn resolve_single_type(
         &self,
         type_ref: &TypeReference,
-        lsp_client: Option<&mut LspClient>,
+        lsp_client: Option<&mut (dyn DefinitionProvider + '_)>,
         _lsp_cache: &mut HashMap<String, Vec<lsp_types::SymbolInformation>>,
     ) -> TypeResolution {
         // First, check local symbol index (fast path)
@@ -93,38 +131,61 @@ impl<'a> TypeResolver<'a> {
             && let Some(client) = lsp_client
         {
             // Use the position from the TypeReference to request typeDefinition
-            match client.type_definition(&type_ref.uri, type_ref.position) {
-                Ok(Some(response)) => {
-                    // Extract location from GotoDefinitionResponse
-                    if let Some((uri, range)) = Self::extract_first_location(response) {
-                        return TypeResolution::External {
-                            file_path: uri_to_file_path(&uri)
-                                .ok()
-                                .map(FilePath::from_absolute_unchecked),
-                            line: Some(range.start.line),
-                        };
-                    }
-                }
-                Ok(None) => {
-                    tracing::debug!(
-                        "No typeDefinition found for '{}' at {:?}",
-                        type_ref.type_name,
-                        type_ref.position
-                    );
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to query typeDefinition for '{}': {}",
-                        type_ref.type_name,
-                        e
-                    );
-                }
+            let type_definition = client.type_definition(&type_ref.uri, type_ref.position);
+            if let Some(resolution) =
+                Self::external_resolution(type_ref, "typeDefinition", type_definition)
+            {
+                return resolution;
+            }
+
+            let definition = client.definition(&type_ref.uri, type_ref.position);
+            if let Some(resolution) = Self::external_resolution(type_ref, "definition", definition)
+            {
+                return resolution;
             }
         }
 
         TypeResolution::Unresolved
     }
 
+    /// Turn a goto response into an external resolution, logging why there is none
+    fn external_resolution(
+        type_ref: &TypeReference,
+        method: &str,
+        response: Result<Option<GotoDefinitionResponse>>,
+    ) -> Option<TypeResolution> {
+        match response {
+            Ok(Some(response)) => {
+                // Extract location from GotoDefinitionResponse
+                let (uri, range) = Self::extract_first_location(response)?;
+                Some(TypeResolution::External {
+                    file_path: uri_to_file_path(&uri)
+                        .ok()
+                        .map(FilePath::from_absolute_unchecked),
+                    line: Some(range.start.line),
+                })
+            }
+            Ok(None) => {
+                tracing::debug!(
+                    "No {} found for '{}' at {:?}",
+                    method,
+                    type_ref.type_name,
+                    type_ref.position
+                );
+                None
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to query {} for '{}': {}",
+                    method,
+                    type_ref.type_name,
+                    e
+                );
+                None
+            }
+        }
+    }
+
     /// Find the best matching symbol location (prefer type definitions)
     fn find_best_match<'b>(
         locations: &'b [SymbolLocation],
@@ -266,4 +327,62 @@ mod tests {
         assert_eq!(resolved.len(), 1);
         assert!(matches!(resolved[0].resolution, TypeResolution::Unresolved));
     }
+
+    /// Server without typeDefinition support that still answers definition
+    struct DefinitionOnly {
+        type_definition_calls: usize,
+    }
+
+    impl DefinitionProvider for DefinitionOnly {
+        fn type_definition(
+            &mut self,
+            _uri: &Uri,
+            _position: Position,
+        ) -> Result<Option<GotoDefinitionResponse>> {
+            self.type_definition_calls += 1;
+            Ok(None)
+        }
+
+        fn definition(
+            &mut self,
+            _uri: &Uri,
+            _position: Position,
+        ) -> Result<Option<GotoDefinitionResponse>> {
+            Ok(Some(GotoDefinitionResponse::Scalar(lsp_types::Location {
+                uri: "file:///deps/models.py".parse().unwrap(),
+                range: lsp_types::Range::new(Position::new(41, 6), Position::new(41, 11)),
+            })))
+        }
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_definition() {
+        let index = SymbolIndex::new();
+        let resolver = TypeResolver::new(&index, true);
+        let mut client = DefinitionOnly {
+            type_definition_calls: 0,
+        };
+
+        let type_refs = vec![TypeReference {
+            type_name: "Model".to_string(),
+            context: TypeContext::FunctionReturn,
+            position: Position::new(3, 20),
+            uri: "file:///app.py".parse::<Uri>().unwrap(),
+            char_offset: None,
+        }];
+
+        let resolved = resolver.resolve_types(&type_refs, Some(&mut client));
+
+        assert_eq!(client.type_definition_calls, 1);
+        match &resolved[0].resolution {
+            TypeResolution::External { file_path, line } => {
+                assert_eq!(
+                    file_path.as_ref().map(|path| path.as_ref()),
+                    Some(std::path::Path::new("/deps/models.py"))
+                );
+                assert_eq!(*line, Some(41));
+            }
+            other => panic!("expected external resolution, got {other:?}"),
+        }
+    }
 }
@@ -1,17 +1,29 @@
 use clap::Parser;
 use ignore::WalkBuilder;
+use quickctx::analyze::entry::find_symbol;
 use quickctx::analyze::uri_utils::uri_from_file_path;
 use quickctx::analyze::{
-    LspClient, LspServerConfig, OutputFormat, ProjectType, RelativePath, SymbolCache, SymbolIndex,
-    SymbolInfo, TypeExtractor, TypeResolver, detect_project_root, extract_project_name,
-    extract_symbols, get_formatter, get_lsp_server_with_config, has_lsp_support,
+    CallerRow, EXTERNAL_DEPENDENCIES_PREFIX, EntryPoint, FailureRecord, FileAnalysis,
+    FormatterOptions, JsonFormatter, LspClient, LspServerConfig, OutputFormat, ProjectType,
+    RelativePath, StageTimings, SymbolCache, SymbolIndex, SymbolInfo, TIMED_OUT_NOTE, TimingStage,
+    TypeExtractor, TypeResolver, analyze_within, caller_rows, collect_entry_subgraph,
+    dedup_external_symbols, enrich_signatures, extract_project_name, extract_symbols,
+    file_project_type, files_over_symbol_limit, get_formatter_with_options,
+    get_lsp_server_with_config, resolve_project_root, retain_changed_files, retain_public_symbols,
+    retain_unskipped, type_dependencies_by_projects, unresolved_type_names, write_failures,
 };
-use quickctx::config::{AnalyzeSection, load_analyze_config};
+use quickctx::config::{AnalyzeSection, env_or_default, load_analyze_config};
 use quickctx::error::Result;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+/// Upper bound on extra hover requests per project for `--enrich-signatures`
+const MAX_SIGNATURE_HOVERS: usize = 500;
 
 /// Project context containing project-level information
 struct ProjectContext {
@@ -19,6 +31,24 @@ struct ProjectContext {
     project_type: ProjectType,
     project_name: String,
     lsp_config: LspServerConfig,
+    /// `--paths-relative-to` directory, tried before the project root
+    paths_base: Option<PathBuf>,
+}
+
+impl ProjectContext {
+    /// Directories report paths are made relative to, in order of preference
+    fn path_bases(&self) -> Vec<&Path> {
+        self.paths_base
+            .iter()
+            .map(PathBuf::as_path)
+            .chain([self.root_path.as_path()])
+            .collect()
+    }
+
+    /// Path of a file as shown in reports
+    fn display_path(&self, path: &Path) -> String {
+        RelativePath::under_first(path, &self.path_bases()).to_string()
+    }
 }
 
 /// Shared processing context
@@ -28,6 +58,20 @@ struct ProcessingContext<'a> {
     progress: &'a quickctx::analyze::progress::ProgressDisplay,
     args: &'a Args,
     cache: Option<&'a SymbolCache>,
+    /// Files skipped along the way, reported with `--errors-json`
+    failures: &'a RefCell<Vec<FailureRecord>>,
+    /// Top-level symbol count per extracted file, taken before any filtering (`--max-symbols`)
+    symbol_counts: &'a RefCell<Vec<(String, usize)>>,
+    /// Stage durations, reported with `--timings`
+    timings: &'a StageTimings,
+}
+
+/// Parse a `--lsp-env KEY=VALUE` pair
+fn parse_env_var(raw: &str) -> std::result::Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{raw}'")),
+    }
 }
 
 /// Parse symbol filter from file or comma-separated string
@@ -70,6 +114,20 @@ fn parse_symbol_filter(filter_arg: &str) -> Result<Vec<String>> {
     Ok(unique_names.into_iter().collect())
 }
 
+/// Placeholder symbol recorded for a file skipped by `--file-timeout`
+fn timed_out_symbol() -> SymbolInfo {
+    SymbolInfo {
+        name: TIMED_OUT_NOTE.to_string(),
+        kind: lsp_types::SymbolKind::FILE,
+        detail: None,
+        documentation: None,
+        range: lsp_types::Range::default(),
+        selection_range: lsp_types::Range::default(),
+        children: Vec::new(),
+        type_dependencies: None,
+    }
+}
+
 /// Filter symbols to only those matching the given names (recursive)
 fn filter_symbols_by_names(symbols: Vec<SymbolInfo>, filter_names: &[String]) -> Vec<SymbolInfo> {
     symbols
@@ -286,10 +344,11 @@ fn fetch_external_symbols(
             }
         };
 
-        // Filter to only the symbols we need
+        // Filter to only the symbols we need, keyed by the resolved file for dedup
+        let file_key = path.canonicalize().unwrap_or(path);
         for symbol in symbols {
             if symbol_names.contains(&symbol.name) {
-                external_symbols.push(symbol);
+                external_symbols.push((file_key.clone(), symbol));
             }
         }
 
@@ -300,10 +359,12 @@ fn fetch_external_symbols(
 
     if let Some(bar) = pb {
         bar.finish_and_clear();
-        eprintln!("[4/4] ✓ Fetching external types");
+    }
+    if let Some(p) = progress {
+        p.stage_done("[4/4]", "Fetching external types");
     }
 
-    Ok(external_symbols)
+    Ok(dedup_external_symbols(external_symbols))
 }
 
 /// Group files by project (root_path, project_type)
@@ -314,16 +375,7 @@ fn group_files_by_project(
     let mut file_groups: HashMap<(PathBuf, ProjectType), Vec<PathBuf>> = HashMap::new();
 
     for file in files {
-        let (root_path, project_type) = if let Some(ref root) = args.project_root {
-            let (detected_root, detected_type) = detect_project_root(root)?;
-            if detected_root == *root {
-                (root.clone(), detected_type)
-            } else {
-                (root.clone(), ProjectType::Unknown)
-            }
-        } else {
-            detect_project_root(file)?
-        };
+        let (root_path, project_type) = resolve_project_root(file, args.project_root.as_deref())?;
 
         tracing::debug!(
             "File {} -> root: {}, type: {:?}",
@@ -347,21 +399,24 @@ fn with_lsp_client<F, R>(
     config: &AnalyzeSection,
     progress: &quickctx::analyze::progress::ProgressDisplay,
     timeout: u64,
+    timings: &StageTimings,
     f: F,
 ) -> Result<R>
 where
     F: FnOnce(&mut LspClient) -> Result<R>,
 {
+    let started = Instant::now();
     let spinner = progress.spinner(format!(
         "Starting LSP server ({})",
         project.lsp_config.command
     ));
-    let mut client = LspClient::new_with_paths(
+    let mut client = LspClient::new_with_env(
         &project.lsp_config.command,
         &project.lsp_config.args,
         &project.root_path,
         project.project_type,
         &config.bin_paths,
+        &config.lsp_env,
     )?;
 
     spinner.set_message("Initializing LSP server...");
@@ -371,6 +426,7 @@ where
 
     let lsp_progress_mgr = progress.lsp_progress_manager();
     client.wait_for_indexing(timeout, Some(&lsp_progress_mgr))?;
+    timings.record(TimingStage::LspStartup, started.elapsed());
 
     let result = f(&mut client)?;
     client.shutdown()?;
@@ -388,6 +444,25 @@ fn write_output(content: &str, output_path: Option<&std::path::Path>) -> Result<
     Ok(())
 }
 
+/// Stream output to a file or stdout through a buffered writer
+fn stream_to_output<F>(output_path: Option<&std::path::Path>, write: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> Result<()>,
+{
+    if let Some(path) = output_path {
+        let file = fs::File::create(path).map_err(quickctx::error::QuickctxError::Io)?;
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()?;
+        println!("Analysis written to: {}", path.display());
+    } else {
+        let mut writer = BufWriter::new(std::io::stdout().lock());
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
 /// Trait for different processing modes (symbols vs diagnostics)
 trait ProcessingMode {
     type FileOutput;
@@ -401,7 +476,38 @@ trait ProcessingMode {
         ctx: &ProcessingContext,
     ) -> Result<Self::ProjectOutput>;
 
-    fn format_output(&self, outputs: Vec<Self::ProjectOutput>, format: OutputFormat) -> String;
+    fn format_output(
+        &self,
+        outputs: Vec<Self::ProjectOutput>,
+        format: OutputFormat,
+        options: &FormatterOptions,
+    ) -> String;
+
+    /// Whether `stream_output` can write this format without building it in memory
+    fn can_stream(&self, _format: OutputFormat) -> bool {
+        false
+    }
+
+    /// Write the formatted output straight to `writer` (only called when `can_stream`)
+    fn stream_output(
+        &self,
+        _outputs: &[Self::ProjectOutput],
+        _format: OutputFormat,
+        _writer: &mut dyn Write,
+    ) -> Result<()> {
+        unreachable!("stream_output called for a format that cannot stream")
+    }
+
+    /// Drop results the user asked to exclude, before sorting, summaries and gates
+    fn filter_outputs(&self, _outputs: &mut [Self::ProjectOutput]) {}
+
+    /// Put projects and their files in a stable order before formatting
+    fn sort_outputs(&self, outputs: &mut [Self::ProjectOutput]);
+
+    /// Final line printed to stderr after the report, if the mode has one
+    fn summary_line(&self, _outputs: &[Self::ProjectOutput]) -> Option<String> {
+        None
+    }
 }
 
 /// Symbol extraction mode
@@ -411,6 +517,24 @@ impl ProcessingMode for SymbolMode {
     type FileOutput = (String, Vec<SymbolInfo>);
     type ProjectOutput = (String, ProjectType, Vec<Self::FileOutput>);
 
+    fn can_stream(&self, format: OutputFormat) -> bool {
+        format == OutputFormat::Json
+    }
+
+    fn stream_output(
+        &self,
+        outputs: &[Self::ProjectOutput],
+        _format: OutputFormat,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        JsonFormatter.write_by_projects(writer, outputs)?;
+        Ok(())
+    }
+
+    fn sort_outputs(&self, outputs: &mut [Self::ProjectOutput]) {
+        quickctx::analyze::sort_project_symbols(outputs);
+    }
+
     fn process_files(
         &self,
         client: &mut LspClient,
@@ -418,8 +542,35 @@ impl ProcessingMode for SymbolMode {
         project: &ProjectContext,
         ctx: &ProcessingContext,
     ) -> Result<Self::ProjectOutput> {
+        let entry = ctx
+            .args
+            .entry
+            .as_deref()
+            .map(EntryPoint::parse)
+            .transpose()?;
+        let entry_file = entry
+            .as_ref()
+            .map(|entry| entry.file.canonicalize())
+            .transpose()
+            .map_err(quickctx::error::QuickctxError::Io)?;
+        // The entry lives in one project; the others contribute nothing to its subgraph
+        if let Some(entry_file) = &entry_file
+            && !files
+                .iter()
+                .any(|file| file.canonicalize().is_ok_and(|file| &file == entry_file))
+        {
+            return Ok((
+                project.project_name.clone(),
+                project.project_type,
+                Vec::new(),
+            ));
+        }
+
         // First pass: collect all symbols from all files
         let mut all_file_symbols = Vec::new();
+        let mut hover_budget = MAX_SIGNATURE_HOVERS;
+        let mut enriched = 0;
+        let extraction_started = Instant::now();
         let pb = ctx.progress.progress_bar(files.len() as u64, "[2/4]");
         pb.set_message("Extracting symbols");
 
@@ -430,70 +581,114 @@ impl ProcessingMode for SymbolMode {
                 .canonicalize()
                 .map_err(quickctx::error::QuickctxError::Io)?;
 
-            // Try to get symbols from cache first
-            let symbols = if let Some(cache) = ctx.cache {
-                match cache.get_symbols(&input_path, project.project_type)? {
-                    Some(cached_symbols) => {
-                        tracing::info!("Using cached symbols for {}", input.display());
-                        cached_symbols
-                    }
-                    None => {
-                        // Cache miss - extract via LSP
-                        let content = fs::read_to_string(&input_path)
-                            .map_err(quickctx::error::QuickctxError::Io)?;
-
-                        let file_uri = uri_from_file_path(&input_path)?;
-
-                        tracing::info!("Opening document: {}", input.display());
-                        client.did_open(&input_path, &content)?;
-
-                        tracing::info!("Extracting symbols...");
-                        let symbols = extract_symbols(client, &file_uri)?;
-
+            // Try to get symbols from cache first; `opened` tracks whether the
+            // server has the document open for follow-up hover requests
+            let cached = match ctx.cache {
+                Some(cache) => cache.get_symbols(&input_path, project.project_type)?,
+                None => None,
+            };
+            let (mut symbols, opened) = if let Some(cached_symbols) = cached {
+                tracing::info!("Using cached symbols for {}", input.display());
+                (cached_symbols, false)
+            } else {
+                // Extract via LSP, giving up on the file once --file-timeout passes
+                let timeout = ctx.args.file_timeout.map(Duration::from_secs);
+                let analysis = analyze_within(timeout, |deadline| {
+                    client.set_deadline(deadline);
+                    let content = fs::read_to_string(&input_path)
+                        .map_err(quickctx::error::QuickctxError::Io)?;
+                    let file_uri = uri_from_file_path(&input_path)?;
+
+                    tracing::info!("Opening document: {}", input.display());
+                    client.did_open(&input_path, &content)?;
+
+                    tracing::info!("Extracting symbols...");
+                    extract_symbols(client, &file_uri)
+                });
+                client.set_deadline(None);
+
+                match analysis? {
+                    FileAnalysis::Done(symbols) => {
                         tracing::info!("Found {} symbols in {}", symbols.len(), input.display());
-
-                        // Save to cache
-                        if let Err(e) =
-                            cache.save_symbols(&input_path, symbols.clone(), project.project_type)
+                        if let Some(cache) = ctx.cache
+                            && let Err(e) = cache.save_symbols(
+                                &input_path,
+                                symbols.clone(),
+                                project.project_type,
+                            )
                         {
                             tracing::warn!("Failed to save symbols to cache: {}", e);
                         }
-
-                        symbols
+                        (symbols, true)
+                    }
+                    FileAnalysis::TimedOut(elapsed) => {
+                        tracing::warn!(
+                            "Skipping {}: analysis took {:.1}s, over --file-timeout",
+                            input.display(),
+                            elapsed.as_secs_f64()
+                        );
+                        ctx.failures
+                            .borrow_mut()
+                            .push(FailureRecord::file_timed_out(
+                                &project.project_name,
+                                &project.root_path,
+                                project.display_path(&input_path),
+                                elapsed,
+                            ));
+                        all_file_symbols.push((input_path, vec![timed_out_symbol()]));
+                        pb.inc(1);
+                        continue;
                     }
                 }
-            } else {
-                // Cache disabled - extract via LSP
-                let content =
-                    fs::read_to_string(&input_path).map_err(quickctx::error::QuickctxError::Io)?;
+            };
 
+            if ctx.args.enrich_signatures {
                 let file_uri = uri_from_file_path(&input_path)?;
+                // Cached documents are not open on the server, so only stored hovers are used
+                let mut no_hovers = 0;
+                let budget = if opened {
+                    &mut hover_budget
+                } else {
+                    &mut no_hovers
+                };
+                enriched += enrich_signatures(client, &file_uri, &mut symbols, budget);
+            }
 
-                tracing::info!("Opening document: {}", input.display());
-                client.did_open(&input_path, &content)?;
-
-                tracing::info!("Extracting symbols...");
-                let symbols = extract_symbols(client, &file_uri)?;
-
-                tracing::info!("Found {} symbols in {}", symbols.len(), input.display());
-
-                symbols
-            };
-
+            // Counted as extracted: timed-out placeholders, external types and
+            // --filter-symbols/--include-private never change what the gate sees
+            ctx.symbol_counts
+                .borrow_mut()
+                .push((project.display_path(&input_path), symbols.len()));
             all_file_symbols.push((input_path, symbols));
             pb.inc(1);
         }
         pb.finish_and_clear();
-        eprintln!("[2/4] ✓ Extracting symbols");
+        ctx.progress.stage_done("[2/4]", "Extracting symbols");
+        ctx.timings
+            .record(TimingStage::SymbolExtraction, extraction_started.elapsed());
+        if ctx.args.enrich_signatures {
+            tracing::info!("Enriched {} symbol signature(s) from hover", enriched);
+        }
 
         // Build symbol index from all files
         let symbol_index = SymbolIndex::build_from_symbols(&all_file_symbols);
         tracing::info!("Built symbol index with {} types", symbol_index.len());
 
+        // Drop private items before resolving their types, unless explicitly
+        // requested (an entry is always kept); the index above still includes
+        // them so public signatures referencing private types resolve locally
+        if !ctx.args.include_private && entry.is_none() {
+            all_file_symbols = all_file_symbols
+                .into_iter()
+                .map(|(path, symbols)| (path, retain_public_symbols(symbols, project.project_type)))
+                .collect();
+        }
+
         // Second pass: populate type dependencies
         let type_extractor = TypeExtractor::new(project.project_type);
         let type_resolver = TypeResolver::new(&symbol_index, true);
-        let mut project_files = Vec::new();
+        let mut populated_files = Vec::new();
+        let resolution_started = Instant::now();
         let pb2 = ctx
             .progress
             .progress_bar(all_file_symbols.len() as u64, "[3/4]");
@@ -524,18 +719,28 @@ impl ProcessingMode for SymbolMode {
                 );
             }
 
-            // Calculate relative path
-            let relative_path = input_path
-                .strip_prefix(&project.root_path)
-                .unwrap_or(input_path)
-                .display()
-                .to_string();
-
-            project_files.push((relative_path, mutable_symbols));
+            populated_files.push((input_path.clone(), mutable_symbols));
             pb2.inc(1);
         }
         pb2.finish_and_clear();
-        eprintln!("[3/4] ✓ Resolving types");
+        ctx.progress.stage_done("[3/4]", "Resolving types");
+        ctx.timings
+            .record(TimingStage::TypeResolution, resolution_started.elapsed());
+
+        // Scope output to the entry symbol and its local type dependencies
+        if let (Some(entry), Some(entry_file)) = (&entry, &entry_file) {
+            populated_files = collect_entry_subgraph(
+                &populated_files,
+                entry_file,
+                &entry.symbol,
+                ctx.args.depth,
+            )?;
+        }
+
+        let mut project_files: Vec<(String, Vec<SymbolInfo>)> = populated_files
+            .into_iter()
+            .map(|(input_path, symbols)| (project.display_path(&input_path), symbols))
+            .collect();
 
         // Apply symbol filter if specified
         if let Some(ref filter_arg) = ctx.args.filter_symbols {
@@ -571,7 +776,10 @@ impl ProcessingMode for SymbolMode {
                 external_files.len(),
                 project.project_name
             );
-            match fetch_external_symbols(&external_files, client, Some(ctx.progress), ctx.cache) {
+            let fetched = ctx.timings.time(TimingStage::ExternalFetch, || {
+                fetch_external_symbols(&external_files, client, Some(ctx.progress), ctx.cache)
+            });
+            match fetched {
                 Ok(external_symbols) => {
                     if !external_symbols.is_empty() {
                         tracing::info!(
@@ -579,7 +787,7 @@ impl ProcessingMode for SymbolMode {
                             external_symbols.len()
                         );
                         let relative_path =
-                            format!("_external_dependencies_{}", project.project_name);
+                            format!("{EXTERNAL_DEPENDENCIES_PREFIX}{}", project.project_name);
                         project_files.push((relative_path, external_symbols));
                     }
                 }
@@ -596,8 +804,13 @@ impl ProcessingMode for SymbolMode {
         ))
     }
 
-    fn format_output(&self, outputs: Vec<Self::ProjectOutput>, format: OutputFormat) -> String {
-        let formatter = get_formatter(format);
+    fn format_output(
+        &self,
+        outputs: Vec<Self::ProjectOutput>,
+        format: OutputFormat,
+        options: &FormatterOptions,
+    ) -> String {
+        let formatter = get_formatter_with_options(format, options);
         formatter.format_by_projects(&outputs)
     }
 }
@@ -605,12 +818,28 @@ impl ProcessingMode for SymbolMode {
 /// Diagnostics collection mode
 struct DiagnosticsMode {
     timeout_ms: u64,
+    /// Drop everything but errors (`--only-errors`)
+    only_errors: bool,
 }
 
 impl ProcessingMode for DiagnosticsMode {
     type FileOutput = quickctx::analyze::FileDiagnostics;
     type ProjectOutput = quickctx::analyze::ProjectDiagnostics;
 
+    fn filter_outputs(&self, outputs: &mut [Self::ProjectOutput]) {
+        if self.only_errors {
+            quickctx::analyze::retain_errors(outputs);
+        }
+    }
+
+    fn sort_outputs(&self, outputs: &mut [Self::ProjectOutput]) {
+        quickctx::analyze::sort_project_diagnostics(outputs);
+    }
+
+    fn summary_line(&self, outputs: &[Self::ProjectOutput]) -> Option<String> {
+        Some(quickctx::analyze::DiagnosticCounts::from_projects(outputs).to_string())
+    }
+
     fn process_files(
         &self,
         client: &mut LspClient,
@@ -635,11 +864,35 @@ impl ProcessingMode for DiagnosticsMode {
             pb.inc(1);
         }
         pb.finish_and_clear();
-        eprintln!("[2/3] ✓ Opening files");
+        ctx.progress.stage_done("[2/3]", "Opening files");
+
+        self.collect(client, files, project, ctx)
+    }
 
+    fn format_output(
+        &self,
+        outputs: Vec<Self::ProjectOutput>,
+        format: OutputFormat,
+        options: &FormatterOptions,
+    ) -> String {
+        let formatter = get_formatter_with_options(format, options);
+        formatter.format_diagnostics(&outputs)
+    }
+}
+
+impl DiagnosticsMode {
+    /// Wait for the server to publish diagnostics for `files`, which must already be open
+    fn collect(
+        &self,
+        client: &mut LspClient,
+        files: &[PathBuf],
+        project: &ProjectContext,
+        ctx: &ProcessingContext,
+    ) -> Result<quickctx::analyze::ProjectDiagnostics> {
         // Collect diagnostics with progress
-        let diagnostics_map =
-            client.collect_diagnostics(self.timeout_ms, Some(files.len()), Some(ctx.progress))?;
+        let diagnostics_map = ctx.timings.time(TimingStage::Diagnostics, || {
+            client.collect_diagnostics(self.timeout_ms, Some(files.len()), Some(ctx.progress))
+        })?;
 
         // Build file diagnostics
         let mut file_diagnostics = Vec::new();
@@ -662,14 +915,8 @@ impl ProcessingMode for DiagnosticsMode {
                 input.display()
             );
 
-            let relative_path = input_path
-                .strip_prefix(&project.root_path)
-                .unwrap_or(&input_path)
-                .display()
-                .to_string();
-
             file_diagnostics.push(quickctx::analyze::FileDiagnostics {
-                file_path: RelativePath::from_string(relative_path),
+                file_path: RelativePath::from_string(project.display_path(&input_path)),
                 diagnostics,
             });
         }
@@ -680,10 +927,192 @@ impl ProcessingMode for DiagnosticsMode {
             files: file_diagnostics,
         })
     }
+}
 
-    fn format_output(&self, outputs: Vec<Self::ProjectOutput>, format: OutputFormat) -> String {
-        let formatter = get_formatter(format);
-        formatter.format_diagnostics(&outputs)
+/// Symbol extraction followed by diagnostics from the same server (`--with-diagnostics`)
+struct SymbolsWithDiagnosticsMode {
+    diagnostics: DiagnosticsMode,
+}
+
+impl ProcessingMode for SymbolsWithDiagnosticsMode {
+    type FileOutput = <SymbolMode as ProcessingMode>::FileOutput;
+    type ProjectOutput = (
+        <SymbolMode as ProcessingMode>::ProjectOutput,
+        quickctx::analyze::ProjectDiagnostics,
+    );
+
+    fn filter_outputs(&self, outputs: &mut [Self::ProjectOutput]) {
+        for (_, diagnostics) in outputs.iter_mut() {
+            self.diagnostics
+                .filter_outputs(std::slice::from_mut(diagnostics));
+        }
+    }
+
+    fn sort_outputs(&self, outputs: &mut [Self::ProjectOutput]) {
+        // Both halves of a pair belong to the same project, so sort pairs by project name
+        outputs.sort_by(|a, b| a.0.0.cmp(&b.0.0));
+        for (symbols, diagnostics) in outputs.iter_mut() {
+            SymbolMode.sort_outputs(std::slice::from_mut(symbols));
+            self.diagnostics
+                .sort_outputs(std::slice::from_mut(diagnostics));
+        }
+    }
+
+    fn summary_line(&self, outputs: &[Self::ProjectOutput]) -> Option<String> {
+        let diagnostics: Vec<_> = outputs.iter().map(|(_, d)| d.clone()).collect();
+        self.diagnostics.summary_line(&diagnostics)
+    }
+
+    fn process_files(
+        &self,
+        client: &mut LspClient,
+        files: &[PathBuf],
+        project: &ProjectContext,
+        ctx: &ProcessingContext,
+    ) -> Result<Self::ProjectOutput> {
+        let symbols = SymbolMode.process_files(client, files, project, ctx)?;
+
+        // Files served from the symbol cache were never opened on the server
+        for input in files {
+            let input_path = input
+                .canonicalize()
+                .map_err(quickctx::error::QuickctxError::Io)?;
+            if !client.is_open(&input_path) {
+                let content =
+                    fs::read_to_string(&input_path).map_err(quickctx::error::QuickctxError::Io)?;
+                client.did_open(&input_path, &content)?;
+            }
+        }
+
+        let diagnostics = self.diagnostics.collect(client, files, project, ctx)?;
+        Ok((symbols, diagnostics))
+    }
+
+    fn format_output(
+        &self,
+        outputs: Vec<Self::ProjectOutput>,
+        format: OutputFormat,
+        options: &FormatterOptions,
+    ) -> String {
+        let (symbols, diagnostics): (Vec<_>, Vec<_>) = outputs.into_iter().unzip();
+        let formatter = get_formatter_with_options(format, options);
+        formatter.format_symbols_with_diagnostics(&symbols, &diagnostics)
+    }
+}
+
+/// Type-dependency report built from the symbol pass (`--type-dependencies`)
+struct TypeDependenciesMode;
+
+impl ProcessingMode for TypeDependenciesMode {
+    type FileOutput = quickctx::analyze::FileTypeDependencies;
+    type ProjectOutput = quickctx::analyze::ProjectTypeDependencies;
+
+    fn sort_outputs(&self, outputs: &mut [Self::ProjectOutput]) {
+        outputs.sort_by(|a, b| a.project_name.cmp(&b.project_name));
+        for project in outputs.iter_mut() {
+            project
+                .files
+                .sort_by(|a, b| a.file_path.as_path_buf().cmp(b.file_path.as_path_buf()));
+        }
+    }
+
+    fn process_files(
+        &self,
+        client: &mut LspClient,
+        files: &[PathBuf],
+        project: &ProjectContext,
+        ctx: &ProcessingContext,
+    ) -> Result<Self::ProjectOutput> {
+        let symbols = SymbolMode.process_files(client, files, project, ctx)?;
+        let mut report = type_dependencies_by_projects(std::slice::from_ref(&symbols));
+        Ok(report.remove(0))
+    }
+
+    fn format_output(
+        &self,
+        outputs: Vec<Self::ProjectOutput>,
+        format: OutputFormat,
+        options: &FormatterOptions,
+    ) -> String {
+        let formatter = get_formatter_with_options(format, options);
+        if options.unresolved_only {
+            return formatter.format_unresolved_types(&unresolved_type_names(&outputs));
+        }
+        formatter.format_type_dependencies(&outputs)
+    }
+}
+
+/// Incoming call hierarchy mode (`--callers FILE:SYMBOL`)
+struct CallersMode {
+    entry: EntryPoint,
+}
+
+impl ProcessingMode for CallersMode {
+    type FileOutput = CallerRow;
+    type ProjectOutput = Vec<CallerRow>;
+
+    fn sort_outputs(&self, outputs: &mut [Self::ProjectOutput]) {
+        for rows in outputs.iter_mut() {
+            rows.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        }
+    }
+
+    fn process_files(
+        &self,
+        client: &mut LspClient,
+        files: &[PathBuf],
+        project: &ProjectContext,
+        _ctx: &ProcessingContext,
+    ) -> Result<Self::ProjectOutput> {
+        let entry_file = self
+            .entry
+            .file
+            .canonicalize()
+            .map_err(quickctx::error::QuickctxError::Io)?;
+        // Other inputs only matter as project context; the target lives in one project
+        if !files
+            .iter()
+            .any(|file| file.canonicalize().is_ok_and(|file| file == entry_file))
+        {
+            return Ok(Vec::new());
+        }
+
+        let content =
+            fs::read_to_string(&entry_file).map_err(quickctx::error::QuickctxError::Io)?;
+        let file_uri = uri_from_file_path(&entry_file)?;
+        client.did_open(&entry_file, &content)?;
+
+        let symbols = extract_symbols(client, &file_uri)?;
+        let symbol = find_symbol(&symbols, &self.entry.symbol).ok_or_else(|| {
+            quickctx::error::QuickctxError::InvalidArgument(format!(
+                "symbol '{}' not found in {}",
+                self.entry.symbol,
+                self.entry.file.display()
+            ))
+        })?;
+
+        let items = client.prepare_call_hierarchy(&file_uri, symbol.selection_range.start)?;
+        if items.is_empty() {
+            tracing::warn!("No call hierarchy item found for {}", self.entry.symbol);
+        }
+
+        let mut calls = Vec::new();
+        for item in &items {
+            calls.extend(client.incoming_calls(item)?);
+        }
+
+        Ok(caller_rows(&calls, &project.path_bases()))
+    }
+
+    fn format_output(
+        &self,
+        outputs: Vec<Self::ProjectOutput>,
+        format: OutputFormat,
+        options: &FormatterOptions,
+    ) -> String {
+        let rows: Vec<CallerRow> = outputs.into_iter().flatten().collect();
+        let formatter = get_formatter_with_options(format, options);
+        formatter.format_callers(&self.entry.symbol, &rows)
     }
 }
 
@@ -695,8 +1124,8 @@ impl ProcessingMode for DiagnosticsMode {
     about = "Analyze source code using LSP to extract symbols, documentation, and types"
 )]
 struct Args {
-    /// Source file(s) to analyze
-    #[arg(value_name = "FILE", required = true)]
+    /// Source file(s) to analyze (defaults to the --entry or --callers file)
+    #[arg(value_name = "FILE", required_unless_present_any = ["entry", "callers"])]
     inputs: Vec<PathBuf>,
 
     /// Output format
@@ -711,6 +1140,10 @@ struct Args {
     #[arg(long, value_name = "FILE")]
     config: Option<PathBuf>,
 
+    /// Apply the [env.NAME] config overlay (defaults to $QUICKCTX_ENV)
+    #[arg(long, value_name = "NAME")]
+    env: Option<String>,
+
     /// Override project root directory
     #[arg(long)]
     project_root: Option<PathBuf>,
@@ -719,6 +1152,10 @@ struct Args {
     #[arg(long)]
     lsp_server: Option<String>,
 
+    /// Set an environment variable for the LSP server (repeatable, added to `[analyze] lsp_env`)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    lsp_env: Vec<(String, String)>,
+
     /// Increase log verbosity (repeatable)
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
@@ -727,6 +1164,14 @@ struct Args {
     #[arg(long)]
     diagnostics: bool,
 
+    /// Show only error diagnostics (implies --diagnostics)
+    #[arg(long)]
+    only_errors: bool,
+
+    /// Also collect diagnostics while extracting symbols, reported after the symbols
+    #[arg(long, conflicts_with_all = ["diagnostics", "only_errors"])]
+    with_diagnostics: bool,
+
     /// Timeout in seconds to wait for diagnostics (default: 30)
     #[arg(long, default_value = "30")]
     diagnostics_timeout: u64,
@@ -739,6 +1184,22 @@ struct Args {
     #[arg(long)]
     hidden: bool,
 
+    /// Never analyze files matching GLOB (repeatable, added to `[analyze] skip`)
+    #[arg(long, value_name = "GLOB")]
+    skip: Vec<String>,
+
+    /// Skip a file whose symbol extraction takes longer than SECS, noting the timeout in the output
+    #[arg(long, value_name = "SECS", alias = "timeout-per-file")]
+    file_timeout: Option<u64>,
+
+    /// Write skipped projects and files to PATH as JSON and keep going past failing projects
+    #[arg(long, value_name = "PATH")]
+    errors_json: Option<PathBuf>,
+
+    /// Show file paths relative to DIR (e.g. a monorepo root) instead of each project root
+    #[arg(long, value_name = "DIR")]
+    paths_relative_to: Option<PathBuf>,
+
     /// Timeout in seconds to wait for LSP server readiness (default: 30)
     #[arg(long, default_value = "30")]
     lsp_timeout: u64,
@@ -754,6 +1215,65 @@ struct Args {
     /// Clear the cache before running
     #[arg(long)]
     clear_cache: bool,
+
+    /// Include private items (Rust/TypeScript default to public items only)
+    #[arg(long)]
+    include_private: bool,
+
+    /// Report the types each file references and where they resolve, instead of symbols
+    #[arg(
+        long,
+        conflicts_with_all = ["diagnostics", "only_errors", "with_diagnostics", "callers", "max_symbols"]
+    )]
+    type_dependencies: bool,
+
+    /// Add a mermaid class diagram to markdown type-dependency reports
+    #[arg(long, requires = "type_dependencies")]
+    mermaid: bool,
+
+    /// List only the unresolved type names in the --type-dependencies report (any format)
+    #[arg(long, requires = "type_dependencies")]
+    unresolved_only: bool,
+
+    /// Hide progress bars and `[n/m]` stage lines, leaving only log output
+    #[arg(long)]
+    plain: bool,
+
+    /// Print how long each stage took (file discovery, LSP startup, extraction, ...) to stderr
+    #[arg(long, alias = "verbose-timings")]
+    timings: bool,
+
+    /// Analyze only SYMBOL in FILE plus the local types it depends on
+    #[arg(long, value_name = "FILE:SYMBOL")]
+    entry: Option<String>,
+
+    /// List the callers of SYMBOL in FILE via the LSP call hierarchy
+    #[arg(
+        long,
+        value_name = "FILE:SYMBOL",
+        conflicts_with_all = ["diagnostics", "only_errors", "with_diagnostics", "entry", "max_symbols"]
+    )]
+    callers: Option<String>,
+
+    /// Maximum dependency hops to follow from --entry
+    #[arg(long, default_value = "3", requires = "entry")]
+    depth: usize,
+
+    /// Report files with more than N top-level symbols
+    #[arg(long, value_name = "N", conflicts_with_all = ["diagnostics", "only_errors"])]
+    max_symbols: Option<usize>,
+
+    /// Exit non-zero when any file exceeds --max-symbols
+    #[arg(long, requires = "max_symbols")]
+    fail_over: bool,
+
+    /// Only analyze files changed versus a git ref (default: HEAD)
+    #[arg(long, value_name = "REF", num_args = 0..=1, default_missing_value = "HEAD")]
+    diff: Option<String>,
+
+    /// Fill missing symbol signatures from hover text (extra LSP requests, capped per project)
+    #[arg(long)]
+    enrich_signatures: bool,
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
@@ -793,7 +1313,7 @@ fn main() -> ExitCode {
         .init();
 
     match run(args) {
-        Ok(()) => ExitCode::SUCCESS,
+        Ok(code) => code,
         Err(err) => {
             eprintln!("Error: {}", err);
             ExitCode::FAILURE
@@ -801,16 +1321,19 @@ fn main() -> ExitCode {
     }
 }
 
-fn run(args: Args) -> Result<()> {
+fn run(args: Args) -> Result<ExitCode> {
     // Create progress display based on verbosity
-    let progress = quickctx::analyze::progress::ProgressDisplay::new(args.verbose);
+    let progress =
+        quickctx::analyze::progress::ProgressDisplay::new(args.verbose).with_plain(args.plain);
 
     // Load configuration to get cache settings
-    let config = load_analyze_config(args.config.as_deref())?;
+    let env = env_or_default(args.env.clone());
+    let config = load_analyze_config(args.config.as_deref(), env.as_deref())?;
 
     // Initialize cache (if enabled)
     let cache = if !args.no_cache && config.enable_cache.unwrap_or(true) {
-        let cache = SymbolCache::new(config.cache_dir.clone())?;
+        let cache = SymbolCache::new(config.cache_dir.clone())?
+            .with_key_mode(config.cache_key.unwrap_or_default());
 
         // Clear cache if requested
         if args.clear_cache {
@@ -822,6 +1345,15 @@ fn run(args: Args) -> Result<()> {
         None
     };
 
+    // An entry point implies its own file when no inputs are given
+    let mut args = args;
+    if let Some(spec) = args.entry.as_ref().or(args.callers.as_ref()) {
+        let entry = EntryPoint::parse(spec)?;
+        if args.inputs.is_empty() {
+            args.inputs.push(entry.file);
+        }
+    }
+
     // Validate all input paths exist
     for input in &args.inputs {
         if !input.exists() {
@@ -833,13 +1365,29 @@ fn run(args: Args) -> Result<()> {
     }
 
     // Expand inputs: files stay as-is, directories are walked recursively
+    let timings = StageTimings::new();
     let respect_gitignore = !args.no_gitignore;
-    let expanded_files = expand_inputs(
-        &args.inputs,
-        respect_gitignore,
-        args.hidden,
-        Some(&progress),
-    )?;
+    let mut expanded_files = timings.time(TimingStage::FindFiles, || {
+        expand_inputs(
+            &args.inputs,
+            respect_gitignore,
+            args.hidden,
+            Some(&progress),
+        )
+    })?;
+
+    let skip: Vec<String> = config.skip.iter().chain(&args.skip).cloned().collect();
+    expanded_files = retain_unskipped(expanded_files, &skip)?;
+
+    if let Some(rev) = &args.diff {
+        let cwd = std::env::current_dir().map_err(quickctx::error::QuickctxError::Io)?;
+        expanded_files = retain_changed_files(expanded_files, &cwd, rev)?;
+        if expanded_files.is_empty() {
+            return Err(quickctx::error::QuickctxError::InvalidArgument(format!(
+                "No source files changed since {rev}"
+            )));
+        }
+    }
 
     if expanded_files.is_empty() {
         return Err(quickctx::error::QuickctxError::InvalidArgument(
@@ -854,14 +1402,42 @@ fn run(args: Args) -> Result<()> {
     expanded_args.inputs = expanded_files;
 
     // Route to appropriate mode using unified processor
-    if expanded_args.diagnostics {
+    let cache = cache.as_ref();
+    let code = if let Some(spec) = &expanded_args.callers {
+        let mode = CallersMode {
+            entry: EntryPoint::parse(spec)?,
+        };
+        process_with_mode(&expanded_args, mode, &progress, cache, &timings)
+    } else if expanded_args.diagnostics || expanded_args.only_errors {
         let mode = DiagnosticsMode {
             timeout_ms: expanded_args.diagnostics_timeout * 1000,
+            only_errors: expanded_args.only_errors,
         };
-        process_with_mode(&expanded_args, mode, &progress, cache.as_ref())
+        process_with_mode(&expanded_args, mode, &progress, cache, &timings)
+    } else if expanded_args.with_diagnostics {
+        let mode = SymbolsWithDiagnosticsMode {
+            diagnostics: DiagnosticsMode {
+                timeout_ms: expanded_args.diagnostics_timeout * 1000,
+                only_errors: false,
+            },
+        };
+        process_with_mode(&expanded_args, mode, &progress, cache, &timings)
+    } else if expanded_args.type_dependencies {
+        process_with_mode(
+            &expanded_args,
+            TypeDependenciesMode,
+            &progress,
+            cache,
+            &timings,
+        )
     } else {
-        process_with_mode(&expanded_args, SymbolMode, &progress, cache.as_ref())
+        process_with_mode(&expanded_args, SymbolMode, &progress, cache, &timings)
+    }?;
+
+    if args.timings {
+        eprint!("{}", timings.report());
     }
+    Ok(code)
 }
 
 /// Expand inputs: files are kept as-is, directories are walked recursively
@@ -943,8 +1519,9 @@ fn walk_directory(
 
         let path = entry.into_path();
 
-        // Only include files with known LSP support
-        if has_lsp_support(&path) {
+        // Only include files with known LSP support; extensionless scripts are
+        // recognized by their shebang
+        if file_project_type(&path).is_some() {
             files.push(path);
         } else {
             tracing::debug!("Skipping file without LSP support: {}", path.display());
@@ -953,7 +1530,9 @@ fn walk_directory(
 
     if let Some(pb) = spinner {
         pb.finish_and_clear();
-        eprintln!("[1/4] ✓ Finding source files");
+    }
+    if let Some(p) = progress {
+        p.stage_done("[1/4]", "Finding source files");
     }
 
     Ok(())
@@ -965,13 +1544,31 @@ fn process_with_mode<M: ProcessingMode>(
     mode: M,
     progress: &quickctx::analyze::progress::ProgressDisplay,
     cache: Option<&SymbolCache>,
-) -> Result<()> {
-    let config = load_analyze_config(args.config.as_deref())?;
+    timings: &StageTimings,
+) -> Result<ExitCode> {
+    let env = env_or_default(args.env.clone());
+    let mut config = load_analyze_config(args.config.as_deref(), env.as_deref())?;
+    config.lsp_env.extend(args.lsp_env.iter().cloned());
     let file_groups = group_files_by_project(&args.inputs, args)?;
 
     tracing::info!("Files grouped into {} project(s)", file_groups.len());
 
+    // Input paths are canonical, so the base must be too for prefixes to match
+    let paths_base = args
+        .paths_relative_to
+        .as_deref()
+        .map(Path::canonicalize)
+        .transpose()
+        .map_err(quickctx::error::QuickctxError::Io)?;
+
     let mut all_outputs = Vec::new();
+    let failures = RefCell::new(Vec::new());
+    let symbol_counts = RefCell::new(Vec::new());
+    let mut failed_projects = 0;
+
+    // Process projects by root path so same-name projects sort the same way every run
+    let mut file_groups: Vec<_> = file_groups.into_iter().collect();
+    file_groups.sort_by(|a, b| a.0.0.cmp(&b.0.0));
 
     for ((root_path, project_type), files) in file_groups {
         let project_name = extract_project_name(&root_path, project_type);
@@ -998,21 +1595,47 @@ fn process_with_mode<M: ProcessingMode>(
             project_type,
             project_name,
             lsp_config,
+            paths_base: paths_base.clone(),
         };
 
         let timeout_secs = config
             .lsp_readiness_timeout_secs
             .unwrap_or(args.lsp_timeout);
 
-        let output = with_lsp_client(&project_ctx, &config, progress, timeout_secs, |client| {
-            let ctx = ProcessingContext {
-                config: &config,
-                progress,
-                args,
-                cache,
-            };
-            mode.process_files(client, &files, &project_ctx, &ctx)
-        })?;
+        let result = with_lsp_client(
+            &project_ctx,
+            &config,
+            progress,
+            timeout_secs,
+            timings,
+            |client| {
+                let ctx = ProcessingContext {
+                    config: &config,
+                    progress,
+                    args,
+                    cache,
+                    failures: &failures,
+                    symbol_counts: &symbol_counts,
+                    timings,
+                };
+                mode.process_files(client, &files, &project_ctx, &ctx)
+            },
+        );
+        let output = match result {
+            Ok(output) => output,
+            // With --errors-json a failing project is recorded and the rest still run
+            Err(err) if args.errors_json.is_some() => {
+                tracing::warn!("Skipping project {}: {}", project_ctx.project_name, err);
+                failures.borrow_mut().push(FailureRecord::project(
+                    &project_ctx.project_name,
+                    &project_ctx.root_path,
+                    &err,
+                ));
+                failed_projects += 1;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
 
         all_outputs.push(output);
         tracing::info!(
@@ -1021,10 +1644,62 @@ fn process_with_mode<M: ProcessingMode>(
         );
     }
 
-    // Format and write output
-    let formatted = mode.format_output(all_outputs, args.format.into());
-    write_output(&formatted, args.output.as_deref())?;
+    mode.filter_outputs(&mut all_outputs);
+    mode.sort_outputs(&mut all_outputs);
+
+    let over_limit = args
+        .max_symbols
+        .map(|max| (max, files_over_symbol_limit(&symbol_counts.borrow(), max)));
+
+    let summary = mode.summary_line(&all_outputs);
+
+    // Format and write output; large JSON reports are streamed instead of built in memory
+    let format = args.format.into();
+    if mode.can_stream(format) {
+        stream_to_output(args.output.as_deref(), |writer| {
+            mode.stream_output(&all_outputs, format, writer)
+        })?;
+    } else {
+        let options = FormatterOptions {
+            mermaid: args.mermaid,
+            unresolved_only: args.unresolved_only,
+        };
+        let formatted = mode.format_output(all_outputs, format, &options);
+        write_output(&formatted, args.output.as_deref())?;
+    }
 
     tracing::info!("Successfully processed {} files", args.inputs.len());
-    Ok(())
+
+    if let Some(summary) = summary {
+        eprintln!("{summary}");
+    }
+
+    if let Some(path) = &args.errors_json {
+        let failures = failures.into_inner();
+        write_failures(path, &failures)?;
+        if !failures.is_empty() {
+            eprintln!(
+                "{} failure(s) recorded in {}",
+                failures.len(),
+                path.display()
+            );
+        }
+    }
+
+    if let Some((max, files)) = over_limit
+        && !files.is_empty()
+    {
+        for (file, count) in &files {
+            eprintln!("{file}: {count} top-level symbols (max {max})");
+        }
+        if args.fail_over {
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    if failed_projects > 0 {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    Ok(ExitCode::SUCCESS)
 }
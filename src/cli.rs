@@ -2,14 +2,21 @@ use std::path::PathBuf;
 
 use clap::{ArgAction, Args, Parser, Subcommand};
 
-use crate::config::{ConflictStrategy, FencePreference, OutputFormat};
+use crate::config::{
+    BudgetStrategy, ByteSize, ChunkSize, CommentStyle, ConflictStrategy, FenceLangMap,
+    FencePreference, HeaderTemplate, InputEncoding, OutputFormat,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
-    /// Path to a configuration file (defaults to quickctx.toml if present)
+    /// Path to a configuration file, repeatable to layer overlays (defaults to quickctx.toml if present)
     #[arg(long, value_name = "FILE")]
-    pub config: Option<PathBuf>,
+    pub config: Vec<PathBuf>,
+
+    /// Apply the `[env.NAME]` overlay from the config files (defaults to $QUICKCTX_ENV)
+    #[arg(long, value_name = "NAME", alias = "config-env")]
+    pub env: Option<String>,
 
     /// Increase log verbosity (repeatable)
     #[arg(short, long, action = ArgAction::Count)]
@@ -29,10 +36,13 @@ pub enum Commands {
     Paste(PasteArgs),
 
     /// Explicit copy mode (equivalent to default invocation)
-    Copy(CopyArgs),
+    Copy(Box<CopyArgs>),
 
     /// Check for and install updates
     Update(UpdateArgs),
+
+    /// Inspect the configuration file format
+    Config(ConfigArgs),
 }
 
 #[derive(Args, Debug, Default, Clone)]
@@ -41,7 +51,8 @@ pub struct CopyArgs {
     #[arg(value_name = "PATH", required = false)]
     pub paths: Vec<PathBuf>,
 
-    /// Write copied markdown to a file instead of stdout
+    /// Write copied markdown to a file instead of stdout; `{date}`, `{time}`, `{git_sha}` and
+    /// `{cwd_name}` in FILE are expanded
     #[arg(short, long, value_name = "FILE")]
     pub output: Option<PathBuf>,
 
@@ -53,10 +64,34 @@ pub struct CopyArgs {
     #[arg(long = "fence", value_enum)]
     pub fence: Option<FencePreference>,
 
+    /// Language label style for code fences
+    #[arg(long = "fence-lang-map", value_enum)]
+    pub fence_lang_map: Option<FenceLangMap>,
+
+    /// Comment marker before the path in `--format comment` output [default: //]
+    #[arg(long = "comment-style", value_enum, value_name = "MARKER")]
+    pub comment_style: Option<CommentStyle>,
+
     /// Do not respect .gitignore entries
     #[arg(long = "no-gitignore", action = ArgAction::SetTrue)]
     pub no_gitignore: bool,
 
+    /// Do not respect the global gitignore (core.excludesFile)
+    #[arg(
+        long = "no-global-gitignore",
+        alias = "no-gitignore-global",
+        action = ArgAction::SetTrue
+    )]
+    pub no_global_gitignore: bool,
+
+    /// Do not respect the repository's .git/info/exclude
+    #[arg(long = "no-git-exclude", action = ArgAction::SetTrue)]
+    pub no_git_exclude: bool,
+
+    /// Do not respect .copierignore files
+    #[arg(long = "no-copierignore", action = ArgAction::SetTrue)]
+    pub no_copierignore: bool,
+
     /// Additional ignore file(s) to apply
     #[arg(long = "ignore-file", value_name = "FILE")]
     pub ignore_file: Vec<PathBuf>,
@@ -64,9 +99,226 @@ pub struct CopyArgs {
     /// Exclude glob pattern(s)
     #[arg(long = "exclude", value_name = "GLOB")]
     pub exclude: Vec<String>,
+
+    /// Exclude directories named NAME at any depth
+    #[arg(long = "exclude-dir", value_name = "NAME")]
+    pub exclude_dir: Vec<String>,
+
+    /// Always read files with extension EXT as text, skipping binary detection
+    #[arg(long = "treat-as-text", value_name = "EXT")]
+    pub treat_as_text: Vec<String>,
+
+    /// Always skip files with extension EXT as binary
+    #[arg(long = "treat-as-binary", value_name = "EXT")]
+    pub treat_as_binary: Vec<String>,
+
+    /// Also exclude common test files: **/tests/**, **/*_test.go, **/test_*.py, **/*.test.ts, **/*.spec.ts
+    #[arg(long = "exclude-tests", action = ArgAction::SetTrue)]
+    pub exclude_tests: bool,
+
+    /// Include zero-byte files (skipped by default)
+    #[arg(long = "include-empty", action = ArgAction::SetTrue)]
+    pub include_empty: bool,
+
+    /// Copy exactly the files listed in FILE (one relative path per line), in listed order
+    #[arg(long = "manifest-in", value_name = "FILE")]
+    pub manifest_in: Option<PathBuf>,
+
+    /// Copy the files selected by a pack FILE (globs, directories, `!` negations, `#` comments)
+    #[arg(long = "pack", value_name = "FILE", conflicts_with = "manifest_in")]
+    pub pack: Option<PathBuf>,
+
+    /// Strip comments from source files before aggregating (lossy)
+    #[arg(long = "strip-comments", action = ArgAction::SetTrue)]
+    pub strip_comments: bool,
+
+    /// Prefix each line of fenced code with its line number (`  42 | ...`)
+    #[arg(long = "line-numbers", action = ArgAction::SetTrue)]
+    pub line_numbers: bool,
+
+    /// Also copy files changed in a git commit range (e.g. v1.0..HEAD)
+    #[arg(long = "since-commit", value_name = "A..B")]
+    pub since_commit: Option<String>,
+
+    /// Also copy files changed since TAG, or since the most recent tag when omitted
+    #[arg(long = "since-tag", value_name = "TAG", require_equals = true)]
+    pub since_tag: Option<Option<String>>,
+
+    /// Replace home and root prefixes of absolute paths in output with ~ and <root>
+    #[arg(long = "redact-paths", action = ArgAction::SetTrue)]
+    pub redact_paths: bool,
+
+    /// Show paths with forward slashes on every platform (the default)
+    #[arg(
+        long = "normalize-paths",
+        action = ArgAction::SetTrue,
+        overrides_with = "no_normalize_paths"
+    )]
+    pub normalize_paths: bool,
+
+    /// Show paths with the platform's own separators
+    #[arg(
+        long = "no-normalize-paths",
+        action = ArgAction::SetTrue,
+        overrides_with = "normalize_paths"
+    )]
+    pub no_normalize_paths: bool,
+
+    /// Record symlinks as a short `symlink -> target` note instead of following them
+    #[arg(
+        long = "note-symlinks",
+        alias = "relativize-symlinks",
+        action = ArgAction::SetTrue
+    )]
+    pub note_symlinks: bool,
+
+    /// Show every path under a virtual root directory NAME (also used for heredoc targets)
+    #[arg(long = "prefix-paths", value_name = "NAME")]
+    pub prefix_paths: Option<String>,
+
+    /// Markdown heading level (1-6) for each file's heading in the `heading` format
+    #[arg(
+        long = "heading-level",
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(1..=6)
+    )]
+    pub heading_level: Option<u8>,
+
+    /// Render file blocks on N threads (output is identical to the serial render)
+    #[arg(long = "rendering-workers", value_name = "N")]
+    pub rendering_workers: Option<usize>,
+
+    /// Read files on N threads (defaults to the number of CPUs; 1 reads them one by one)
+    #[arg(
+        long = "jobs",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub jobs: Option<u64>,
+
+    /// Stop adding files once the document would exceed SIZE bytes (`500k`, `2m`), marking the cut
+    #[arg(long = "max-total-bytes", value_name = "SIZE")]
+    pub max_total_bytes: Option<ByteSize>,
+
+    /// Stop adding files once the document would exceed about N tokens (at ~4 characters per token)
+    #[arg(long = "max-tokens", value_name = "N")]
+    pub max_tokens: Option<u64>,
+
+    /// Skip any file larger than F (0-1) of the --max-tokens or --max-total-bytes budget, leaving room for others
+    #[arg(long = "max-file-fraction", value_name = "F")]
+    pub max_file_fraction: Option<f64>,
+
+    /// Which files to leave out when over --max-tokens or --max-total-bytes [default: by-order]
+    #[arg(long = "budget-strategy", value_enum)]
+    pub budget_strategy: Option<BudgetStrategy>,
+
+    /// Skip files larger than SIZE bytes (`200k`, `1m`, `1g`); a single file argument is always copied
+    #[arg(long = "max-file-size", value_name = "SIZE")]
+    pub max_file_size: Option<ByteSize>,
+
+    /// Replace each file's contents with the output of CMD run on them (by `sh -c`, `cmd /C` on Windows)
+    #[arg(long = "pipe-through", value_name = "CMD")]
+    pub pipe_through: Option<String>,
+
+    /// Write TEMPLATE before each fenced block instead of the path line ({path}, {lang}, {size}, {index})
+    #[arg(long = "header-template", value_name = "TEMPLATE")]
+    pub header_template: Option<HeaderTemplate>,
+
+    /// Split the output into numbered files of at most SIZE bytes (or `Nt` tokens), between files
+    #[arg(
+        long = "chunk",
+        value_name = "SIZE",
+        conflicts_with_all = ["max_total_bytes", "max_tokens"]
+    )]
+    pub chunk: Option<ChunkSize>,
+
+    /// Spread the files over N output files of roughly equal size, named NAME.1.EXT ... NAME.N.EXT
+    #[arg(
+        long = "split-by-size",
+        value_name = "N",
+        conflicts_with_all = ["chunk", "max_total_bytes", "max_tokens"],
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub split_by_size: Option<u64>,
+
+    /// Record each file's modification time in a JSON manifest at PATH
+    #[arg(long = "mtime-manifest", value_name = "PATH")]
+    pub mtime_manifest: Option<PathBuf>,
+
+    /// Write file count, size, token estimate and skip counts as JSON to PATH
+    #[arg(long = "summary-json", value_name = "PATH")]
+    pub summary_json: Option<PathBuf>,
+
+    /// Render without writing and print file count, size and a token estimate to stderr
+    #[arg(long = "estimate", aliases = ["dry-run", "count-only"], action = ArgAction::SetTrue)]
+    pub estimate: bool,
+
+    /// Also copy the paths listed in FILE, one per line (`-` reads stdin)
+    #[arg(
+        long = "paths-from",
+        value_name = "FILE",
+        conflicts_with = "paths_from0"
+    )]
+    pub paths_from: Option<PathBuf>,
+
+    /// Like --paths-from but NUL-separated, e.g. from `find -print0`
+    #[arg(long = "paths-from0", value_name = "FILE")]
+    pub paths_from0: Option<PathBuf>,
+
+    /// Include non-UTF-8 paths under a lossily decoded name instead of skipping them
+    #[arg(long = "allow-lossy-paths", action = ArgAction::SetTrue)]
+    pub allow_lossy_paths: bool,
+
+    /// Show each file's size next to its path in headers
+    #[arg(long = "annotate-sizes", action = ArgAction::SetTrue)]
+    pub annotate_sizes: bool,
+
+    /// Render .ipynb notebooks as their markdown and code cells instead of raw JSON
+    #[arg(long = "render-notebooks", action = ArgAction::SetTrue)]
+    pub render_notebooks: bool,
+
+    /// Prepend a YAML front matter block with generation metadata (not for heredoc)
+    #[arg(long = "front-matter", action = ArgAction::SetTrue)]
+    pub front_matter: bool,
+
+    /// Note each file's last commit (short sha, author, date) under its header
+    #[arg(long = "git-info", action = ArgAction::SetTrue)]
+    pub git_info: bool,
+
+    /// Make output reproducible: fixed front matter time, paths outside the working directory shown relative to it, path order
+    #[arg(long = "deterministic", action = ArgAction::SetTrue)]
+    pub deterministic: bool,
+
+    /// Replace files identical to an earlier one with a reference note (lossy)
+    #[arg(long = "dedup-contents", action = ArgAction::SetTrue)]
+    pub dedup_contents: bool,
+
+    /// Append to --output after a separator instead of overwriting it
+    #[arg(long = "append", action = ArgAction::SetTrue)]
+    pub append: bool,
+
+    /// Marker line between documents with --append [default: ---]
+    #[arg(long = "append-separator", value_name = "MARKER")]
+    pub append_separator: Option<String>,
+
+    /// Re-include gitignored files that git still tracks (e.g. added with -f)
+    #[arg(long = "prefer-tracked", action = ArgAction::SetTrue)]
+    pub prefer_tracked: bool,
+
+    /// Print files and bytes per language to stderr
+    #[arg(long = "language-stats", action = ArgAction::SetTrue)]
+    pub language_stats: bool,
+
+    /// Exit with an error when no files are collected
+    #[arg(long = "fail-if-empty", action = ArgAction::SetTrue)]
+    pub fail_if_empty: bool,
+
+    /// Lead with the project's README and manifest (Cargo.toml, package.json, ...), even if excluded
+    #[arg(long = "with-overview", action = ArgAction::SetTrue)]
+    pub with_overview: bool,
 }
 
-#[derive(Args, Debug, Clone)]
+#[derive(Args, Debug, Default, Clone)]
 pub struct PasteArgs {
     /// Markdown input file (omit to read from stdin)
     #[arg(value_name = "INPUT", required = false)]
@@ -79,6 +331,46 @@ pub struct PasteArgs {
     /// Conflict handling strategy
     #[arg(long = "conflict", value_enum)]
     pub conflict: Option<ConflictStrategy>,
+
+    /// Rewrite output paths with FROM=>TO (prefix FROM with `re:` for a regex; repeatable)
+    #[arg(long = "rewrite", value_name = "FROM=>TO")]
+    pub rewrite: Vec<String>,
+
+    /// File name for a single code block that has no path hint
+    #[arg(
+        long = "default-name",
+        alias = "stdin-fallback-name",
+        value_name = "NAME"
+    )]
+    pub default_name: Option<String>,
+
+    /// Restore modification times from a manifest written by `copy --mtime-manifest`
+    #[arg(long = "mtime-manifest", value_name = "PATH")]
+    pub mtime_manifest: Option<PathBuf>,
+
+    /// Decode the markdown input from NAME instead of strict UTF-8
+    #[arg(long = "input-encoding", value_name = "NAME", value_enum)]
+    pub input_encoding: Option<InputEncoding>,
+
+    /// Hard-link blocks identical to an earlier block instead of writing a copy
+    #[arg(long = "hard-link", action = ArgAction::SetTrue)]
+    pub hard_link: bool,
+
+    /// Skip blocks with invalid paths and log blocks that fail to write, failing once all blocks are done
+    #[arg(long = "keep-going", action = ArgAction::SetTrue)]
+    pub keep_going: bool,
+
+    /// Drop a leading directory that only the first block's path has (`myproject/src/lib.rs`, `src/main.rs`)
+    #[arg(
+        long = "infer-base",
+        alias = "base-dir-from-first-block",
+        action = ArgAction::SetTrue
+    )]
+    pub infer_base: bool,
+
+    /// Append each block to its file (creating it if needed) instead of overwriting
+    #[arg(long = "append-blocks", action = ArgAction::SetTrue)]
+    pub append_blocks: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -90,4 +382,24 @@ pub struct UpdateArgs {
     /// Install update without prompting for confirmation
     #[arg(short = 'y', long = "yes", action = ArgAction::SetTrue)]
     pub yes: bool,
+
+    /// Permanently turn off background update checks (writes the user config file)
+    #[arg(long = "disable", action = ArgAction::SetTrue, conflicts_with = "enable")]
+    pub disable: bool,
+
+    /// Turn background update checks back on
+    #[arg(long = "enable", action = ArgAction::SetTrue)]
+    pub enable: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy)]
+pub enum ConfigCommand {
+    /// Print a JSON Schema for quickctx.toml to stdout
+    Schema,
 }
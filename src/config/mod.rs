@@ -1,16 +1,29 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::PathBuf;
 
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::ValueEnum;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use strum::{Display, EnumString};
 
-use crate::cli::{Cli, Commands, CopyArgs, PasteArgs, UpdateArgs};
+use crate::cli::{Cli, Commands, ConfigCommand, CopyArgs, PasteArgs, UpdateArgs};
 use crate::error::{QuickctxError, Result};
+use crate::utils;
 
 #[derive(
-    Debug, Clone, Copy, ValueEnum, Deserialize, Display, EnumString, PartialEq, Eq, Default,
+    Debug,
+    Clone,
+    Copy,
+    ValueEnum,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumString,
+    PartialEq,
+    Eq,
+    Default,
 )]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
@@ -23,7 +36,17 @@ pub enum OutputFormat {
 }
 
 #[derive(
-    Debug, Clone, Copy, ValueEnum, Deserialize, Display, EnumString, PartialEq, Eq, Default,
+    Debug,
+    Clone,
+    Copy,
+    ValueEnum,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumString,
+    PartialEq,
+    Eq,
+    Default,
 )]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
@@ -32,10 +55,25 @@ pub enum FencePreference {
     Auto,
     Backtick,
     Tilde,
+    /// Prefer backticks, widening them as needed; tilde only as a last resort
+    #[value(alias = "tilde-if-needed")]
+    #[serde(alias = "tilde-if-needed")]
+    #[strum(to_string = "smart", serialize = "tilde-if-needed")]
+    Smart,
 }
 
 #[derive(
-    Debug, Clone, Copy, ValueEnum, Deserialize, Display, EnumString, PartialEq, Eq, Default,
+    Debug,
+    Clone,
+    Copy,
+    ValueEnum,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumString,
+    PartialEq,
+    Eq,
+    Default,
 )]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
@@ -46,10 +84,232 @@ pub enum ConflictStrategy {
     Overwrite,
 }
 
+/// How code fence language labels are written
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    ValueEnum,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumString,
+    PartialEq,
+    Eq,
+    Default,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum FenceLangMap {
+    /// Full language names (`typescript`, `bash`)
+    #[default]
+    Canonical,
+    /// Common short tags (`ts`, `sh`)
+    Short,
+}
+
+/// Which files `copy` leaves out when `max_tokens` or `max_total_bytes` is exceeded
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    ValueEnum,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumString,
+    PartialEq,
+    Eq,
+    Default,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum BudgetStrategy {
+    /// Keep files in collection order up to the first that does not fit
+    #[default]
+    ByOrder,
+    /// Drop files from the end of the path-sorted list until the rest fits
+    LastFirst,
+    /// Drop the largest files until the rest fits
+    LargestFirst,
+}
+
+/// Comment marker before the path inside `OutputFormat::Comment` fences
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    ValueEnum,
+    Deserialize,
+    JsonSchema,
+    Display,
+    EnumString,
+    PartialEq,
+    Eq,
+    Default,
+)]
+pub enum CommentStyle {
+    /// `// path`
+    #[default]
+    #[value(name = "//")]
+    #[serde(rename = "//")]
+    #[strum(serialize = "//")]
+    Slash,
+    /// `# path`
+    #[value(name = "#")]
+    #[serde(rename = "#")]
+    #[strum(serialize = "#")]
+    Hash,
+    /// `-- path`
+    #[value(name = "--")]
+    #[serde(rename = "--")]
+    #[strum(serialize = "--")]
+    Dash,
+    /// `; path`
+    #[value(name = ";")]
+    #[serde(rename = ";")]
+    #[strum(serialize = ";")]
+    Semicolon,
+}
+
+impl CommentStyle {
+    /// The comment marker itself
+    pub fn marker(self) -> &'static str {
+        match self {
+            Self::Slash => "//",
+            Self::Hash => "#",
+            Self::Dash => "--",
+            Self::Semicolon => ";",
+        }
+    }
+}
+
+/// Upper bound on each file written by `copy --chunk`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkSize {
+    /// Rendered size in bytes, written as `N` or with a size suffix (`64k`, `1m`)
+    Bytes(u64),
+    /// Estimated tokens, written as `Nt`
+    Tokens(u64),
+}
+
+impl ChunkSize {
+    /// Whether text of `bytes` bytes and `chars` characters stays within the limit
+    pub fn allows(&self, bytes: usize, chars: usize) -> bool {
+        match *self {
+            ChunkSize::Bytes(cap) => bytes as u64 <= cap,
+            ChunkSize::Tokens(cap) => utils::estimate_tokens_for_chars(chars) as u64 <= cap,
+        }
+    }
+}
+
+impl std::str::FromStr for ChunkSize {
+    type Err = String;
+
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        let raw = raw.trim();
+        let invalid = || format!("invalid chunk size '{raw}': expected a byte size or Nt tokens");
+        let size = match raw.strip_suffix('t') {
+            Some(digits) => digits.parse::<u64>().ok().map(ChunkSize::Tokens),
+            None => utils::parse_size(raw).ok().map(ChunkSize::Bytes),
+        };
+        size.filter(|size| !matches!(size, ChunkSize::Bytes(0) | ChunkSize::Tokens(0)))
+            .ok_or_else(invalid)
+    }
+}
+
+/// Threads `copy` reads files on unless `--jobs` says otherwise: one per CPU
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, std::num::NonZero::get)
+}
+
+/// Byte count such as `200k` or `1.5m`, as taken by `copy --max-file-size` (see [`utils::parse_size`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteSize(pub u64);
+
+impl std::str::FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        utils::parse_size(raw)
+            .map(ByteSize)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Per-file header written by `copy --header-template` before each fenced block
+///
+/// `{path}`, `{lang}`, `{size}` and `{index}` are replaced per file; any other
+/// `{...}` is rejected when the template is parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderTemplate(String);
+
+impl HeaderTemplate {
+    const PLACEHOLDERS: [&str; 4] = ["path", "lang", "size", "index"];
+
+    /// Fill in the placeholders for one file
+    pub fn expand(&self, path: &str, lang: &str, size: &str, index: usize) -> String {
+        let mut expanded = String::with_capacity(self.0.len() + path.len());
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            expanded.push_str(&rest[..start]);
+            // Parsing guarantees every `{` closes on a known placeholder
+            let end = start + rest[start..].find('}').expect("validated template");
+            match &rest[start + 1..end] {
+                "path" => expanded.push_str(path),
+                "lang" => expanded.push_str(lang),
+                "size" => expanded.push_str(size),
+                _ => expanded.push_str(&index.to_string()),
+            }
+            rest = &rest[end + 1..];
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+}
+
+impl std::str::FromStr for HeaderTemplate {
+    type Err = String;
+
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        let mut rest = raw;
+        while let Some(start) = rest.find('{') {
+            let name = rest[start + 1..]
+                .find('}')
+                .map(|end| &rest[start + 1..start + 1 + end])
+                .ok_or_else(|| format!("unclosed '{{' in header template '{raw}'"))?;
+            if !Self::PLACEHOLDERS.contains(&name) {
+                return Err(format!(
+                    "unknown placeholder '{{{name}}}' in header template: expected {{path}}, {{lang}}, {{size}} or {{index}}"
+                ));
+            }
+            rest = &rest[start + name.len() + 2..];
+        }
+        Ok(Self(raw.to_string()))
+    }
+}
+
+/// Character encoding of the markdown read by `paste`
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq, Default)]
+pub enum InputEncoding {
+    /// Strict UTF-8; invalid input is an error
+    #[default]
+    #[value(name = "utf-8", alias = "utf8")]
+    Utf8,
+    /// ISO-8859-1, where every byte is the code point of the same value
+    #[value(name = "latin1", aliases = ["latin-1", "iso-8859-1"])]
+    Latin1,
+    /// Windows-1252, Latin-1 with printable characters in 0x80-0x9F
+    #[value(name = "windows-1252", alias = "cp1252")]
+    Windows1252,
+}
+
 #[derive(Debug, Clone)]
 pub struct RuntimeConfig {
     pub context: AppContext,
     pub mode: ModeConfig,
+    /// Whether the background update notifier may run
+    pub check_updates: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -60,11 +320,30 @@ pub struct AppContext {
 
 #[derive(Debug, Clone)]
 pub enum ModeConfig {
-    Copy(CopyConfig),
+    Copy(Box<CopyConfig>),
     Paste(PasteConfig),
     Update(UpdateConfig),
+    ConfigSchema,
 }
 
+/// Heading level used for each file in `OutputFormat::Heading` (`## path`)
+pub const DEFAULT_HEADING_LEVEL: u8 = 2;
+
+/// Marker line written between documents with `append`
+pub const DEFAULT_APPEND_SEPARATOR: &str = "---";
+
+/// Exclude globs added by `--exclude-tests`
+///
+/// `**/tests/**` also covers Rust integration tests; inline `#[cfg(test)]`
+/// modules can't be matched by path and are kept.
+pub const TEST_EXCLUDES: &[&str] = &[
+    "**/tests/**",
+    "**/*_test.go",
+    "**/test_*.py",
+    "**/*.test.ts",
+    "**/*.spec.ts",
+];
+
 #[derive(Debug, Clone)]
 pub struct CopyConfig {
     pub inputs: Vec<String>,
@@ -72,13 +351,154 @@ pub struct CopyConfig {
     pub format: OutputFormat,
     pub fence: FencePreference,
     pub respect_gitignore: bool,
+    pub respect_global_gitignore: bool,
+    pub respect_git_exclude: bool,
+    /// Read `.copierignore` files in walked directories and their parents
+    pub respect_copierignore: bool,
     pub ignore_files: Vec<Utf8PathBuf>,
     pub excludes: Vec<String>,
+    /// Directory names excluded at any depth
+    pub exclude_dirs: Vec<String>,
+    /// Also exclude the [`TEST_EXCLUDES`] globs
+    pub exclude_tests: bool,
+    pub include_empty: bool,
+    pub manifest: Option<Utf8PathBuf>,
+    /// Include/exclude spec selecting the files to copy, in order
+    pub pack: Option<Utf8PathBuf>,
+    pub strip_comments: bool,
+    /// Prefix each line inside a fence with its line number
+    pub line_numbers: bool,
+    pub since_commit: Option<String>,
+    /// Tag to diff against HEAD; `Some(None)` means the most recent tag
+    pub since_tag: Option<Option<String>>,
+    pub redact_paths: bool,
+    /// Show paths with `/` separators whatever the platform
+    pub normalize_paths: bool,
+    pub note_symlinks: bool,
+    pub path_prefix: Option<Utf8PathBuf>,
+    pub fence_lang_map: FenceLangMap,
+    pub comment_style: CommentStyle,
+    pub fence_lang_overrides: HashMap<String, String>,
+    pub mtime_manifest: Option<Utf8PathBuf>,
+    /// Write run statistics as JSON to this path
+    pub summary_json: Option<Utf8PathBuf>,
+    pub paths_from: Option<Utf8PathBuf>,
+    pub null_separated: bool,
+    pub treat_as_text: Vec<String>,
+    pub treat_as_binary: Vec<String>,
+    pub estimate: bool,
+    pub allow_lossy_paths: bool,
+    pub max_total_bytes: Option<u64>,
+    /// Stop adding files once the estimated token count would exceed this
+    pub max_tokens: Option<u64>,
+    /// Skip files larger than this share of `max_total_bytes` or `max_tokens`
+    pub max_file_fraction: Option<f64>,
+    /// Which files to leave out when over `max_total_bytes` or `max_tokens`
+    pub budget_strategy: BudgetStrategy,
+    /// Skip files larger than this many bytes, unless a single file is copied
+    pub max_file_size: Option<u64>,
+    /// Heading level of the per-file heading in `OutputFormat::Heading` (1-6)
+    pub heading_level: u8,
+    /// Threads rendering file blocks; 1 renders serially
+    pub rendering_workers: usize,
+    /// Threads reading and classifying files; 1 reads them one after another
+    pub jobs: usize,
+    /// Command each file's contents are piped through before rendering
+    pub pipe_through: Option<String>,
+    /// Header replacing the format's own path line before each fenced block
+    pub header_template: Option<HeaderTemplate>,
+    /// Split the output into numbered files no larger than this
+    pub chunk: Option<ChunkSize>,
+    /// Split the output into this many files of roughly equal size
+    pub split_by_size: Option<usize>,
+    pub annotate_sizes: bool,
+    pub render_notebooks: bool,
+    pub front_matter: bool,
+    pub git_info: bool,
+    pub deterministic: bool,
+    pub dedup_contents: bool,
+    pub append: bool,
+    pub append_separator: String,
+    pub prefer_tracked: bool,
+    pub language_stats: bool,
+    pub fail_if_empty: bool,
+    pub with_overview: bool,
+}
+
+impl Default for CopyConfig {
+    fn default() -> Self {
+        Self {
+            inputs: Vec::new(),
+            output: None,
+            format: OutputFormat::default(),
+            fence: FencePreference::default(),
+            respect_gitignore: true,
+            respect_global_gitignore: true,
+            respect_git_exclude: true,
+            respect_copierignore: true,
+            ignore_files: Vec::new(),
+            excludes: Vec::new(),
+            exclude_dirs: Vec::new(),
+            exclude_tests: false,
+            include_empty: false,
+            manifest: None,
+            pack: None,
+            strip_comments: false,
+            line_numbers: false,
+            since_commit: None,
+            since_tag: None,
+            redact_paths: false,
+            normalize_paths: true,
+            note_symlinks: false,
+            path_prefix: None,
+            fence_lang_map: FenceLangMap::default(),
+            comment_style: CommentStyle::default(),
+            fence_lang_overrides: HashMap::new(),
+            mtime_manifest: None,
+            summary_json: None,
+            paths_from: None,
+            null_separated: false,
+            treat_as_text: Vec::new(),
+            treat_as_binary: Vec::new(),
+            estimate: false,
+            allow_lossy_paths: false,
+            max_total_bytes: None,
+            max_tokens: None,
+            max_file_fraction: None,
+            budget_strategy: BudgetStrategy::default(),
+            max_file_size: None,
+            heading_level: DEFAULT_HEADING_LEVEL,
+            rendering_workers: 1,
+            jobs: default_jobs(),
+            pipe_through: None,
+            header_template: None,
+            chunk: None,
+            split_by_size: None,
+            annotate_sizes: false,
+            render_notebooks: false,
+            front_matter: false,
+            git_info: false,
+            deterministic: false,
+            dedup_contents: false,
+            append: false,
+            append_separator: DEFAULT_APPEND_SEPARATOR.to_string(),
+            prefer_tracked: false,
+            language_stats: false,
+            fail_if_empty: false,
+            with_overview: false,
+        }
+    }
 }
 
 impl CopyConfig {
     pub fn require_inputs(&self) -> Result<()> {
-        if self.inputs.is_empty() {
+        if self.inputs.is_empty()
+            && self.manifest.is_none()
+            && self.pack.is_none()
+            && self.since_commit.is_none()
+            && self.since_tag.is_none()
+            && self.paths_from.is_none()
+        {
             return Err(QuickctxError::InvalidArgument(
                 "no input paths were provided".to_string(),
             ));
@@ -98,12 +518,44 @@ pub struct PasteConfig {
     pub source: InputSource,
     pub output_dir: Utf8PathBuf,
     pub conflict: ConflictStrategy,
+    pub rewrites: Vec<String>,
+    pub default_name: Option<String>,
+    pub mtime_manifest: Option<Utf8PathBuf>,
+    pub input_encoding: InputEncoding,
+    /// Hard-link blocks whose contents repeat an earlier block
+    pub hard_link: bool,
+    /// Keep writing later blocks after one has an invalid path or fails to write
+    pub keep_going: bool,
+    /// Strip a leading directory only the first block's path carries
+    pub infer_base: bool,
+    /// Append every block to its file instead of writing it
+    pub append_blocks: bool,
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        Self {
+            source: InputSource::Stdin,
+            output_dir: Utf8PathBuf::from("."),
+            conflict: ConflictStrategy::default(),
+            rewrites: Vec::new(),
+            default_name: None,
+            mtime_manifest: None,
+            input_encoding: InputEncoding::default(),
+            hard_link: false,
+            keep_going: false,
+            infer_base: false,
+            append_blocks: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct UpdateConfig {
     pub check_only: bool,
     pub yes: bool,
+    /// Persist `general.check_updates` in the user config instead of updating
+    pub set_check_updates: Option<bool>,
 }
 
 // ============================================================================
@@ -116,8 +568,59 @@ struct CopyConfigBuilder {
     format: OutputFormat,
     fence: FencePreference,
     respect_gitignore: bool,
+    respect_global_gitignore: bool,
+    respect_git_exclude: bool,
+    respect_copierignore: bool,
     ignore_files: Vec<Utf8PathBuf>,
     excludes: Vec<String>,
+    exclude_dirs: Vec<String>,
+    exclude_tests: bool,
+    include_empty: bool,
+    manifest: Option<Utf8PathBuf>,
+    pack: Option<Utf8PathBuf>,
+    strip_comments: bool,
+    line_numbers: bool,
+    since_commit: Option<String>,
+    since_tag: Option<Option<String>>,
+    redact_paths: bool,
+    normalize_paths: bool,
+    note_symlinks: bool,
+    path_prefix: Option<Utf8PathBuf>,
+    fence_lang_map: FenceLangMap,
+    comment_style: CommentStyle,
+    fence_lang_overrides: HashMap<String, String>,
+    mtime_manifest: Option<Utf8PathBuf>,
+    summary_json: Option<Utf8PathBuf>,
+    paths_from: Option<Utf8PathBuf>,
+    null_separated: bool,
+    treat_as_text: Vec<String>,
+    treat_as_binary: Vec<String>,
+    estimate: bool,
+    allow_lossy_paths: bool,
+    max_total_bytes: Option<u64>,
+    max_tokens: Option<u64>,
+    max_file_fraction: Option<f64>,
+    budget_strategy: BudgetStrategy,
+    max_file_size: Option<u64>,
+    heading_level: u8,
+    rendering_workers: usize,
+    jobs: usize,
+    pipe_through: Option<String>,
+    header_template: Option<HeaderTemplate>,
+    chunk: Option<ChunkSize>,
+    split_by_size: Option<usize>,
+    annotate_sizes: bool,
+    render_notebooks: bool,
+    front_matter: bool,
+    git_info: bool,
+    deterministic: bool,
+    dedup_contents: bool,
+    append: bool,
+    append_separator: String,
+    prefer_tracked: bool,
+    language_stats: bool,
+    fail_if_empty: bool,
+    with_overview: bool,
 }
 
 impl CopyConfigBuilder {
@@ -128,8 +631,59 @@ impl CopyConfigBuilder {
             format: OutputFormat::default(),
             fence: FencePreference::default(),
             respect_gitignore: true,
+            respect_global_gitignore: true,
+            respect_git_exclude: true,
+            respect_copierignore: true,
             ignore_files: Vec::new(),
             excludes: Vec::new(),
+            exclude_dirs: Vec::new(),
+            exclude_tests: false,
+            include_empty: false,
+            manifest: None,
+            pack: None,
+            strip_comments: false,
+            line_numbers: false,
+            since_commit: None,
+            since_tag: None,
+            redact_paths: false,
+            normalize_paths: true,
+            note_symlinks: false,
+            path_prefix: None,
+            fence_lang_map: FenceLangMap::default(),
+            comment_style: CommentStyle::default(),
+            fence_lang_overrides: HashMap::new(),
+            mtime_manifest: None,
+            summary_json: None,
+            paths_from: None,
+            null_separated: false,
+            treat_as_text: Vec::new(),
+            treat_as_binary: Vec::new(),
+            estimate: false,
+            allow_lossy_paths: false,
+            max_total_bytes: None,
+            max_tokens: None,
+            max_file_fraction: None,
+            budget_strategy: BudgetStrategy::default(),
+            max_file_size: None,
+            heading_level: DEFAULT_HEADING_LEVEL,
+            rendering_workers: 1,
+            jobs: default_jobs(),
+            pipe_through: None,
+            header_template: None,
+            chunk: None,
+            split_by_size: None,
+            annotate_sizes: false,
+            render_notebooks: false,
+            front_matter: false,
+            git_info: false,
+            deterministic: false,
+            dedup_contents: false,
+            append: false,
+            append_separator: DEFAULT_APPEND_SEPARATOR.to_string(),
+            prefer_tracked: false,
+            language_stats: false,
+            fail_if_empty: false,
+            with_overview: false,
         }
     }
 
@@ -138,6 +692,9 @@ impl CopyConfigBuilder {
         self.inputs = file.paths.clone();
         self.ignore_files = file.ignore_files.clone();
         self.excludes = file.exclude.clone();
+        self.exclude_dirs = file.exclude_dirs.clone();
+        self.treat_as_text = file.treat_as_text.clone();
+        self.treat_as_binary = file.treat_as_binary.clone();
 
         // Options: use file value if not already set
         if self.output.is_none() {
@@ -152,6 +709,107 @@ impl CopyConfigBuilder {
         if let Some(respect) = file.respect_gitignore {
             self.respect_gitignore = respect;
         }
+        if let Some(respect) = file.respect_global_gitignore {
+            self.respect_global_gitignore = respect;
+        }
+        if let Some(respect) = file.respect_git_exclude {
+            self.respect_git_exclude = respect;
+        }
+        if let Some(respect) = file.respect_copierignore {
+            self.respect_copierignore = respect;
+        }
+        if let Some(exclude_tests) = file.exclude_tests {
+            self.exclude_tests = exclude_tests;
+        }
+        if let Some(include_empty) = file.include_empty {
+            self.include_empty = include_empty;
+        }
+        if let Some(strip_comments) = file.strip_comments {
+            self.strip_comments = strip_comments;
+        }
+        if let Some(line_numbers) = file.line_numbers {
+            self.line_numbers = line_numbers;
+        }
+        if let Some(redact_paths) = file.redact_paths {
+            self.redact_paths = redact_paths;
+        }
+        if let Some(normalize_paths) = file.normalize_paths {
+            self.normalize_paths = normalize_paths;
+        }
+        if let Some(note_symlinks) = file.note_symlinks {
+            self.note_symlinks = note_symlinks;
+        }
+        if let Some(prefix) = &file.prefix_paths {
+            self.path_prefix = Some(prefix.clone());
+        }
+        if let Some(max) = file.max_total_bytes {
+            self.max_total_bytes = Some(max);
+        }
+        if let Some(max) = file.max_tokens {
+            self.max_tokens = Some(max);
+        }
+        if let Some(fraction) = file.max_file_fraction {
+            self.max_file_fraction = Some(fraction);
+        }
+        if let Some(strategy) = file.budget_strategy {
+            self.budget_strategy = strategy;
+        }
+        if let Some(level) = file.heading_level {
+            self.heading_level = level;
+        }
+        if let Some(workers) = file.rendering_workers {
+            self.rendering_workers = workers;
+        }
+        if let Some(map) = file.fence_lang_map {
+            self.fence_lang_map = map;
+        }
+        if let Some(style) = file.comment_style {
+            self.comment_style = style;
+        }
+        self.fence_lang_overrides.extend(
+            file.fence_lang_overrides
+                .iter()
+                .map(|(language, tag)| (language.clone(), tag.clone())),
+        );
+        if let Some(allow_lossy_paths) = file.allow_lossy_paths {
+            self.allow_lossy_paths = allow_lossy_paths;
+        }
+        if let Some(annotate_sizes) = file.annotate_sizes {
+            self.annotate_sizes = annotate_sizes;
+        }
+        if let Some(render_notebooks) = file.render_notebooks {
+            self.render_notebooks = render_notebooks;
+        }
+        if let Some(front_matter) = file.front_matter {
+            self.front_matter = front_matter;
+        }
+        if let Some(git_info) = file.git_info {
+            self.git_info = git_info;
+        }
+        if let Some(deterministic) = file.deterministic {
+            self.deterministic = deterministic;
+        }
+        if let Some(dedup_contents) = file.dedup_contents {
+            self.dedup_contents = dedup_contents;
+        }
+        if let Some(append) = file.append {
+            self.append = append;
+        }
+        if let Some(separator) = &file.append_separator {
+            self.append_separator = separator.clone();
+        }
+        if let Some(prefer_tracked) = file.prefer_tracked {
+            self.prefer_tracked = prefer_tracked;
+        }
+        if let Some(language_stats) = file.language_stats {
+            self.language_stats = language_stats;
+        }
+        if let Some(fail_if_empty) = file.fail_if_empty {
+            self.fail_if_empty = fail_if_empty;
+        }
+        if let Some(with_overview) = file.with_overview {
+            self.with_overview = with_overview;
+        }
 
         self
     }
@@ -161,6 +819,11 @@ impl CopyConfigBuilder {
         self.inputs
             .extend(args.paths.iter().map(|p| p.to_string_lossy().to_string()));
         self.excludes.extend(args.exclude.iter().cloned());
+        self.exclude_dirs.extend(args.exclude_dir.iter().cloned());
+        self.treat_as_text
+            .extend(args.treat_as_text.iter().cloned());
+        self.treat_as_binary
+            .extend(args.treat_as_binary.iter().cloned());
 
         for path in &args.ignore_file {
             self.ignore_files.push(to_utf8_path(path.clone())?);
@@ -176,11 +839,156 @@ impl CopyConfigBuilder {
         if let Some(fence) = args.fence {
             self.fence = fence;
         }
+        if let Some(map) = args.fence_lang_map {
+            self.fence_lang_map = map;
+        }
+        if let Some(style) = args.comment_style {
+            self.comment_style = style;
+        }
+        if let Some(path) = &args.manifest_in {
+            self.manifest = Some(to_utf8_path(path.clone())?);
+        }
+        if let Some(path) = &args.pack {
+            self.pack = Some(to_utf8_path(path.clone())?);
+        }
+        if let Some(range) = &args.since_commit {
+            self.since_commit = Some(range.clone());
+        }
+        if let Some(tag) = &args.since_tag {
+            self.since_tag = Some(tag.clone());
+        }
+        if let Some(prefix) = &args.prefix_paths {
+            self.path_prefix = Some(Utf8PathBuf::from(prefix));
+        }
+        if let Some(ByteSize(max)) = args.max_total_bytes {
+            self.max_total_bytes = Some(max);
+        }
+        if let Some(max) = args.max_tokens {
+            self.max_tokens = Some(max);
+        }
+        if let Some(fraction) = args.max_file_fraction {
+            self.max_file_fraction = Some(fraction);
+        }
+        if let Some(strategy) = args.budget_strategy {
+            self.budget_strategy = strategy;
+        }
+        if let Some(ByteSize(size)) = args.max_file_size {
+            self.max_file_size = Some(size);
+        }
+        if let Some(level) = args.heading_level {
+            self.heading_level = level;
+        }
+        if let Some(workers) = args.rendering_workers {
+            self.rendering_workers = workers;
+        }
+        if let Some(jobs) = args.jobs {
+            self.jobs = jobs as usize;
+        }
+        if let Some(command) = &args.pipe_through {
+            self.pipe_through = Some(command.clone());
+        }
+        if let Some(template) = &args.header_template {
+            self.header_template = Some(template.clone());
+        }
+        if let Some(size) = args.chunk {
+            self.chunk = Some(size);
+        }
+        if let Some(parts) = args.split_by_size {
+            self.split_by_size = Some(parts as usize);
+        }
+        if let Some(path) = &args.mtime_manifest {
+            self.mtime_manifest = Some(to_utf8_path(path.clone())?);
+        }
+        if let Some(path) = &args.summary_json {
+            self.summary_json = Some(to_utf8_path(path.clone())?);
+        }
+        if let Some(path) = &args.paths_from {
+            self.paths_from = Some(to_utf8_path(path.clone())?);
+        }
+        if let Some(path) = &args.paths_from0 {
+            self.paths_from = Some(to_utf8_path(path.clone())?);
+            self.null_separated = true;
+        }
 
         // Special: no_gitignore flag overrides everything
         if args.no_gitignore {
             self.respect_gitignore = false;
         }
+        if args.no_global_gitignore {
+            self.respect_global_gitignore = false;
+        }
+        if args.no_git_exclude {
+            self.respect_git_exclude = false;
+        }
+        if args.no_copierignore {
+            self.respect_copierignore = false;
+        }
+        if args.exclude_tests {
+            self.exclude_tests = true;
+        }
+        if args.include_empty {
+            self.include_empty = true;
+        }
+        if args.estimate {
+            self.estimate = true;
+        }
+        if args.strip_comments {
+            self.strip_comments = true;
+        }
+        if args.line_numbers {
+            self.line_numbers = true;
+        }
+        if args.redact_paths {
+            self.redact_paths = true;
+        }
+        if args.normalize_paths {
+            self.normalize_paths = true;
+        }
+        if args.no_normalize_paths {
+            self.normalize_paths = false;
+        }
+        if args.note_symlinks {
+            self.note_symlinks = true;
+        }
+        if args.allow_lossy_paths {
+            self.allow_lossy_paths = true;
+        }
+        if args.annotate_sizes {
+            self.annotate_sizes = true;
+        }
+        if args.render_notebooks {
+            self.render_notebooks = true;
+        }
+        if args.front_matter {
+            self.front_matter = true;
+        }
+        if args.git_info {
+            self.git_info = true;
+        }
+        if args.deterministic {
+            self.deterministic = true;
+        }
+        if args.dedup_contents {
+            self.dedup_contents = true;
+        }
+        if args.append {
+            self.append = true;
+        }
+        if let Some(separator) = &args.append_separator {
+            self.append_separator = separator.clone();
+        }
+        if args.prefer_tracked {
+            self.prefer_tracked = true;
+        }
+        if args.language_stats {
+            self.language_stats = true;
+        }
+        if args.fail_if_empty {
+            self.fail_if_empty = true;
+        }
+        if args.with_overview {
+            self.with_overview = true;
+        }
 
         Ok(self)
     }
@@ -192,8 +1000,59 @@ impl CopyConfigBuilder {
             format: self.format,
             fence: self.fence,
             respect_gitignore: self.respect_gitignore,
+            respect_global_gitignore: self.respect_global_gitignore,
+            respect_git_exclude: self.respect_git_exclude,
+            respect_copierignore: self.respect_copierignore,
             ignore_files: self.ignore_files,
             excludes: self.excludes,
+            exclude_dirs: self.exclude_dirs,
+            exclude_tests: self.exclude_tests,
+            include_empty: self.include_empty,
+            manifest: self.manifest,
+            pack: self.pack,
+            strip_comments: self.strip_comments,
+            line_numbers: self.line_numbers,
+            since_commit: self.since_commit,
+            since_tag: self.since_tag,
+            redact_paths: self.redact_paths,
+            normalize_paths: self.normalize_paths,
+            note_symlinks: self.note_symlinks,
+            path_prefix: self.path_prefix,
+            fence_lang_map: self.fence_lang_map,
+            comment_style: self.comment_style,
+            fence_lang_overrides: self.fence_lang_overrides,
+            mtime_manifest: self.mtime_manifest,
+            summary_json: self.summary_json,
+            paths_from: self.paths_from,
+            null_separated: self.null_separated,
+            treat_as_text: self.treat_as_text,
+            treat_as_binary: self.treat_as_binary,
+            estimate: self.estimate,
+            allow_lossy_paths: self.allow_lossy_paths,
+            max_total_bytes: self.max_total_bytes,
+            max_tokens: self.max_tokens,
+            max_file_fraction: self.max_file_fraction,
+            budget_strategy: self.budget_strategy,
+            max_file_size: self.max_file_size,
+            heading_level: self.heading_level,
+            rendering_workers: self.rendering_workers,
+            jobs: self.jobs,
+            pipe_through: self.pipe_through,
+            header_template: self.header_template,
+            chunk: self.chunk,
+            split_by_size: self.split_by_size,
+            annotate_sizes: self.annotate_sizes,
+            render_notebooks: self.render_notebooks,
+            front_matter: self.front_matter,
+            git_info: self.git_info,
+            deterministic: self.deterministic,
+            dedup_contents: self.dedup_contents,
+            append: self.append,
+            append_separator: self.append_separator,
+            prefer_tracked: self.prefer_tracked,
+            language_stats: self.language_stats,
+            fail_if_empty: self.fail_if_empty,
+            with_overview: self.with_overview,
         }
     }
 }
@@ -202,6 +1061,14 @@ struct PasteConfigBuilder {
     output_dir: Utf8PathBuf,
     conflict: ConflictStrategy,
     source: Option<InputSource>,
+    rewrites: Vec<String>,
+    default_name: Option<String>,
+    mtime_manifest: Option<Utf8PathBuf>,
+    input_encoding: InputEncoding,
+    hard_link: bool,
+    keep_going: bool,
+    infer_base: bool,
+    append_blocks: bool,
 }
 
 impl PasteConfigBuilder {
@@ -210,6 +1077,14 @@ impl PasteConfigBuilder {
             output_dir: cwd,
             conflict: ConflictStrategy::default(),
             source: None,
+            rewrites: Vec::new(),
+            default_name: None,
+            mtime_manifest: None,
+            input_encoding: InputEncoding::default(),
+            hard_link: false,
+            keep_going: false,
+            infer_base: false,
+            append_blocks: false,
         }
     }
 
@@ -220,6 +1095,7 @@ impl PasteConfigBuilder {
         if let Some(conflict) = file.conflict {
             self.conflict = conflict;
         }
+        self.rewrites.extend(file.rewrite.iter().cloned());
         self
     }
 
@@ -230,6 +1106,28 @@ impl PasteConfigBuilder {
         if let Some(conflict) = args.conflict {
             self.conflict = conflict;
         }
+        self.rewrites.extend(args.rewrite.iter().cloned());
+        if let Some(name) = &args.default_name {
+            self.default_name = Some(name.clone());
+        }
+        if let Some(path) = &args.mtime_manifest {
+            self.mtime_manifest = Some(to_utf8_path(path.clone())?);
+        }
+        if let Some(encoding) = args.input_encoding {
+            self.input_encoding = encoding;
+        }
+        if args.hard_link {
+            self.hard_link = true;
+        }
+        if args.keep_going {
+            self.keep_going = true;
+        }
+        if args.infer_base {
+            self.infer_base = true;
+        }
+        if args.append_blocks {
+            self.append_blocks = true;
+        }
 
         self.source = Some(match &args.input {
             Some(path) => InputSource::File(to_utf8_path(path.clone())?),
@@ -244,6 +1142,14 @@ impl PasteConfigBuilder {
             source: self.source.unwrap_or(InputSource::Stdin),
             output_dir: self.output_dir,
             conflict: self.conflict,
+            rewrites: self.rewrites,
+            default_name: self.default_name,
+            mtime_manifest: self.mtime_manifest,
+            input_encoding: self.input_encoding,
+            hard_link: self.hard_link,
+            keep_going: self.keep_going,
+            infer_base: self.infer_base,
+            append_blocks: self.append_blocks,
         }
     }
 }
@@ -252,12 +1158,13 @@ pub fn load(cli: &Cli) -> Result<RuntimeConfig> {
     let cwd = std::env::current_dir()?;
     let cwd = to_utf8_path(cwd)?;
 
-    let config_path = resolve_config_path(cli, &cwd);
-    let file_config = if let Some(path) = &config_path {
-        parse_file_config(path)?
-    } else {
-        FileConfig::default()
-    };
+    let mut file_config = FileConfig::default();
+    for path in resolve_config_paths(cli, &cwd)? {
+        file_config.merge(parse_file_config(&path)?);
+    }
+    if let Some(name) = selected_env(cli) {
+        file_config.apply_env(&name)?;
+    }
 
     let verbosity = cli.verbose + file_config.general.verbose.unwrap_or(0);
 
@@ -266,7 +1173,7 @@ pub fn load(cli: &Cli) -> Result<RuntimeConfig> {
     let mode = match &cli.command {
         Some(Commands::Copy(args)) => {
             let cfg = build_copy_config(Some(args), &cli.copy, &file_config)?;
-            ModeConfig::Copy(cfg)
+            ModeConfig::Copy(Box::new(cfg))
         }
         Some(Commands::Paste(args)) => {
             let cfg = build_paste_config(args, &file_config, &context)?;
@@ -276,25 +1183,69 @@ pub fn load(cli: &Cli) -> Result<RuntimeConfig> {
             let cfg = build_update_config(args);
             ModeConfig::Update(cfg)
         }
+        Some(Commands::Config(args)) => match args.command {
+            ConfigCommand::Schema => ModeConfig::ConfigSchema,
+        },
         None => {
             let cfg = build_copy_config(None, &cli.copy, &file_config)?;
-            ModeConfig::Copy(cfg)
+            ModeConfig::Copy(Box::new(cfg))
         }
     };
 
-    Ok(RuntimeConfig { context, mode })
+    Ok(RuntimeConfig {
+        context,
+        mode,
+        check_updates: file_config
+            .general
+            .check_updates
+            .or(user_check_updates()?)
+            .unwrap_or(true),
+    })
 }
 
-fn resolve_config_path(cli: &Cli, cwd: &Utf8Path) -> Option<Utf8PathBuf> {
-    if let Some(path) = &cli.config {
-        return to_utf8_path(path.clone()).ok();
+/// Config files to load, in merge order: every `--config` given, or the
+/// default `quickctx.toml` when none were.
+fn resolve_config_paths(cli: &Cli, cwd: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    let mut paths = Vec::new();
+    if !cli.config.is_empty() {
+        for path in &cli.config {
+            paths.push(to_utf8_path(path.clone())?);
+        }
+        return Ok(paths);
     }
 
     let default = cwd.join("quickctx.toml");
     if default.exists() {
-        Some(default)
-    } else {
-        None
+        paths.push(default);
+    }
+    Ok(paths)
+}
+
+/// Environment overlay named by `--env`, falling back to `$QUICKCTX_ENV`
+fn selected_env(cli: &Cli) -> Option<String> {
+    env_or_default(cli.env.clone())
+}
+
+/// `flag` when given, otherwise `$QUICKCTX_ENV`; empty names select no overlay
+pub fn env_or_default(flag: Option<String>) -> Option<String> {
+    flag.or_else(|| std::env::var("QUICKCTX_ENV").ok())
+        .filter(|name| !name.is_empty())
+}
+
+/// Per-user config file (`$XDG_CONFIG_HOME/quickctx/config.toml` on Linux),
+/// written by `update --disable`/`--enable`
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("quickctx").join("config.toml"))
+}
+
+/// `general.check_updates` from the user config; its other keys are ignored so
+/// copy, paste and analyze settings only come from project config files
+fn user_check_updates() -> Result<Option<bool>> {
+    match user_config_path().filter(|path| path.exists()) {
+        Some(path) => Ok(parse_file_config(&to_utf8_path(path)?)?
+            .general
+            .check_updates),
+        None => Ok(None),
     }
 }
 
@@ -310,6 +1261,30 @@ fn build_copy_config(
         .with_cli_args(args)?
         .build();
 
+    if !(1..=6).contains(&config.heading_level) {
+        return Err(QuickctxError::Config(format!(
+            "copy.heading_level must be between 1 and 6, got {}",
+            config.heading_level
+        )));
+    }
+    if let Some(fraction) = config.max_file_fraction {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(QuickctxError::Config(format!(
+                "copy.max_file_fraction must be between 0 and 1, got {fraction}"
+            )));
+        }
+        if config.max_total_bytes.is_none() && config.max_tokens.is_none() {
+            return Err(QuickctxError::Config(
+                "copy.max_file_fraction needs a --max-tokens or --max-total-bytes budget".into(),
+            ));
+        }
+    }
+    if config.rendering_workers == 0 {
+        return Err(QuickctxError::Config(
+            "copy.rendering_workers must be at least 1".into(),
+        ));
+    }
+
     Ok(config)
 }
 
@@ -327,9 +1302,18 @@ fn build_paste_config(
 }
 
 fn build_update_config(args: &UpdateArgs) -> UpdateConfig {
+    let set_check_updates = if args.disable {
+        Some(false)
+    } else if args.enable {
+        Some(true)
+    } else {
+        None
+    };
+
     UpdateConfig {
         check_only: args.check_only,
         yes: args.yes,
+        set_check_updates,
     }
 }
 
@@ -348,30 +1332,79 @@ fn to_utf8_path(path: PathBuf) -> Result<Utf8PathBuf> {
         .map_err(|p| QuickctxError::InvalidUtfPath(p.to_string_lossy().into_owned()))
 }
 
-/// Load analyze configuration from a config file
-pub fn load_analyze_config(config_path: Option<&std::path::Path>) -> Result<AnalyzeSection> {
-    if let Some(path) = config_path
-        && path.exists()
-    {
-        let file_config = parse_file_config(
-            &Utf8PathBuf::from_path_buf(path.to_path_buf())
-                .map_err(|p| QuickctxError::InvalidUtfPath(p.to_string_lossy().into_owned()))?,
-        )?;
-        return Ok(file_config.analyze);
+/// JSON Schema describing the config file, for editor completion and validation
+pub fn file_config_schema() -> Result<String> {
+    let schema = schemars::schema_for!(FileConfig);
+    serde_json::to_string_pretty(&schema)
+        .map_err(|err| QuickctxError::Config(format!("failed to serialize schema: {err}")))
+}
+
+/// Load analyze configuration from a config file, layering the `[env.NAME]`
+/// overlay named by `env` on top
+pub fn load_analyze_config(
+    config_path: Option<&std::path::Path>,
+    env: Option<&str>,
+) -> Result<AnalyzeSection> {
+    let path = match config_path.filter(|path| path.exists()) {
+        Some(path) => Some(to_utf8_path(path.to_path_buf())?),
+        // Try default location
+        None => Some(Utf8PathBuf::from("quickctx.toml")).filter(|path| path.exists()),
+    };
+
+    let mut file_config = match path {
+        Some(path) => parse_file_config(&path)?,
+        None => FileConfig::default(),
+    };
+    if let Some(name) = env {
+        file_config.apply_env(name)?;
     }
+    Ok(file_config.analyze)
+}
 
-    // Try default location
-    let default_path = Utf8PathBuf::from("quickctx.toml");
-    if default_path.exists() {
-        let file_config = parse_file_config(&default_path)?;
-        return Ok(file_config.analyze);
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct FileConfig {
+    #[serde(default)]
+    copy: CopySection,
+    #[serde(default)]
+    paste: PasteSection,
+    #[serde(default)]
+    general: GeneralSection,
+    #[serde(default)]
+    analyze: AnalyzeSection,
+    /// Named overlays selected with `--env NAME` or `$QUICKCTX_ENV`
+    #[serde(default)]
+    env: BTreeMap<String, EnvOverlay>,
+}
+
+impl FileConfig {
+    /// Layer `overlay` on top of this config: vecs concatenate, scalars take
+    /// the overlay value when it is set.
+    fn merge(&mut self, overlay: FileConfig) {
+        self.copy.merge(overlay.copy);
+        self.paste.merge(overlay.paste);
+        self.general.merge(overlay.general);
+        self.analyze.merge(overlay.analyze);
+        for (name, env) in overlay.env {
+            self.env.entry(name).or_default().merge(env);
+        }
     }
 
-    Ok(AnalyzeSection::default())
+    /// Layer the `[env.NAME]` overlay on top of the base sections.
+    fn apply_env(&mut self, name: &str) -> Result<()> {
+        let env = self.env.remove(name).ok_or_else(|| {
+            QuickctxError::Config(format!("no [env.{name}] table found in the config files"))
+        })?;
+        self.copy.merge(env.copy);
+        self.paste.merge(env.paste);
+        self.general.merge(env.general);
+        self.analyze.merge(env.analyze);
+        Ok(())
+    }
 }
 
-#[derive(Debug, Default, Deserialize)]
-struct FileConfig {
+/// Per-environment sections layered over the base config
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct EnvOverlay {
     #[serde(default)]
     copy: CopySection,
     #[serde(default)]
@@ -382,11 +1415,27 @@ struct FileConfig {
     analyze: AnalyzeSection,
 }
 
-#[derive(Debug, Default, Deserialize)]
+impl EnvOverlay {
+    fn merge(&mut self, overlay: EnvOverlay) {
+        self.copy.merge(overlay.copy);
+        self.paste.merge(overlay.paste);
+        self.general.merge(overlay.general);
+        self.analyze.merge(overlay.analyze);
+    }
+}
+
+fn merge_option<T>(base: &mut Option<T>, overlay: Option<T>) {
+    if overlay.is_some() {
+        *base = overlay;
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 struct CopySection {
     #[serde(default)]
     paths: Vec<String>,
     #[serde(default)]
+    #[schemars(with = "Option<String>")]
     output: Option<Utf8PathBuf>,
     #[serde(default)]
     format: Option<OutputFormat>,
@@ -395,26 +1444,183 @@ struct CopySection {
     #[serde(default)]
     respect_gitignore: Option<bool>,
     #[serde(default)]
+    respect_global_gitignore: Option<bool>,
+    #[serde(default)]
+    respect_git_exclude: Option<bool>,
+    #[serde(default)]
+    respect_copierignore: Option<bool>,
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
     ignore_files: Vec<Utf8PathBuf>,
     #[serde(default)]
     exclude: Vec<String>,
+    #[serde(default)]
+    exclude_dirs: Vec<String>,
+    #[serde(default)]
+    treat_as_text: Vec<String>,
+    #[serde(default)]
+    treat_as_binary: Vec<String>,
+    #[serde(default)]
+    exclude_tests: Option<bool>,
+    #[serde(default)]
+    include_empty: Option<bool>,
+    #[serde(default)]
+    strip_comments: Option<bool>,
+    #[serde(default)]
+    line_numbers: Option<bool>,
+    #[serde(default)]
+    redact_paths: Option<bool>,
+    #[serde(default)]
+    normalize_paths: Option<bool>,
+    #[serde(default)]
+    note_symlinks: Option<bool>,
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    prefix_paths: Option<Utf8PathBuf>,
+    #[serde(default)]
+    max_total_bytes: Option<u64>,
+    #[serde(default)]
+    max_tokens: Option<u64>,
+    #[serde(default)]
+    max_file_fraction: Option<f64>,
+    #[serde(default)]
+    budget_strategy: Option<BudgetStrategy>,
+    #[serde(default)]
+    heading_level: Option<u8>,
+    #[serde(default)]
+    rendering_workers: Option<usize>,
+    #[serde(default)]
+    fence_lang_map: Option<FenceLangMap>,
+    #[serde(default)]
+    comment_style: Option<CommentStyle>,
+    #[serde(default)]
+    fence_lang_overrides: HashMap<String, String>,
+    #[serde(default)]
+    allow_lossy_paths: Option<bool>,
+    #[serde(default)]
+    annotate_sizes: Option<bool>,
+    #[serde(default)]
+    render_notebooks: Option<bool>,
+    #[serde(default)]
+    front_matter: Option<bool>,
+    #[serde(default)]
+    git_info: Option<bool>,
+    #[serde(default)]
+    deterministic: Option<bool>,
+    #[serde(default)]
+    dedup_contents: Option<bool>,
+    #[serde(default)]
+    append: Option<bool>,
+    #[serde(default)]
+    append_separator: Option<String>,
+    #[serde(default)]
+    prefer_tracked: Option<bool>,
+    #[serde(default)]
+    language_stats: Option<bool>,
+    #[serde(default)]
+    fail_if_empty: Option<bool>,
+    #[serde(default)]
+    with_overview: Option<bool>,
 }
 
-#[derive(Debug, Default, Deserialize)]
+impl CopySection {
+    fn merge(&mut self, overlay: CopySection) {
+        self.paths.extend(overlay.paths);
+        self.ignore_files.extend(overlay.ignore_files);
+        self.exclude.extend(overlay.exclude);
+        self.exclude_dirs.extend(overlay.exclude_dirs);
+        self.treat_as_text.extend(overlay.treat_as_text);
+        self.treat_as_binary.extend(overlay.treat_as_binary);
+        merge_option(&mut self.output, overlay.output);
+        merge_option(&mut self.format, overlay.format);
+        merge_option(&mut self.fence, overlay.fence);
+        merge_option(&mut self.respect_gitignore, overlay.respect_gitignore);
+        merge_option(
+            &mut self.respect_global_gitignore,
+            overlay.respect_global_gitignore,
+        );
+        merge_option(&mut self.respect_git_exclude, overlay.respect_git_exclude);
+        merge_option(&mut self.respect_copierignore, overlay.respect_copierignore);
+        merge_option(&mut self.exclude_tests, overlay.exclude_tests);
+        merge_option(&mut self.include_empty, overlay.include_empty);
+        merge_option(&mut self.strip_comments, overlay.strip_comments);
+        merge_option(&mut self.line_numbers, overlay.line_numbers);
+        merge_option(&mut self.redact_paths, overlay.redact_paths);
+        merge_option(&mut self.normalize_paths, overlay.normalize_paths);
+        merge_option(&mut self.note_symlinks, overlay.note_symlinks);
+        merge_option(&mut self.prefix_paths, overlay.prefix_paths);
+        merge_option(&mut self.max_total_bytes, overlay.max_total_bytes);
+        merge_option(&mut self.max_tokens, overlay.max_tokens);
+        merge_option(&mut self.max_file_fraction, overlay.max_file_fraction);
+        merge_option(&mut self.budget_strategy, overlay.budget_strategy);
+        merge_option(&mut self.heading_level, overlay.heading_level);
+        merge_option(&mut self.rendering_workers, overlay.rendering_workers);
+        self.fence_lang_overrides
+            .extend(overlay.fence_lang_overrides);
+        merge_option(&mut self.fence_lang_map, overlay.fence_lang_map);
+        merge_option(&mut self.comment_style, overlay.comment_style);
+        merge_option(&mut self.allow_lossy_paths, overlay.allow_lossy_paths);
+        merge_option(&mut self.annotate_sizes, overlay.annotate_sizes);
+        merge_option(&mut self.render_notebooks, overlay.render_notebooks);
+        merge_option(&mut self.front_matter, overlay.front_matter);
+        merge_option(&mut self.git_info, overlay.git_info);
+        merge_option(&mut self.deterministic, overlay.deterministic);
+        merge_option(&mut self.dedup_contents, overlay.dedup_contents);
+        merge_option(&mut self.append, overlay.append);
+        merge_option(&mut self.append_separator, overlay.append_separator);
+        merge_option(&mut self.prefer_tracked, overlay.prefer_tracked);
+        merge_option(&mut self.language_stats, overlay.language_stats);
+        merge_option(&mut self.fail_if_empty, overlay.fail_if_empty);
+        merge_option(&mut self.with_overview, overlay.with_overview);
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 struct PasteSection {
     #[serde(default)]
+    #[schemars(with = "Option<String>")]
     output_dir: Option<Utf8PathBuf>,
     #[serde(default)]
     conflict: Option<ConflictStrategy>,
+    #[serde(default)]
+    rewrite: Vec<String>,
 }
 
-#[derive(Debug, Default, Deserialize)]
+impl PasteSection {
+    fn merge(&mut self, overlay: PasteSection) {
+        self.rewrite.extend(overlay.rewrite);
+        merge_option(&mut self.output_dir, overlay.output_dir);
+        merge_option(&mut self.conflict, overlay.conflict);
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 struct GeneralSection {
     #[serde(default)]
     verbose: Option<u8>,
+    #[serde(default)]
+    check_updates: Option<bool>,
 }
 
-#[derive(Debug, Default, Deserialize, Clone)]
+impl GeneralSection {
+    fn merge(&mut self, overlay: GeneralSection) {
+        merge_option(&mut self.verbose, overlay.verbose);
+        merge_option(&mut self.check_updates, overlay.check_updates);
+    }
+}
+
+/// How `quickctx-analyze` decides whether a cached result is still fresh
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheKeyMode {
+    /// File size and modification time (fast, but misses on `touch` or checkout)
+    #[default]
+    Mtime,
+    /// SHA-256 of the file contents
+    Content,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema, Clone)]
 pub struct AnalyzeSection {
     #[serde(default)]
     pub format: Option<String>,
@@ -422,10 +1628,35 @@ pub struct AnalyzeSection {
     pub lsp_servers: std::collections::HashMap<String, String>,
     #[serde(default)]
     pub bin_paths: Vec<String>,
+    /// Extra environment variables for spawned LSP servers
+    #[serde(default)]
+    pub lsp_env: std::collections::HashMap<String, String>,
     #[serde(default)]
     pub lsp_readiness_timeout_secs: Option<u64>,
     #[serde(default)]
     pub enable_cache: Option<bool>,
     #[serde(default)]
     pub cache_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub cache_key: Option<CacheKeyMode>,
+    /// Glob patterns of files never passed to the language server
+    #[serde(default)]
+    pub skip: Vec<String>,
+}
+
+impl AnalyzeSection {
+    fn merge(&mut self, overlay: AnalyzeSection) {
+        self.lsp_servers.extend(overlay.lsp_servers);
+        self.bin_paths.extend(overlay.bin_paths);
+        self.lsp_env.extend(overlay.lsp_env);
+        self.skip.extend(overlay.skip);
+        merge_option(&mut self.format, overlay.format);
+        merge_option(
+            &mut self.lsp_readiness_timeout_secs,
+            overlay.lsp_readiness_timeout_secs,
+        );
+        merge_option(&mut self.enable_cache, overlay.enable_cache);
+        merge_option(&mut self.cache_dir, overlay.cache_dir);
+        merge_option(&mut self.cache_key, overlay.cache_key);
+    }
 }
@@ -1,72 +1,365 @@
 use std::collections::BTreeSet;
 use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use tracing::{debug, warn};
 
-use crate::config::{AppContext, CopyConfig};
+use crate::config::{AppContext, CopyConfig, OutputFormat, TEST_EXCLUDES};
 use crate::error::{QuickctxError, Result};
 use crate::utils;
 
-use super::FileEntry;
+use super::git;
 use super::glob_expansion;
+use super::overview;
+use super::pack;
+use super::pipe;
 use super::walker_config::WalkerConfigBuilder;
+use super::{CollectStats, FileEntry};
 
 /// Collects file entries based on the provided configuration.
-pub fn collect_entries(context: &AppContext, config: &CopyConfig) -> Result<Vec<FileEntry>> {
-    let excludes = build_exclude_set(&config.excludes)?;
-    let paths = expand_all_inputs(context, config)?;
-    let mut entries = process_paths(paths, context, config, excludes.as_ref())?;
+///
+/// Entries are sorted by relative path, except when a manifest is given: then
+/// exactly the listed files are collected in the order they appear. The stats
+/// count files that were found but skipped as binary or excluded.
+pub fn collect_entries(
+    context: &AppContext,
+    config: &CopyConfig,
+) -> Result<(Vec<FileEntry>, CollectStats)> {
+    let (mut entries, mut stats) = gather_entries(context, config)?;
+    if let Some(fraction) = config.max_file_fraction {
+        drop_oversized(&mut entries, fraction, config, &mut stats);
+    }
+    if config.with_overview {
+        prepend_overview(&mut entries, context, config)?;
+    }
+    if config.git_info {
+        let paths: Vec<&Path> = entries.iter().map(FileEntry::source_path).collect();
+        let notes = git::last_commit_notes(&paths);
+        for (entry, note) in entries.iter_mut().zip(notes) {
+            entry.git_info = note;
+        }
+    }
+    // Duplicates are resolved per rendered document, once budgets and splits are known
+    if config.dedup_contents && matches!(config.format, OutputFormat::Heredoc) {
+        return Err(QuickctxError::InvalidArgument(
+            "--dedup-contents cannot be used with the heredoc format".into(),
+        ));
+    }
+    Ok((entries, stats))
+}
+
+/// Drops entries that would take more than `fraction` of the byte or token budget on their own.
+fn drop_oversized(
+    entries: &mut Vec<FileEntry>,
+    fraction: f64,
+    config: &CopyConfig,
+    stats: &mut CollectStats,
+) {
+    let share = |cap: u64| (cap as f64 * fraction) as usize;
+    let byte_limit = config.max_total_bytes.map(share);
+    let token_limit = config.max_tokens.map(share);
+    entries.retain(|entry| {
+        let bytes = entry.contents.len();
+        let tokens = utils::estimate_tokens(&entry.contents);
+        if byte_limit.is_some_and(|limit| bytes > limit) {
+            warn!(
+                path = %entry.relative,
+                bytes,
+                limit = byte_limit,
+                "skipping file larger than --max-file-fraction of --max-total-bytes"
+            );
+        } else if token_limit.is_some_and(|limit| tokens > limit) {
+            warn!(
+                path = %entry.relative,
+                tokens,
+                limit = token_limit,
+                "skipping file larger than --max-file-fraction of --max-tokens"
+            );
+        } else {
+            return true;
+        }
+        stats.skipped_over_budget += 1;
+        false
+    });
+}
+
+/// Moves the project README and manifest to the front, adding them if they were not collected.
+///
+/// Excludes do not apply to them, but the usual binary and empty-file checks do.
+fn prepend_overview(
+    entries: &mut Vec<FileEntry>,
+    context: &AppContext,
+    config: &CopyConfig,
+) -> Result<()> {
+    let mut overview = Vec::new();
+    for path in overview::overview_paths(&context.cwd) {
+        let mut skipped = CollectStats::default();
+        add_file(
+            &path,
+            None,
+            context,
+            config,
+            None,
+            &mut skipped,
+            &mut overview,
+        )?;
+    }
+
+    let overview_files: Vec<PathBuf> = overview
+        .iter()
+        .filter_map(|entry| fs::canonicalize(entry.source_path()).ok())
+        .collect();
+    entries.retain(|entry| {
+        fs::canonicalize(entry.source_path()).map_or(true, |path| !overview_files.contains(&path))
+    });
+    entries.splice(0..0, overview);
+    Ok(())
+}
+
+/// Reads the manifest or walks the inputs, in output order.
+fn gather_entries(
+    context: &AppContext,
+    config: &CopyConfig,
+) -> Result<(Vec<FileEntry>, CollectStats)> {
+    let tests: &[&str] = if config.exclude_tests {
+        TEST_EXCLUDES
+    } else {
+        &[]
+    };
+    let excludes = build_exclude_set(&config.excludes, tests, &config.exclude_dirs)?;
+    let mut stats = CollectStats::default();
+
+    if let Some(manifest) = &config.manifest {
+        if !config.inputs.is_empty() {
+            warn!(manifest = %manifest, "input paths are ignored when a manifest is given");
+        }
+        let entries =
+            collect_from_manifest(manifest, context, config, excludes.as_ref(), &mut stats)?;
+        return Ok((entries, stats));
+    }
+
+    if let Some(pack) = &config.pack {
+        if !config.inputs.is_empty() {
+            warn!(pack = %pack, "input paths are ignored when a pack is given");
+        }
+        let mut entries = Vec::new();
+        let paths = pack::resolve_pack(pack, context, config)?;
+        add_files(
+            &paths,
+            context,
+            config,
+            excludes.as_ref(),
+            &mut stats,
+            &mut entries,
+        )?;
+        return Ok((entries, stats));
+    }
+
+    let mut non_utf8 = Vec::new();
+    let paths = expand_all_inputs(context, config, &mut non_utf8)?;
+
+    // A lone file named on the command line is copied whatever its size
+    let unlimited;
+    let config = if config.max_file_size.is_some()
+        && non_utf8.is_empty()
+        && paths.len() == 1
+        && paths.first().is_some_and(|path| path.is_file())
+    {
+        unlimited = CopyConfig {
+            max_file_size: None,
+            ..config.clone()
+        };
+        &unlimited
+    } else {
+        config
+    };
+    let mut entries = process_paths(paths, context, config, excludes.as_ref(), &mut stats)?;
+    for path in non_utf8 {
+        add_non_utf8_file(
+            &path,
+            context,
+            config,
+            excludes.as_ref(),
+            &mut stats,
+            &mut entries,
+        )?;
+    }
 
     entries.sort_by(|a, b| a.relative.cmp(&b.relative));
-    Ok(entries)
+    Ok((entries, stats))
 }
 
-/// Expands all input paths/globs and deduplicates them.
-fn expand_all_inputs(context: &AppContext, config: &CopyConfig) -> Result<BTreeSet<Utf8PathBuf>> {
+/// Expands all input paths/globs, plus files changed in a commit range, and
+/// deduplicates them. Non-UTF-8 glob matches are collected into `non_utf8`.
+fn expand_all_inputs(
+    context: &AppContext,
+    config: &CopyConfig,
+    non_utf8: &mut Vec<PathBuf>,
+) -> Result<BTreeSet<Utf8PathBuf>> {
     let mut paths = BTreeSet::new();
 
     for input in &config.inputs {
-        let expanded = glob_expansion::expand_input(context, input)?;
+        let expanded = glob_expansion::expand_input(context, input, non_utf8)?;
         for path in expanded {
             paths.insert(path);
         }
     }
 
+    // The walk drops gitignored files, so bring back the ones git still tracks
+    if config.prefer_tracked && config.respect_gitignore {
+        let dirs: Vec<Utf8PathBuf> = paths.iter().filter(|p| p.is_dir()).cloned().collect();
+        for dir in dirs {
+            match git::tracked_ignored_files(&dir) {
+                Ok(tracked) => paths.extend(tracked),
+                Err(err) => warn!(dir = %dir, error = %err, "cannot list tracked files"),
+            }
+        }
+    }
+
+    if let Some(range) = &config.since_commit {
+        paths.extend(git::changed_files_in_range(&context.cwd, range)?);
+    }
+
+    if let Some(tag) = &config.since_tag {
+        paths.extend(git::changed_files_since_tag(&context.cwd, tag.as_deref())?);
+    }
+
+    if let Some(list) = &config.paths_from {
+        paths.extend(read_path_list(list, config.null_separated, context)?);
+    }
+
     Ok(paths)
 }
 
+/// Reads literal paths from a list file, or stdin when the list is `-`.
+///
+/// Entries are newline-separated, or NUL-separated when `null_separated` is
+/// set so names containing spaces or newlines survive. They are not
+/// glob-expanded and resolve against the working directory.
+fn read_path_list(
+    list: &Utf8Path,
+    null_separated: bool,
+    context: &AppContext,
+) -> Result<Vec<Utf8PathBuf>> {
+    let contents = if list == "-" {
+        let mut buffer = String::new();
+        std::io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    } else {
+        let list_path = context.cwd.join(list);
+        fs::read_to_string(list_path.as_std_path()).map_err(|e| {
+            QuickctxError::Io(std::io::Error::new(e.kind(), format!("{list_path}: {e}")))
+        })?
+    };
+
+    let paths = if null_separated {
+        contents.split('\0').collect::<Vec<_>>()
+    } else {
+        contents.lines().collect()
+    };
+
+    Ok(paths
+        .into_iter()
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| context.cwd.join(entry))
+        .collect())
+}
+
 /// Processes a collection of paths, walking directories and collecting file entries.
 fn process_paths(
     paths: BTreeSet<Utf8PathBuf>,
     context: &AppContext,
     config: &CopyConfig,
-    excludes: Option<&GlobSet>,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
 ) -> Result<Vec<FileEntry>> {
     let mut entries = Vec::new();
+    let mut files = Vec::new();
 
     for path in paths {
+        if config.note_symlinks && path.is_symlink() {
+            add_symlink_note(&path, context, config, excludes, stats, &mut entries)?;
+            continue;
+        }
+
         let metadata = fs::metadata(path.as_std_path())?;
         if metadata.is_dir() {
-            collect_from_directory(&path, context, config, excludes, &mut entries)?;
+            collect_from_directory(
+                &path,
+                context,
+                config,
+                excludes,
+                stats,
+                &mut entries,
+                &mut files,
+            )?;
         } else if metadata.is_file() {
-            try_add_file_entry(&path, context, config, excludes, &mut entries)?;
+            files.push(path);
         } else {
             debug!(path = %path, "skipping non-regular path");
         }
     }
 
+    add_files(&files, context, config, excludes, stats, &mut entries)?;
     Ok(entries)
 }
 
-/// Walks a directory and collects all file entries within it.
+/// Reads a manifest of relative paths and collects them in listed order.
+///
+/// Blank lines and `#` comments are ignored. Paths are resolved against the
+/// working directory, and any listed path that is not a file is an error.
+fn collect_from_manifest(
+    manifest: &Utf8Path,
+    context: &AppContext,
+    config: &CopyConfig,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
+) -> Result<Vec<FileEntry>> {
+    let manifest_path = context.cwd.join(manifest);
+    let contents = fs::read_to_string(manifest_path.as_std_path()).map_err(|e| {
+        QuickctxError::Io(std::io::Error::new(
+            e.kind(),
+            format!("{manifest_path}: {e}"),
+        ))
+    })?;
+
+    let mut files = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let listed = line.trim();
+        if listed.is_empty() || listed.starts_with('#') {
+            continue;
+        }
+
+        let path = context.cwd.join(listed);
+        if !path.is_file() {
+            return Err(QuickctxError::InvalidArgument(format!(
+                "{manifest}:{}: file not found: {listed}",
+                index + 1
+            )));
+        }
+
+        files.push(path);
+    }
+
+    let mut entries = Vec::new();
+    add_files(&files, context, config, excludes, stats, &mut entries)?;
+    Ok(entries)
+}
+
+/// Walks a directory, queueing regular files in `files` for [`add_files`].
+///
+/// Symlink notes and files with non-UTF-8 names are added to `entries` directly.
 fn collect_from_directory(
     dir: &Utf8Path,
     context: &AppContext,
     config: &CopyConfig,
-    excludes: Option<&GlobSet>,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
     entries: &mut Vec<FileEntry>,
+    files: &mut Vec<Utf8PathBuf>,
 ) -> Result<()> {
     let walker = WalkerConfigBuilder::from_config(dir, config)
         .build()
@@ -86,63 +379,324 @@ fn collect_from_directory(
             None => continue,
         };
 
-        if !file_type.is_file() {
+        // Symlinks must be caught here: the walker does not follow them, so
+        // they would otherwise be dropped by the regular-file check below
+        let is_noted_symlink = config.note_symlinks && file_type.is_symlink();
+        if !file_type.is_file() && !is_noted_symlink {
             continue;
         }
 
         let path = match Utf8PathBuf::from_path_buf(dir_entry.into_path()) {
             Ok(p) => p,
+            Err(p) if is_noted_symlink => {
+                warn!(path = %p.to_string_lossy(), "skipping non-utf8 symlink");
+                continue;
+            }
             Err(p) => {
-                warn!(path = %p.to_string_lossy(), "skipping non-utf8 path");
+                add_non_utf8_file(&p, context, config, excludes, stats, entries)?;
                 continue;
             }
         };
 
-        try_add_file_entry(&path, context, config, excludes, entries)?;
+        if is_noted_symlink {
+            add_symlink_note(&path, context, config, excludes, stats, entries)?;
+        } else {
+            files.push(path);
+        }
     }
 
     Ok(())
 }
 
-/// Attempts to add a file entry, applying exclusion rules and binary file detection.
+/// Reads and adds `files` on `config.jobs` threads, keeping their order.
+///
+/// Each thread reads a contiguous batch into its own entries and stats, and
+/// the batches are joined in order, so the result is the same as reading the
+/// files one after another.
+fn add_files(
+    files: &[Utf8PathBuf],
+    context: &AppContext,
+    config: &CopyConfig,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
+    entries: &mut Vec<FileEntry>,
+) -> Result<()> {
+    if config.jobs <= 1 || files.len() < 2 {
+        for path in files {
+            try_add_file_entry(path, context, config, excludes, stats, entries)?;
+        }
+        return Ok(());
+    }
+
+    let per_worker = files.len().div_ceil(config.jobs);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(per_worker)
+            .map(|batch| {
+                scope.spawn(move || -> Result<_> {
+                    let mut batch_stats = CollectStats::default();
+                    let mut batch_entries = Vec::new();
+                    for path in batch {
+                        try_add_file_entry(
+                            path,
+                            context,
+                            config,
+                            excludes,
+                            &mut batch_stats,
+                            &mut batch_entries,
+                        )?;
+                    }
+                    Ok((batch_entries, batch_stats))
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let (batch_entries, batch_stats) = handle.join().expect("file reader panicked")?;
+            entries.extend(batch_entries);
+            stats.skipped_binary += batch_stats.skipped_binary;
+            stats.skipped_excluded += batch_stats.skipped_excluded;
+        }
+        Ok(())
+    })
+}
+
+/// Attempts to add a file entry, applying exclusion rules, empty-file skipping,
+/// binary file detection and optional comment stripping.
 fn try_add_file_entry(
     path: &Utf8Path,
     context: &AppContext,
-    _config: &CopyConfig,
-    excludes: Option<&GlobSet>,
+    config: &CopyConfig,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
+    entries: &mut Vec<FileEntry>,
+) -> Result<()> {
+    add_file(path, None, context, config, excludes, stats, entries)
+}
+
+/// Adds a file whose path is not valid UTF-8 under its lossily decoded name,
+/// or skips it with a warning unless `allow_lossy_paths` is set.
+fn add_non_utf8_file(
+    path: &Path,
+    context: &AppContext,
+    config: &CopyConfig,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
     entries: &mut Vec<FileEntry>,
 ) -> Result<()> {
-    if excludes.is_some_and(|e| e.is_match(path.as_std_path())) {
+    if !config.allow_lossy_paths {
+        warn!(path = %path.to_string_lossy(), "skipping non-utf8 path");
+        return Ok(());
+    }
+    if !path.is_file() {
+        warn!(path = %path.to_string_lossy(), "skipping non-utf8 path that is not a file");
+        return Ok(());
+    }
+
+    let lossy = Utf8PathBuf::from(path.to_string_lossy().into_owned());
+    warn!(path = %lossy, "including non-utf8 path under a lossy name");
+    add_file(
+        &lossy,
+        Some(path),
+        context,
+        config,
+        excludes,
+        stats,
+        entries,
+    )
+}
+
+/// Reads and adds one file; `source` is the real path when `path` is a lossy rendering.
+fn add_file(
+    path: &Utf8Path,
+    source: Option<&Path>,
+    context: &AppContext,
+    config: &CopyConfig,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
+    entries: &mut Vec<FileEntry>,
+) -> Result<()> {
+    let source_path = source.unwrap_or(path.as_std_path());
+    if is_excluded(excludes, source_path, &context.cwd) {
         debug!(path = %path, "excluded by pattern");
+        stats.skipped_excluded += 1;
         return Ok(());
     }
 
-    let bytes = fs::read(path.as_std_path())?;
-    if utils::is_probably_binary(&bytes) {
+    let forced_text = has_listed_extension(path, &config.treat_as_text);
+    if !forced_text && has_listed_extension(path, &config.treat_as_binary) {
+        debug!(path = %path, "skipping file treated as binary");
+        stats.skipped_binary += 1;
+        return Ok(());
+    }
+
+    if let Some(limit) = config.max_file_size {
+        let size = fs::metadata(source_path)?.len();
+        if size > limit {
+            warn!(path = %path, size, limit, "skipping file larger than --max-file-size");
+            stats.skipped_excluded += 1;
+            return Ok(());
+        }
+    }
+
+    let bytes = fs::read(source_path)?;
+    if bytes.is_empty() && !config.include_empty {
+        debug!(path = %path, "skipping empty file");
+        return Ok(());
+    }
+    if !forced_text && utils::is_probably_binary(&bytes) {
         warn!(path = %path, "skipping binary file");
+        stats.skipped_binary += 1;
         return Ok(());
     }
 
-    let contents = String::from_utf8_lossy(&bytes).into_owned();
-    let relative = utils::relative_to(path, &context.cwd);
-    let language = utils::language_for_path(path).map(ToString::to_string);
+    let mut contents = String::from_utf8_lossy(&bytes).into_owned();
+    let relative = entry_relative(path, context, config);
+    let mut language = utils::language_for_path(path).map(ToString::to_string);
+
+    if config.render_notebooks
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+    {
+        match utils::render_notebook(&contents) {
+            Ok(rendered) => {
+                contents = rendered;
+                language = Some("markdown".to_string());
+            }
+            Err(err) => warn!(path = %path, error = %err, "keeping notebook as raw JSON"),
+        }
+    }
+
+    if config.strip_comments
+        && let Some(language) = &language
+    {
+        contents = utils::strip_comments(language, &contents);
+    }
+
+    if let Some(command) = &config.pipe_through
+        && let Some(piped) = pipe::pipe_through(command, path, &contents)
+    {
+        contents = piped;
+    }
 
     entries.push(FileEntry {
         absolute: path.to_owned(),
         relative,
         contents,
         language,
+        source: source.map(Path::to_path_buf),
+        git_info: None,
     });
 
     Ok(())
 }
 
-/// Builds a GlobSet from exclude patterns.
-fn build_exclude_set(patterns: &[String]) -> Result<Option<GlobSet>> {
-    if patterns.is_empty() {
+/// Checks a path's extension against a list such as `dat` or `.pbtxt`, ignoring case.
+fn has_listed_extension(path: &Utf8Path, extensions: &[String]) -> bool {
+    path.extension().is_some_and(|ext| {
+        extensions
+            .iter()
+            .any(|listed| listed.trim_start_matches('.').eq_ignore_ascii_case(ext))
+    })
+}
+
+/// Path an entry is shown under: relative to the working directory when inside it.
+///
+/// With `--deterministic`, files outside it are shown with `..` segments too,
+/// so no absolute path ends up in the output.
+fn entry_relative(path: &Utf8Path, context: &AppContext, config: &CopyConfig) -> Utf8PathBuf {
+    let relative = utils::relative_to(path, &context.cwd);
+    if config.deterministic && relative.is_absolute() {
+        utils::relative_path_from(path, &context.cwd)
+    } else {
+        relative
+    }
+}
+
+/// Adds a note recording where a symlink points, without reading its target.
+fn add_symlink_note(
+    path: &Utf8Path,
+    context: &AppContext,
+    config: &CopyConfig,
+    excludes: Option<&ExcludeSet>,
+    stats: &mut CollectStats,
+    entries: &mut Vec<FileEntry>,
+) -> Result<()> {
+    if is_excluded(excludes, path.as_std_path(), &context.cwd) {
+        debug!(path = %path, "excluded by pattern");
+        stats.skipped_excluded += 1;
+        return Ok(());
+    }
+
+    let target = fs::read_link(path.as_std_path())?;
+    entries.push(FileEntry {
+        absolute: path.to_owned(),
+        relative: entry_relative(path, context, config),
+        contents: format!("symlink -> {}\n", target.display()),
+        language: None,
+        source: None,
+        git_info: None,
+    });
+
+    Ok(())
+}
+
+/// Exclude globs, split by the paths they are matched against
+struct ExcludeSet {
+    /// Every glob, matched against the path relative to the working directory
+    relative: GlobSet,
+    /// The `--exclude` patterns, also matched against the absolute path
+    absolute: GlobSet,
+}
+
+/// Whether an exclude pattern matches `path`
+///
+/// Only `--exclude` patterns see the absolute path, so `/abs/dir/**` works.
+/// The built-in test globs and `--exclude-dir` names would otherwise hit a
+/// directory above the project, such as `**/tests/**`, and drop every file.
+fn is_excluded(excludes: Option<&ExcludeSet>, path: &Path, cwd: &Utf8Path) -> bool {
+    excludes.is_some_and(|set| {
+        // Walking `.` yields `cwd/./dir/file`; components() drops the `.`
+        let path: PathBuf = path.components().collect();
+        set.relative
+            .is_match(path.strip_prefix(cwd).unwrap_or(&path))
+            || set.absolute.is_match(&path)
+    })
+}
+
+/// Builds the exclude sets from `--exclude` patterns, test globs and directory names.
+///
+/// Each excluded directory name expands to `**/NAME` and `**/NAME/**`, so the
+/// directory is dropped at any depth while files merely sharing its name
+/// (such as `NAME.txt`) are kept.
+fn build_exclude_set(
+    patterns: &[String],
+    tests: &[&str],
+    dirs: &[String],
+) -> Result<Option<ExcludeSet>> {
+    if patterns.is_empty() && tests.is_empty() && dirs.is_empty() {
         return Ok(None);
     }
 
+    let dir_patterns = dirs.iter().flat_map(|name| {
+        let name = name.trim_matches('/');
+        [format!("**/{name}"), format!("**/{name}/**")]
+    });
+    let builtin: Vec<String> = tests
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .chain(dir_patterns)
+        .collect();
+
+    Ok(Some(ExcludeSet {
+        relative: glob_set(patterns.iter().chain(&builtin))?,
+        absolute: glob_set(patterns)?,
+    }))
+}
+
+/// Compiles exclude patterns into one set, rejecting invalid globs
+fn glob_set<'a>(patterns: impl IntoIterator<Item = &'a String>) -> Result<GlobSet> {
     let mut builder = GlobSetBuilder::new();
     for pattern in patterns {
         let glob = Glob::new(pattern).map_err(|err| {
@@ -153,6 +707,5 @@ fn build_exclude_set(patterns: &[String]) -> Result<Option<GlobSet>> {
 
     builder
         .build()
-        .map(Some)
         .map_err(|err| QuickctxError::InvalidArgument(format!("failed to build glob set: {err}")))
 }
@@ -0,0 +1,37 @@
+use camino::Utf8PathBuf;
+
+use crate::config::{AppContext, CopyConfig, OutputFormat};
+use crate::utils;
+
+use super::output_template;
+
+/// YAML front matter describing a generated document, or `None` when the
+/// format has no place for it
+///
+/// Holds the UTC generation time, the tool version, the working directory
+/// (redacted with `redact_paths`) and the number of files. With
+/// `deterministic` the time is the unix epoch and the directory is `.`.
+pub fn front_matter(context: &AppContext, config: &CopyConfig, files: usize) -> Option<String> {
+    if !config.front_matter || config.format == OutputFormat::Heredoc {
+        return None;
+    }
+
+    let cwd = if config.deterministic {
+        ".".to_string()
+    } else if config.redact_paths {
+        let home = dirs::home_dir().and_then(|home| Utf8PathBuf::from_path_buf(home).ok());
+        utils::redact_path(&context.cwd, home.as_deref())
+    } else {
+        context.cwd.to_string()
+    };
+
+    Some(format!(
+        "---\ngenerated: {}\ntool: {} {}\ncwd: {}\nfiles: {}\n---\n\n",
+        output_template::format_timestamp(output_template::now_or_epoch(config.deterministic)),
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        // A JSON string is a valid double-quoted YAML scalar
+        serde_json::Value::from(cwd),
+        files
+    ))
+}
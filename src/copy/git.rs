@@ -0,0 +1,245 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use tracing::{debug, warn};
+
+use crate::error::{QuickctxError, Result};
+
+/// Runs `git` with the given arguments inside `cwd` and returns its stdout.
+///
+/// A non-zero exit status is turned into an error carrying git's stderr.
+pub fn run_git(cwd: &Utf8Path, args: &[&str]) -> Result<String> {
+    debug!(cwd = %cwd, ?args, "running git");
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd.as_std_path())
+        .output()
+        .map_err(|err| QuickctxError::Git(format!("failed to run git: {err}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(QuickctxError::Git(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Describes the last commit touching each of `paths` as `last modified <sha> by <author> on <date>`.
+///
+/// Files are grouped by repository and each history is walked once with
+/// `git log --name-only`, stopping as soon as every file has been seen. A note
+/// is `None` when its file is outside a git repository or not committed.
+pub fn last_commit_notes(paths: &[&Path]) -> Vec<Option<String>> {
+    let mut notes = vec![None; paths.len()];
+
+    // Repository root -> path relative to it -> indices into `paths`
+    let mut repos: BTreeMap<PathBuf, HashMap<String, Vec<usize>>> = BTreeMap::new();
+    for (index, path) in paths.iter().enumerate() {
+        let Ok(path) = path.canonicalize() else {
+            continue;
+        };
+        let Some(root) = path
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join(".git").exists())
+        else {
+            continue;
+        };
+        let Some(relative) = path.strip_prefix(root).ok().and_then(Path::to_str) else {
+            continue;
+        };
+        repos
+            .entry(root.to_path_buf())
+            .or_default()
+            .entry(relative.replace('\\', "/"))
+            .or_default()
+            .push(index);
+    }
+
+    for (root, mut wanted) in repos {
+        if let Err(err) = walk_last_commits(&root, &mut wanted, &mut notes) {
+            debug!(root = %root.display(), %err, "git log walk failed");
+        }
+    }
+    notes
+}
+
+/// Assigns the newest commit naming each `wanted` path to its notes.
+fn walk_last_commits(
+    root: &Path,
+    wanted: &mut HashMap<String, Vec<usize>>,
+    notes: &mut [Option<String>],
+) -> std::io::Result<()> {
+    debug!(root = %root.display(), files = wanted.len(), "walking git log");
+    let mut child = Command::new("git")
+        .args([
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "--date=short",
+            "--name-only",
+            "--format=%x00%h%x09%an%x09%ad",
+        ])
+        .current_dir(root)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let mut note = None;
+    for line in BufReader::new(stdout).split(b'\n') {
+        let line = line?;
+        let line = String::from_utf8_lossy(&line);
+        if let Some(header) = line.strip_prefix('\0') {
+            let mut fields = header.splitn(3, '\t');
+            note = match (fields.next(), fields.next(), fields.next()) {
+                (Some(sha), Some(author), Some(date)) if !sha.is_empty() => {
+                    Some(format!("last modified {sha} by {author} on {date}"))
+                }
+                _ => None,
+            };
+        } else if let Some(indices) = wanted.remove(line.as_ref()) {
+            for index in indices {
+                notes[index] = note.clone();
+            }
+            if wanted.is_empty() {
+                break;
+            }
+        }
+    }
+
+    // The rest of the history is not needed once every file was found
+    let _ = child.kill();
+    child.wait()?;
+    Ok(())
+}
+
+/// Lists files changed in a commit range (`A..B`) that still exist on disk.
+///
+/// Paths are resolved against `cwd`; files deleted by the range are skipped
+/// with a warning.
+pub fn changed_files_in_range(cwd: &Utf8Path, range: &str) -> Result<Vec<Utf8PathBuf>> {
+    validate_range(range)?;
+
+    let stdout = run_git(cwd, &["diff", "--name-only", "--relative", range, "--"])?;
+
+    let mut files = Vec::new();
+    for line in stdout.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let path = cwd.join(line);
+        if path.is_file() {
+            files.push(path);
+        } else {
+            warn!(path = %line, "skipping file no longer present in working tree");
+        }
+    }
+
+    Ok(files)
+}
+
+/// Lists files under `dir` that git tracks even though a gitignore rule matches them.
+pub fn tracked_ignored_files(dir: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    let stdout = run_git(
+        dir,
+        &["ls-files", "--cached", "--ignored", "--exclude-standard"],
+    )?;
+
+    Ok(stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| dir.join(line))
+        .filter(|path| path.is_file())
+        .collect())
+}
+
+/// Lists files changed between `tag` and `HEAD`, defaulting to the most recent tag.
+pub fn changed_files_since_tag(cwd: &Utf8Path, tag: Option<&str>) -> Result<Vec<Utf8PathBuf>> {
+    let tag = match tag {
+        Some(tag) => tag.to_string(),
+        None => latest_tag(cwd)?,
+    };
+    changed_files_in_range(cwd, &format!("{tag}..HEAD"))
+}
+
+/// Resolves the most recent tag reachable from `HEAD`.
+fn latest_tag(cwd: &Utf8Path) -> Result<String> {
+    let no_tags = || {
+        QuickctxError::Git(
+            "no git tags found; create one with `git tag <name>` or pass --since-tag <TAG>"
+                .to_string(),
+        )
+    };
+
+    // Check for tags first so other git failures are reported as they are
+    if run_git(cwd, &["tag", "--list"])?.trim().is_empty() {
+        return Err(no_tags());
+    }
+
+    let stdout = run_git(cwd, &["describe", "--tags", "--abbrev=0"])?;
+    Ok(stdout.trim().to_string())
+}
+
+/// Lists files that differ between `rev` and the working tree and still exist.
+///
+/// Paths are resolved against `cwd`, which must be inside a git repository.
+pub fn changed_files_since(cwd: &Utf8Path, rev: &str) -> Result<Vec<Utf8PathBuf>> {
+    if !is_valid_rev(rev) {
+        return Err(QuickctxError::InvalidArgument(format!(
+            "invalid git revision '{rev}'"
+        )));
+    }
+
+    let stdout = run_git(cwd, &["diff", "--name-only", "--relative", rev, "--"])?;
+
+    Ok(stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| cwd.join(line))
+        .filter(|path| path.is_file())
+        .collect())
+}
+
+/// Checks that a range has the `A..B` shape with two non-empty revisions.
+fn validate_range(range: &str) -> Result<()> {
+    let invalid =
+        || QuickctxError::InvalidArgument(format!("invalid commit range '{range}': expected A..B"));
+
+    let (from, to) = range.split_once("..").ok_or_else(invalid)?;
+    if !is_valid_rev(from) || !is_valid_rev(to) {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+/// Rejects revisions that git could mistake for options or ranges.
+fn is_valid_rev(rev: &str) -> bool {
+    !rev.is_empty()
+        && !rev.starts_with('-')
+        && !rev.starts_with('.')
+        && !rev.chars().any(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_range() {
+        assert!(validate_range("v1.0..HEAD").is_ok());
+        assert!(validate_range("abc123..def456").is_ok());
+        assert!(validate_range("HEAD").is_err());
+        assert!(validate_range("..HEAD").is_err());
+        assert!(validate_range("HEAD..").is_err());
+        assert!(validate_range("A...B").is_err());
+        assert!(validate_range("--output=x..HEAD").is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use camino::{Utf8Path, Utf8PathBuf};
 use glob::glob;
 use tracing::warn;
@@ -7,10 +9,15 @@ use crate::error::{QuickctxError, Result};
 use crate::utils;
 
 /// Expands a single input string (which may be a path or glob pattern) into
-/// a vector of concrete paths.
-pub fn expand_input(context: &AppContext, raw: &str) -> Result<Vec<Utf8PathBuf>> {
+/// a vector of concrete paths. Glob matches that are not valid UTF-8 are
+/// pushed to `non_utf8` for the caller to include or skip.
+pub fn expand_input(
+    context: &AppContext,
+    raw: &str,
+    non_utf8: &mut Vec<PathBuf>,
+) -> Result<Vec<Utf8PathBuf>> {
     if utils::looks_like_glob(raw) {
-        expand_glob_pattern(context, raw)
+        expand_glob_pattern(context, raw, non_utf8)
     } else {
         expand_simple_path(context, raw)
     }
@@ -29,7 +36,11 @@ fn expand_simple_path(context: &AppContext, raw: &str) -> Result<Vec<Utf8PathBuf
 }
 
 /// Expands a glob pattern into a vector of matching paths.
-fn expand_glob_pattern(context: &AppContext, pattern: &str) -> Result<Vec<Utf8PathBuf>> {
+fn expand_glob_pattern(
+    context: &AppContext,
+    pattern: &str,
+    non_utf8: &mut Vec<PathBuf>,
+) -> Result<Vec<Utf8PathBuf>> {
     let pattern = normalize_glob_pattern(context, pattern);
 
     let mut paths = Vec::new();
@@ -39,9 +50,7 @@ fn expand_glob_pattern(context: &AppContext, pattern: &str) -> Result<Vec<Utf8Pa
         match entry {
             Ok(path) => match Utf8PathBuf::from_path_buf(path) {
                 Ok(p) => paths.push(p),
-                Err(p) => {
-                    warn!(path = %p.to_string_lossy(), "skipping non-utf8 glob match");
-                }
+                Err(p) => non_utf8.push(p),
             },
             Err(err) => {
                 warn!(error = %err, "glob expansion error");
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use super::FileEntry;
+use crate::utils;
+
+/// Label for files whose language was not detected
+const UNKNOWN_LANGUAGE: &str = "other";
+
+/// Files and bytes collected for one language, as reported by `copy --language-stats`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageStat {
+    pub language: String,
+    pub files: usize,
+    pub bytes: usize,
+}
+
+impl std::fmt::Display for LanguageStat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<16} {:>6} files {:>10}",
+            self.language,
+            self.files,
+            utils::human_size(self.bytes as u64)
+        )
+    }
+}
+
+/// Groups entries by language, largest total size first
+pub(super) fn tally(entries: &[FileEntry]) -> Vec<LanguageStat> {
+    let mut by_language: HashMap<&str, LanguageStat> = HashMap::new();
+    for entry in entries {
+        let language = entry.language.as_deref().unwrap_or(UNKNOWN_LANGUAGE);
+        let stat = by_language.entry(language).or_insert_with(|| LanguageStat {
+            language: language.to_string(),
+            files: 0,
+            bytes: 0,
+        });
+        stat.files += 1;
+        stat.bytes += entry.contents.len();
+    }
+
+    let mut stats: Vec<LanguageStat> = by_language.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+    stats
+}
@@ -1,15 +1,28 @@
 mod collector;
+mod front_matter;
+mod git;
 mod glob_expansion;
+mod language_stats;
+mod output_template;
+mod overview;
+mod pack;
+mod pipe;
 mod walker_config;
 
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
 use tracing::debug;
 
 use crate::config::{AppContext, CopyConfig};
-use crate::error::Result;
+use crate::error::{QuickctxError, Result};
 use crate::render;
+use crate::utils;
+
+pub use git::changed_files_since;
+pub use language_stats::LanguageStat;
 
 #[derive(Debug, Clone)]
 pub struct FileEntry {
@@ -17,21 +30,267 @@ pub struct FileEntry {
     pub relative: Utf8PathBuf,
     pub contents: String,
     pub language: Option<String>,
+    /// Real on-disk path when `absolute` is a lossy rendering of a non-UTF-8 name
+    pub source: Option<PathBuf>,
+    /// Last commit touching the file, shown under its header with `git_info`
+    pub git_info: Option<String>,
+}
+
+/// Size of a rendered copy document, as reported by `copy --estimate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopyEstimate {
+    pub files: usize,
+    pub bytes: usize,
+    pub tokens: usize,
+}
+
+/// Files found while collecting but left out of the document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectStats {
+    pub skipped_binary: usize,
+    pub skipped_excluded: usize,
+    /// Dropped by `max_file_fraction` for taking too much of the budget
+    pub skipped_over_budget: usize,
 }
 
-pub fn run(context: &AppContext, config: CopyConfig) -> Result<()> {
+/// Run statistics written by `copy --summary-json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CopySummary {
+    pub files: usize,
+    pub bytes: usize,
+    pub tokens_estimate: usize,
+    pub skipped_binary: usize,
+    pub skipped_excluded: usize,
+    pub skipped_over_budget: usize,
+    /// Output path, or `None` when the document went to stdout
+    pub output: Option<Utf8PathBuf>,
+}
+
+impl std::fmt::Display for CopyEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files, {} bytes, ~{} tokens",
+            self.files, self.bytes, self.tokens
+        )
+    }
+}
+
+impl FileEntry {
+    /// Path to read the file from on disk.
+    pub fn source_path(&self) -> &Path {
+        self.source
+            .as_deref()
+            .unwrap_or(self.absolute.as_std_path())
+    }
+}
+
+pub fn run(context: &AppContext, mut config: CopyConfig) -> Result<()> {
+    if config.estimate {
+        eprintln!("{}", estimate(context, &config)?);
+        return Ok(());
+    }
+
     config.require_inputs()?;
+    if config.append && config.output.is_none() {
+        return Err(QuickctxError::InvalidArgument(
+            "--append requires --output".into(),
+        ));
+    }
+    let split = match (config.chunk, config.split_by_size) {
+        (Some(_), _) => Some(("--chunk", chunk_path as fn(&Utf8Path, usize) -> Utf8PathBuf)),
+        (None, Some(_)) => Some(("--split-by-size", part_path as _)),
+        (None, None) => None,
+    };
+    if let Some((flag, _)) = split
+        && config.append
+    {
+        return Err(QuickctxError::InvalidArgument(format!(
+            "--append cannot be combined with {flag}"
+        )));
+    }
+    if let Some(output) = &config.output {
+        config.output = Some(output_template::expand_output_path(output, &context.cwd)?);
+    }
+
+    let rendered = collect_and_render(context, &config)?;
+    if rendered.entries.is_empty() && config.fail_if_empty {
+        return Err(QuickctxError::InvalidArgument(
+            "no files matched the inputs (--fail-if-empty)".into(),
+        ));
+    }
+    if config.language_stats {
+        for stat in language_stats::tally(&rendered.entries) {
+            eprintln!("{stat}");
+        }
+    }
+
+    match split {
+        Some((flag, path_for)) => write_chunks(&config, &rendered.documents, flag, path_for)?,
+        None => write_output(&config, &rendered.documents.concat())?,
+    }
 
-    let entries = collector::collect_entries(context, &config)?;
-    let document = render::render_entries(&entries, &config)?;
+    if let Some(manifest) = &config.mtime_manifest {
+        write_mtimes(manifest, &rendered.entries, &config)?;
+    }
 
-    write_output(&config, &document)?;
+    if let Some(path) = &config.summary_json {
+        let document = rendered.documents.concat();
+        let summary = CopySummary {
+            files: rendered.entries.len(),
+            bytes: document.len(),
+            tokens_estimate: utils::estimate_tokens(&document),
+            skipped_binary: rendered.stats.skipped_binary,
+            skipped_excluded: rendered.stats.skipped_excluded,
+            skipped_over_budget: rendered.stats.skipped_over_budget,
+            output: config.output.clone(),
+        };
+        write_summary(path, &summary)?;
+    }
 
     Ok(())
 }
 
+/// Collects and renders like [`run`], returning the document instead of writing it.
+///
+/// The output path, estimate and mtime manifest settings are ignored, so
+/// nothing is written to disk or stdout. With `chunk` or `split_by_size` the
+/// documents that [`run`] would write to separate files are concatenated.
+pub fn run_to_string(context: &AppContext, config: &CopyConfig) -> Result<String> {
+    config.require_inputs()?;
+    Ok(collect_and_render(context, config)?.documents.concat())
+}
+
+/// Collects and renders like [`run`], but measures the document instead of writing it.
+///
+/// With `chunk` or `split_by_size` the sizes of all documents are summed.
+pub fn estimate(context: &AppContext, config: &CopyConfig) -> Result<CopyEstimate> {
+    config.require_inputs()?;
+
+    let rendered = collect_and_render(context, config)?;
+    let document = rendered.documents.concat();
+
+    Ok(CopyEstimate {
+        files: rendered.entries.len(),
+        bytes: document.len(),
+        tokens: utils::estimate_tokens(&document),
+    })
+}
+
+/// Collects like [`run`] and groups the files by language, largest total size first.
+pub fn language_stats(context: &AppContext, config: &CopyConfig) -> Result<Vec<LanguageStat>> {
+    config.require_inputs()?;
+    let (entries, _) = collector::collect_entries(context, config)?;
+    Ok(language_stats::tally(&entries))
+}
+
+/// Rendered entries and the documents holding them
+struct Rendered {
+    /// Entries in the documents, leaving out any the budget cut
+    entries: Vec<FileEntry>,
+    stats: CollectStats,
+    /// One document, or one per chunk or part with `chunk` / `split_by_size`
+    documents: Vec<String>,
+}
+
+/// Collects and renders the documents every entry point shares, without writing anything
+fn collect_and_render(context: &AppContext, config: &CopyConfig) -> Result<Rendered> {
+    let (mut entries, stats) = collector::collect_entries(context, config)?;
+
+    // Counting every entry can only overstate the final header's size
+    let reserved = front_matter::front_matter(context, config, entries.len()).unwrap_or_default();
+    let rendered = render::render_documents(&entries, config, &reserved)?;
+    let mut included = rendered.included.into_iter().peekable();
+    let mut index = 0;
+    entries.retain(|_| {
+        let keep = included.next_if_eq(&index).is_some();
+        index += 1;
+        keep
+    });
+    let mut documents = rendered.documents;
+
+    // The front matter describes the whole run, so only the first document carries it
+    if let Some(first) = documents.first_mut()
+        && let Some(header) = front_matter::front_matter(context, config, entries.len())
+    {
+        first.insert_str(0, &header);
+    }
+
+    Ok(Rendered {
+        entries,
+        stats,
+        documents,
+    })
+}
+
+fn write_summary(path: &Utf8Path, summary: &CopySummary) -> Result<()> {
+    let json = serde_json::to_string_pretty(summary).map_err(|e| {
+        QuickctxError::InvalidArgument(format!("{path}: failed to serialize summary: {e}"))
+    })?;
+    utils::write_with_parent(path, json.as_bytes())?;
+    debug!(path = %path, "wrote copy summary");
+    Ok(())
+}
+
+/// Records modification times keyed by the paths as they appear in the output.
+fn write_mtimes(manifest: &Utf8Path, entries: &[FileEntry], config: &CopyConfig) -> Result<()> {
+    let paths = render::display_paths(entries, config);
+    let mut mtimes = utils::MtimeManifest::new();
+    for (entry, path) in entries.iter().zip(paths) {
+        mtimes.insert(path, utils::modified_secs(entry.source_path())?);
+    }
+
+    utils::write_mtime_manifest(manifest, &mtimes)?;
+    debug!(path = %manifest, files = mtimes.len(), "wrote mtime manifest");
+    Ok(())
+}
+
+/// Writes chunks next to `--output`, numbered from 1 by `path_for`
+fn write_chunks(
+    config: &CopyConfig,
+    chunks: &[String],
+    flag: &str,
+    path_for: fn(&Utf8Path, usize) -> Utf8PathBuf,
+) -> Result<()> {
+    let output = config.output.as_ref().ok_or_else(|| {
+        QuickctxError::InvalidArgument(format!("{flag} requires --output to name the output files"))
+    })?;
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let path = path_for(output, index + 1);
+        utils::write_with_parent(&path, chunk.as_bytes())?;
+        debug!(path = %path, "wrote chunk");
+    }
+    Ok(())
+}
+
+/// `--chunk` file name: `NAME.001.EXT`, `NAME.002.EXT`, ...
+fn chunk_path(output: &Utf8Path, number: usize) -> Utf8PathBuf {
+    let stem = output.file_stem().unwrap_or("output");
+    let name = match output.extension() {
+        Some(ext) => format!("{stem}.{number:03}.{ext}"),
+        None => format!("{stem}.{number:03}"),
+    };
+    output.with_file_name(name)
+}
+
+/// `--split-by-size` file name: `NAME.1.EXT` ... `NAME.N.EXT`
+fn part_path(output: &Utf8Path, number: usize) -> Utf8PathBuf {
+    let stem = output.file_stem().unwrap_or("output");
+    let name = match output.extension() {
+        Some(ext) => format!("{stem}.{number}.{ext}"),
+        None => format!("{stem}.{number}"),
+    };
+    output.with_file_name(name)
+}
+
 fn write_output(config: &CopyConfig, document: &str) -> Result<()> {
     if let Some(output) = &config.output {
+        if config.append {
+            append_output(output, document, &config.append_separator)?;
+            debug!(path = %output, "appended copied markdown");
+            return Ok(());
+        }
         crate::utils::write_with_parent(output, document.as_bytes())?;
         debug!(path = %output, "wrote copied markdown");
     } else {
@@ -40,3 +299,17 @@ fn write_output(config: &CopyConfig, document: &str) -> Result<()> {
     }
     Ok(())
 }
+
+/// Appends `document` to `output`, after the separator when the file already has content
+fn append_output(output: &Utf8Path, document: &str, separator: &str) -> Result<()> {
+    let has_content = output
+        .as_std_path()
+        .metadata()
+        .is_ok_and(|meta| meta.len() > 0);
+    let block = if has_content {
+        format!("\n{separator}\n\n{document}")
+    } else {
+        document.to_string()
+    };
+    utils::append_with_parent(output, block.as_bytes())
+}
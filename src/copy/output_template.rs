@@ -0,0 +1,94 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::error::{QuickctxError, Result};
+
+use super::git;
+
+/// Expands placeholders in an `--output` path.
+///
+/// Supported placeholders are `{date}` (`YYYY-MM-DD`), `{time}` (`HH-MM-SS`),
+/// `{git_sha}` (short `HEAD` sha) and `{cwd_name}` (name of the working
+/// directory). Dates and times are in UTC. Other braces are left untouched.
+pub fn expand_output_path(template: &Utf8Path, cwd: &Utf8Path) -> Result<Utf8PathBuf> {
+    let template = template.as_str();
+    if !template.contains('{') {
+        return Ok(Utf8PathBuf::from(template));
+    }
+
+    let (date, time) = format_utc(SystemTime::now());
+
+    let mut expanded = template
+        .replace("{date}", &date)
+        .replace("{time}", &time)
+        .replace("{cwd_name}", cwd.file_name().unwrap_or("root"));
+
+    if expanded.contains("{git_sha}") {
+        let sha = git::run_git(cwd, &["rev-parse", "--short", "HEAD"]).map_err(|err| {
+            QuickctxError::InvalidArgument(format!(
+                "output path uses {{git_sha}} but the HEAD commit could not be read: {err}"
+            ))
+        })?;
+        expanded = expanded.replace("{git_sha}", sha.trim());
+    }
+
+    Ok(Utf8PathBuf::from(expanded))
+}
+
+/// The current time, or the unix epoch when output must be reproducible
+pub(super) fn now_or_epoch(deterministic: bool) -> SystemTime {
+    if deterministic {
+        UNIX_EPOCH
+    } else {
+        SystemTime::now()
+    }
+}
+
+/// Formats a time as an RFC 3339 UTC timestamp (`YYYY-MM-DDTHH:MM:SSZ`).
+pub(super) fn format_timestamp(at: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(at).to_string()
+}
+
+/// Formats a time as a UTC `YYYY-MM-DD` date and `HH-MM-SS` time.
+fn format_utc(at: SystemTime) -> (String, String) {
+    let timestamp = format_timestamp(at);
+    (
+        timestamp[..10].to_string(),
+        timestamp[11..19].replace(':', "-"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(
+            format_utc(now_or_epoch(true)),
+            ("1970-01-01".to_string(), "00-00-00".to_string())
+        );
+        // 2024-02-29T13:05:09Z, a leap day
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_211_909);
+        assert_eq!(
+            format_utc(leap_day),
+            ("2024-02-29".to_string(), "13-05-09".to_string())
+        );
+        assert_eq!(format_timestamp(leap_day), "2024-02-29T13:05:09Z");
+    }
+
+    #[test]
+    fn test_expand_leaves_plain_paths_and_unknown_braces() {
+        let cwd = Utf8Path::new("/work/project");
+        assert_eq!(
+            expand_output_path(Utf8Path::new("out.md"), cwd).unwrap(),
+            "out.md"
+        );
+        assert_eq!(
+            expand_output_path(Utf8Path::new("{cwd_name}-{other}.md"), cwd).unwrap(),
+            "project-{other}.md"
+        );
+    }
+}
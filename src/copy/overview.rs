@@ -0,0 +1,43 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use tracing::debug;
+
+use crate::analyze::{detect_project_root, project_manifest};
+
+/// README names tried in the project root, first match wins
+const README_NAMES: &[&str] = &[
+    "README.md",
+    "README",
+    "README.rst",
+    "README.txt",
+    "readme.md",
+];
+
+/// README and primary manifest of the project containing `cwd`, in that order.
+///
+/// The project root is found the way `analyze` finds it, walking up from the
+/// working directory to the first `Cargo.toml`, `package.json`, ... or `.git`.
+/// Paths keep the spelling of `cwd` so they display like other collected files.
+pub(super) fn overview_paths(cwd: &Utf8Path) -> Vec<Utf8PathBuf> {
+    let Some((root, project_type)) = detect_project_root(cwd.as_std_path())
+        .ok()
+        .and_then(|(root, project_type)| Some((spelled_from(cwd, &root)?, project_type)))
+    else {
+        return Vec::new();
+    };
+    debug!(root = %root, ?project_type, "collecting project overview");
+
+    let readme = README_NAMES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file());
+    let manifest = project_manifest(root.as_std_path(), project_type)
+        .and_then(|path| Some(root.join(path.file_name()?.to_str()?)));
+    readme.into_iter().chain(manifest).collect()
+}
+
+/// `root` (canonical) as an ancestor of `cwd` spelled the way `cwd` is
+fn spelled_from(cwd: &Utf8Path, root: &std::path::Path) -> Option<Utf8PathBuf> {
+    let canonical_cwd = cwd.as_std_path().canonicalize().ok()?;
+    let depth = canonical_cwd.strip_prefix(root).ok()?.components().count();
+    cwd.ancestors().nth(depth).map(Utf8Path::to_path_buf)
+}
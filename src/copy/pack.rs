@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use globset::{GlobBuilder, GlobMatcher};
+use tracing::warn;
+
+use super::walker_config::WalkerConfigBuilder;
+use crate::config::{AppContext, CopyConfig};
+use crate::error::{QuickctxError, Result};
+
+/// One line of a pack file, relative to the working directory
+struct PackRule {
+    /// `!` lines remove files selected by earlier lines
+    negated: bool,
+    pattern: PackPattern,
+}
+
+enum PackPattern {
+    /// An existing directory, walked with the usual ignore rules
+    Dir(Utf8PathBuf),
+    /// A file path or glob, expanded without walking
+    Glob { raw: String, matcher: GlobMatcher },
+}
+
+impl PackPattern {
+    fn matches(&self, relative: &Utf8Path) -> bool {
+        match self {
+            Self::Dir(dir) => relative.starts_with(dir),
+            Self::Glob { matcher, .. } => matcher.is_match(relative.as_std_path()),
+        }
+    }
+}
+
+/// Resolves a pack file into the files it selects, in order of first selection.
+///
+/// Lines are globs, file paths or directories, with `#` comments and blank
+/// lines ignored. As in a gitignore, the last line matching a file decides
+/// whether it is kept, so `!pattern` drops files picked by earlier lines.
+pub(super) fn resolve_pack(
+    pack: &Utf8Path,
+    context: &AppContext,
+    config: &CopyConfig,
+) -> Result<Vec<Utf8PathBuf>> {
+    let pack_path = context.cwd.join(pack);
+    let contents = fs::read_to_string(pack_path.as_std_path()).map_err(|e| {
+        QuickctxError::Io(std::io::Error::new(e.kind(), format!("{pack_path}: {e}")))
+    })?;
+    let rules = parse_rules(pack, &contents, &context.cwd)?;
+
+    let mut candidates = Vec::new();
+    let mut seen = HashSet::new();
+    for rule in rules.iter().filter(|rule| !rule.negated) {
+        let matched = expand(&rule.pattern, context, config)?;
+        if matched.is_empty()
+            && let PackPattern::Glob { raw, .. } = &rule.pattern
+        {
+            warn!(pack = %pack, pattern = %raw, "pack line matched no files");
+        }
+        for relative in matched {
+            if seen.insert(relative.clone()) {
+                candidates.push(relative);
+            }
+        }
+    }
+
+    Ok(candidates
+        .into_iter()
+        .filter(|relative| {
+            rules
+                .iter()
+                .rev()
+                .find(|rule| rule.pattern.matches(relative))
+                .is_some_and(|rule| !rule.negated)
+        })
+        .map(|relative| context.cwd.join(relative))
+        .collect())
+}
+
+fn parse_rules(pack: &Utf8Path, contents: &str, cwd: &Utf8Path) -> Result<Vec<PackRule>> {
+    let mut rules = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (negated, raw) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, line),
+        };
+        let raw = raw.strip_prefix("./").unwrap_or(raw).trim_end_matches('/');
+
+        let pattern = if cwd.join(raw).is_dir() {
+            PackPattern::Dir(Utf8PathBuf::from(raw))
+        } else {
+            let matcher = GlobBuilder::new(raw)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    QuickctxError::InvalidArgument(format!(
+                        "{pack}:{}: invalid pattern {raw}: {e}",
+                        index + 1
+                    ))
+                })?
+                .compile_matcher();
+            PackPattern::Glob {
+                raw: raw.to_string(),
+                matcher,
+            }
+        };
+        rules.push(PackRule { negated, pattern });
+    }
+    Ok(rules)
+}
+
+/// Files selected by one include line, relative to the working directory and sorted
+fn expand(
+    pattern: &PackPattern,
+    context: &AppContext,
+    config: &CopyConfig,
+) -> Result<Vec<Utf8PathBuf>> {
+    let absolute: Vec<Utf8PathBuf> = match pattern {
+        PackPattern::Dir(dir) => WalkerConfigBuilder::from_config(&context.cwd.join(dir), config)
+            .build()
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+            .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.into_path()).ok())
+            .collect(),
+        PackPattern::Glob { raw, .. } => {
+            let pattern = context.cwd.join(raw);
+            glob::glob(pattern.as_str())
+                .map_err(|e| QuickctxError::InvalidArgument(e.to_string()))?
+                .filter_map(|entry| entry.ok())
+                .filter(|path| path.is_file())
+                .filter_map(|path| Utf8PathBuf::from_path_buf(path).ok())
+                .collect()
+        }
+    };
+
+    let mut relative: Vec<Utf8PathBuf> = absolute
+        .into_iter()
+        .filter_map(|path| {
+            path.strip_prefix(&context.cwd)
+                .ok()
+                .map(Utf8Path::to_path_buf)
+        })
+        .collect();
+    relative.sort();
+    Ok(relative)
+}
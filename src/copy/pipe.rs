@@ -0,0 +1,65 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use camino::Utf8Path;
+use tracing::warn;
+
+/// Runs `command` with a file's contents on stdin and returns what it printed.
+///
+/// The command line is run by the shell (`sh -c`, or `cmd /C` on Windows), so
+/// quoting and pipes work as typed. When the command cannot be started or
+/// exits non-zero, the file keeps its contents and a warning names the
+/// command's stderr.
+pub(super) fn pipe_through(command: &str, path: &Utf8Path, contents: &str) -> Option<String> {
+    match run(command, contents) {
+        Ok(output) => Some(output),
+        Err(err) => {
+            warn!(path = %path, command, error = %err, "--pipe-through failed, keeping original contents");
+            None
+        }
+    }
+}
+
+/// The shell invocation that runs `command`
+fn shell(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut shell = Command::new(shell);
+    shell.args([flag, command]);
+    shell
+}
+
+fn run(command: &str, input: &str) -> io::Result<String> {
+    if command.trim().is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty command"));
+    }
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = std::thread::scope(|scope| {
+        // Writing from another thread keeps a command that prints before it has
+        // read all of its input from filling the stdout pipe and deadlocking
+        scope.spawn(move || {
+            // A command may exit without reading everything; its status decides
+            let _ = stdin.write_all(input.as_bytes());
+        });
+        child.wait_with_output()
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "{}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -3,12 +3,23 @@ use ignore::WalkBuilder;
 
 use crate::config::CopyConfig;
 
+/// Per-directory exclude file, read with `.gitignore` syntax and precedence
+pub const EXCLUDE_FILE_NAME: &str = "copier.exclude";
+
+/// Ignore file for copy alone, honored whether or not gitignores are
+pub const COPIERIGNORE_FILE_NAME: &str = ".copierignore";
+
 /// Configuration builder for setting up a directory walker with appropriate
 /// gitignore handling and custom ignore files.
 pub struct WalkerConfigBuilder {
     root: Utf8PathBuf,
     respect_gitignore: bool,
+    respect_global_gitignore: bool,
+    respect_git_exclude: bool,
+    respect_copierignore: bool,
     ignore_files: Vec<Utf8PathBuf>,
+    /// Directory names pruned from the walk at any depth
+    exclude_dirs: Vec<String>,
 }
 
 impl WalkerConfigBuilder {
@@ -17,7 +28,15 @@ impl WalkerConfigBuilder {
         Self {
             root: root.to_owned(),
             respect_gitignore: config.respect_gitignore,
+            respect_global_gitignore: config.respect_global_gitignore,
+            respect_git_exclude: config.respect_git_exclude,
+            respect_copierignore: config.respect_copierignore,
             ignore_files: config.ignore_files.clone(),
+            exclude_dirs: config
+                .exclude_dirs
+                .iter()
+                .map(|name| name.trim_matches('/').to_string())
+                .collect(),
         }
     }
 
@@ -30,11 +49,11 @@ impl WalkerConfigBuilder {
         builder.sort_by_file_name(|a, b| a.cmp(b));
         builder.standard_filters(true);
 
-        // Gitignore configuration
+        // Gitignore configuration; global and exclude files can be turned off separately
         if self.respect_gitignore {
             builder.git_ignore(true);
-            builder.git_global(true);
-            builder.git_exclude(true);
+            builder.git_global(self.respect_global_gitignore);
+            builder.git_exclude(self.respect_git_exclude);
             builder.require_git(false);
         } else {
             builder.git_ignore(false);
@@ -42,11 +61,34 @@ impl WalkerConfigBuilder {
             builder.git_exclude(false);
         }
 
+        // Like gitignores these are also read from parent directories, so the
+        // working directory's file applies when walking one of its subdirectories
+        if self.respect_copierignore {
+            builder.add_custom_ignore_filename(COPIERIGNORE_FILE_NAME);
+        }
+
         // Add custom ignore files
         for ignore_file in &self.ignore_files {
             builder.add_ignore(ignore_file);
         }
 
+        // Exclude files apply to their own subtree; the files themselves are never copied
+        builder.add_custom_ignore_filename(EXCLUDE_FILE_NAME);
+
+        // Excluded directories are not descended into, but the walk root itself is kept
+        let exclude_dirs = self.exclude_dirs;
+        builder.filter_entry(move |entry| {
+            if entry.file_name() == EXCLUDE_FILE_NAME {
+                return false;
+            }
+            let excluded_dir = entry.depth() > 0
+                && entry.file_type().is_some_and(|kind| kind.is_dir())
+                && exclude_dirs
+                    .iter()
+                    .any(|name| entry.file_name() == name.as_str());
+            !excluded_dir
+        });
+
         builder
     }
 }
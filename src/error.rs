@@ -24,6 +24,10 @@ pub enum QuickctxError {
     #[error("markdown parse error: {0}")]
     Markdown(String),
 
+    /// A pasted code block carried no path hint and no fallback name applied
+    #[error("unable to determine file path")]
+    MissingPath,
+
     #[error("operation aborted: {0}")]
     Aborted(String),
 
@@ -32,4 +36,10 @@ pub enum QuickctxError {
 
     #[error("self-update error: {0}")]
     SelfUpdate(String),
+
+    #[error("git error: {0}")]
+    Git(String),
+
+    #[error("Failed to spawn LSP server '{server}': {source}")]
+    LspSpawn { server: String, source: io::Error },
 }
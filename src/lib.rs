@@ -19,13 +19,22 @@ pub fn run(cli: Cli) -> Result<()> {
     telemetry::init(runtime.context.verbosity)?;
 
     // Check for updates in the background (non-blocking, only for non-update commands)
-    if !matches!(runtime.mode, ModeConfig::Update(_)) {
+    if runtime.check_updates
+        && !matches!(
+            runtime.mode,
+            ModeConfig::Update(_) | ModeConfig::ConfigSchema
+        )
+    {
         let _ = update::check_for_update_background();
     }
 
     match runtime.mode {
-        ModeConfig::Copy(cfg) => copy::run(&runtime.context, cfg),
-        ModeConfig::Paste(cfg) => paste::run(&runtime.context, cfg),
+        ModeConfig::Copy(cfg) => copy::run(&runtime.context, *cfg),
+        ModeConfig::Paste(cfg) => paste::run(&runtime.context, cfg).map(|_| ()),
         ModeConfig::Update(cfg) => update::run(&runtime.context, cfg),
+        ModeConfig::ConfigSchema => {
+            println!("{}", config::file_config_schema()?);
+            Ok(())
+        }
     }
 }
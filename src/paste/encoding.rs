@@ -0,0 +1,50 @@
+use crate::config::InputEncoding;
+
+/// Code points for Windows-1252 bytes 0x80-0x9F; the five bytes it leaves
+/// undefined map to the C1 control of the same value, as browsers do.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Decodes input bytes into a UTF-8 string
+///
+/// UTF-8 input must be valid; the single-byte encodings accept any byte.
+pub fn decode(
+    bytes: Vec<u8>,
+    encoding: InputEncoding,
+) -> Result<String, std::string::FromUtf8Error> {
+    match encoding {
+        InputEncoding::Utf8 => String::from_utf8(bytes),
+        InputEncoding::Latin1 => Ok(bytes.into_iter().map(char::from).collect()),
+        InputEncoding::Windows1252 => Ok(bytes
+            .into_iter()
+            .map(|byte| match byte {
+                0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+                _ => char::from(byte),
+            })
+            .collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_single_byte_encodings() {
+        let bytes = b"caf\xe9 \x93quoted\x94 \x80".to_vec();
+
+        assert_eq!(
+            decode(bytes.clone(), InputEncoding::Latin1).unwrap(),
+            "caf\u{e9} \u{93}quoted\u{94} \u{80}"
+        );
+        assert_eq!(
+            decode(bytes.clone(), InputEncoding::Windows1252).unwrap(),
+            "caf\u{e9} \u{201C}quoted\u{201D} \u{20AC}"
+        );
+        assert!(decode(bytes, InputEncoding::Utf8).is_err());
+    }
+}
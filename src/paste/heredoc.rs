@@ -0,0 +1,104 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::error::{QuickctxError, Result};
+
+use super::FileBlock;
+use super::path_hint;
+
+/// `cat > 'PATH' << 'DELIM'` as written by `copy --format heredoc`
+static CAT_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^cat > '([^']*)' << '([^']+)'$").expect("valid regex"));
+
+/// Whether the input is a heredoc script rather than markdown
+///
+/// The first meaningful line must be a `cat > '...' << '...'` command;
+/// blank lines, comments (including a shebang) and `mkdir -p` lines may
+/// precede it.
+pub fn is_heredoc_script(input: &str) -> bool {
+    input
+        .lines()
+        .map(str::trim_end)
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("mkdir -p "))
+        .is_some_and(|line| CAT_LINE.is_match(line))
+}
+
+/// Parses every `cat` heredoc into a block holding its target path and body
+///
+/// Lines outside heredocs (such as `mkdir -p`) are ignored. A heredoc that
+/// never reaches its closing delimiter is reported as an invalid block.
+pub fn parse_blocks(script: &str) -> Vec<Result<FileBlock>> {
+    let mut blocks = Vec::new();
+    let mut lines = script.split_inclusive('\n');
+
+    while let Some(line) = lines.next() {
+        let Some(captures) = CAT_LINE.captures(line.trim_end_matches(['\r', '\n'])) else {
+            continue;
+        };
+        let raw_path = &captures[1];
+        let delimiter = &captures[2];
+
+        let mut contents = String::new();
+        let mut closed = false;
+        for body_line in lines.by_ref() {
+            if body_line.trim_end_matches(['\r', '\n']) == delimiter {
+                closed = true;
+                break;
+            }
+            contents.push_str(body_line);
+        }
+
+        let block = if closed {
+            path_hint::sanitize_relative(raw_path).map(|path| FileBlock {
+                path: Some(path),
+                contents,
+                append: false,
+            })
+        } else {
+            Err(QuickctxError::Markdown(format!(
+                "heredoc for {raw_path} is missing its closing {delimiter} line"
+            )))
+        };
+        blocks.push(block);
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_heredoc_scripts() {
+        assert!(is_heredoc_script(
+            "mkdir -p 'src'\ncat > 'src/main.rs' << 'EOF'\nfn main() {}\nEOF\n"
+        ));
+        assert!(!is_heredoc_script(
+            "# Title\n\n```sh\ncat > 'a.txt' << 'EOF'\n```\n"
+        ));
+    }
+
+    #[test]
+    fn test_parse_heredoc_blocks() {
+        let script = "mkdir -p 'src'\ncat > 'src/a.rs' << 'EOF'\nEOF1\nfn a() {}\nEOF\n\ncat > 'b.txt' << 'END'\nEOF\nEND\n";
+        let blocks: Vec<FileBlock> = parse_blocks(script)
+            .into_iter()
+            .map(|block| block.unwrap())
+            .collect();
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].path.as_deref().unwrap(), "src/a.rs");
+        assert_eq!(blocks[0].contents, "EOF1\nfn a() {}\n");
+        assert_eq!(blocks[1].path.as_deref().unwrap(), "b.txt");
+        assert_eq!(blocks[1].contents, "EOF\n");
+    }
+
+    #[test]
+    fn test_rejects_unsafe_or_unterminated_heredocs() {
+        let blocks = parse_blocks("cat > '../x' << 'EOF'\nx\nEOF\ncat > 'y' << 'EOF'\ny\n");
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks.iter().all(Result::is_err));
+    }
+}
@@ -1,6 +1,11 @@
+mod encoding;
+mod heredoc;
 mod path_hint;
+mod rewrite;
 
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{self, IsTerminal, Read};
 
 use camino::{Utf8Path, Utf8PathBuf};
@@ -8,29 +13,224 @@ use dialoguer::Confirm;
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 use tracing::{info, warn};
 
-use crate::config::{AppContext, ConflictStrategy, InputSource, PasteConfig};
+use crate::config::{AppContext, ConflictStrategy, InputEncoding, InputSource, PasteConfig};
 use crate::error::{QuickctxError, Result};
 use crate::utils;
 
-pub fn run(_context: &AppContext, config: PasteConfig) -> Result<()> {
-    let markdown = read_input(&config.source)?;
-    let blocks = parse_blocks(&markdown)?;
+/// Outcome of a paste run
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PasteSummary {
+    /// Blocks written (or left in place by the conflict strategy)
+    pub written: usize,
+    /// Blocks skipped because no file path could be determined
+    pub skipped_missing_path: usize,
+    /// Blocks skipped under `keep_going` because their path was invalid
+    pub skipped_invalid: usize,
+    /// Blocks that failed to write under `keep_going`
+    pub failed: usize,
+}
+
+impl PasteSummary {
+    fn skipped(&self) -> usize {
+        self.skipped_missing_path + self.skipped_invalid
+    }
+}
+
+/// Pastes every code block it can, skipping blocks without a path. Fails only
+/// when nothing could be pasted, with the first block error.
+///
+/// A block with an invalid path (absolute, `..`) or that fails to write aborts
+/// the run, unless `keep_going` is set: then invalid blocks are skipped, write
+/// failures are logged, the remaining blocks are written and the run fails at
+/// the end if anything failed to write.
+pub fn run(_context: &AppContext, config: PasteConfig) -> Result<PasteSummary> {
+    let markdown = read_input(&config.source, config.input_encoding)?;
+    let rewrites = rewrite::parse_rules(&config.rewrites)?;
+    let default_name = config
+        .default_name
+        .as_deref()
+        .map(path_hint::sanitize_relative)
+        .transpose()?;
+    let mtimes = config
+        .mtime_manifest
+        .as_deref()
+        .map(utils::read_mtime_manifest)
+        .transpose()?
+        .unwrap_or_default();
+    let blocks = parse_input(&markdown);
+    let base = config
+        .infer_base
+        .then(|| {
+            path_hint::infer_base(
+                blocks
+                    .iter()
+                    .filter_map(|block| block.as_ref().ok()?.path.as_deref()),
+            )
+        })
+        .flatten();
+    if let Some(base) = &base {
+        info!(base = %base, "stripping base directory inferred from the first block");
+    }
+
+    // The fallback name is only unambiguous when a single block lacks a path
+    let unnamed_blocks = blocks
+        .iter()
+        .filter(|block| matches!(block, Ok(FileBlock { path: None, .. })))
+        .count();
+    let fallback = default_name.filter(|_| unnamed_blocks == 1);
+
+    let mut summary = PasteSummary::default();
+    let mut first_error = None;
+    let mut written_by_hash = HashMap::new();
+
+    for (index, block) in blocks.into_iter().enumerate() {
+        let block_number = index + 1;
+        let resolved = block.and_then(|block| {
+            let path = match block.path {
+                Some(path) => path,
+                None => fallback.clone().ok_or(QuickctxError::MissingPath)?,
+            };
+            let relative = base
+                .as_deref()
+                .and_then(|base| path.strip_prefix(base).ok())
+                .unwrap_or(&path);
+            let destination = rewrite::rewrite_path(relative, &rewrites)?;
+            Ok((path, destination, block.contents, block.append))
+        });
+
+        match resolved {
+            Ok((path, destination, contents, append)) => {
+                let outcome = if append || config.append_blocks {
+                    append_block(&config, &destination, &contents)
+                } else {
+                    write_block(&config, &destination, &contents, &mut written_by_hash)
+                }
+                .and_then(|written| {
+                    // The manifest is keyed by the path in the markdown, before rewrites
+                    if written && let Some(&secs) = mtimes.get(path.as_str()) {
+                        utils::set_mtime(&config.output_dir.join(&destination), secs)?;
+                    }
+                    Ok(())
+                });
+                match outcome {
+                    Ok(()) => summary.written += 1,
+                    Err(err) if config.keep_going => {
+                        warn!(block = block_number, path = %destination, error = %err, "failed to write code block");
+                        summary.failed += 1;
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            Err(err @ QuickctxError::MissingPath) => {
+                warn!(block = block_number, error = %err, "skipping code block");
+                summary.skipped_missing_path += 1;
+                first_error.get_or_insert(err);
+            }
+            Err(err) if config.keep_going => {
+                warn!(block = block_number, error = %err, "skipping code block");
+                summary.skipped_invalid += 1;
+                first_error.get_or_insert(err);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    if summary.written == 0
+        && let Some(err) = first_error
+    {
+        return Err(err);
+    }
+
+    if summary.skipped() > 0 {
+        warn!(
+            written = summary.written,
+            missing_path = summary.skipped_missing_path,
+            invalid_path = summary.skipped_invalid,
+            "skipped {} code block(s)",
+            summary.skipped()
+        );
+    }
 
-    for block in blocks {
-        write_block(&config, &block)?;
+    if summary.failed > 0 {
+        return Err(QuickctxError::Aborted(format!(
+            "{} of {} code block(s) failed to write",
+            summary.failed,
+            summary.written + summary.failed
+        )));
     }
 
     info!("paste complete");
-    Ok(())
+    Ok(summary)
+}
+
+/// Parses markdown (or a heredoc script) into a map of path to contents
+/// without touching the filesystem.
+///
+/// Paths are sanitized exactly as [`run`] does; blocks without a usable path
+/// are skipped, and a later block for the same path replaces an earlier one.
+/// Fails only when no block could be extracted, with the first block error.
+pub fn extract_to_map(markdown: &str) -> Result<BTreeMap<Utf8PathBuf, String>> {
+    let mut files = BTreeMap::new();
+    let mut first_error = None;
+
+    for block in parse_input(markdown) {
+        match block {
+            Ok(FileBlock {
+                path: Some(path),
+                contents,
+                ..
+            }) => {
+                files.insert(path, contents);
+            }
+            Ok(FileBlock { path: None, .. }) => {
+                first_error.get_or_insert(QuickctxError::MissingPath);
+            }
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+
+    match first_error {
+        Some(err) if files.is_empty() => Err(err),
+        _ => Ok(files),
+    }
+}
+
+/// Scripts from `copy --format heredoc` are read back as well as markdown
+fn parse_input(input: &str) -> Vec<Result<FileBlock>> {
+    if heredoc::is_heredoc_script(input) {
+        heredoc::parse_blocks(input)
+    } else {
+        parse_blocks(input)
+    }
 }
 
 #[derive(Debug)]
 struct FileBlock {
-    path: Utf8PathBuf,
+    /// `None` when the block carried no path hint at all
+    path: Option<Utf8PathBuf>,
     contents: String,
+    /// Marked with `<!-- append: path -->`
+    append: bool,
 }
 
-fn read_input(source: &InputSource) -> Result<String> {
+fn read_input(source: &InputSource, encoding: InputEncoding) -> Result<String> {
+    if encoding != InputEncoding::Utf8 {
+        let bytes = match source {
+            InputSource::File(path) => fs::read(path.as_std_path()).map_err(|e| {
+                QuickctxError::Io(io::Error::new(e.kind(), format!("{}: {}", path, e)))
+            })?,
+            InputSource::Stdin => {
+                let mut buf = Vec::new();
+                io::stdin().read_to_end(&mut buf)?;
+                buf
+            }
+        };
+        return encoding::decode(bytes, encoding)
+            .map_err(|e| QuickctxError::Io(io::Error::new(io::ErrorKind::InvalidData, e)));
+    }
+
     match source {
         InputSource::File(path) => fs::read_to_string(path.as_std_path())
             .map_err(|e| QuickctxError::Io(io::Error::new(e.kind(), format!("{}: {}", path, e)))),
@@ -116,25 +316,14 @@ impl ParserState {
     }
 
     /// Transition from InCodeBlock state to Idle state, returning the finished block
-    fn transition_to_idle_from_code_block(self) -> Result<(Self, Option<FileBlock>)> {
+    fn transition_to_idle_from_code_block(self) -> (Self, Option<Result<FileBlock>>) {
+        let idle = ParserState::Idle {
+            trailing_text: String::new(),
+            heading_hint: None,
+        };
         match self {
-            ParserState::InCodeBlock { state } => {
-                let block = state.finish()?;
-                Ok((
-                    ParserState::Idle {
-                        trailing_text: String::new(),
-                        heading_hint: None,
-                    },
-                    Some(block),
-                ))
-            }
-            _ => Ok((
-                ParserState::Idle {
-                    trailing_text: String::new(),
-                    heading_hint: None,
-                },
-                None,
-            )),
+            ParserState::InCodeBlock { state } => (idle, Some(state.finish())),
+            _ => (idle, None),
         }
     }
 
@@ -189,7 +378,8 @@ impl ParserState {
     }
 }
 
-fn parse_blocks(markdown: &str) -> Result<Vec<FileBlock>> {
+/// Parses every fenced code block; each entry is the block or why its path was rejected
+fn parse_blocks(markdown: &str) -> Vec<Result<FileBlock>> {
     let mut blocks = Vec::new();
     let mut state = ParserState::Idle {
         trailing_text: String::new(),
@@ -244,7 +434,7 @@ fn parse_blocks(markdown: &str) -> Result<Vec<FileBlock>> {
                         heading_hint: None,
                     },
                 );
-                let (new_state, block) = old_state.transition_to_idle_from_code_block()?;
+                let (new_state, block) = old_state.transition_to_idle_from_code_block();
                 state = new_state;
                 if let Some(block) = block {
                     blocks.push(block);
@@ -263,18 +453,24 @@ fn parse_blocks(markdown: &str) -> Result<Vec<FileBlock>> {
         }
     }
 
-    Ok(blocks)
+    blocks
 }
 
 struct BlockState {
     path_hint: Option<String>,
+    append: bool,
     contents: String,
 }
 
 impl BlockState {
     fn new(path_hint: Option<String>) -> Self {
+        let append_target = path_hint
+            .as_deref()
+            .and_then(path_hint::append_target)
+            .map(str::to_string);
         Self {
-            path_hint,
+            append: append_target.is_some(),
+            path_hint: append_target.or(path_hint),
             contents: String::new(),
         }
     }
@@ -291,38 +487,153 @@ impl BlockState {
         // Priority order:
         // 1. Comment hint inside code block (most explicit)
         // 2. Path hint from heading or trailing text
-        let path = if let Some(comment_path) = path_hint::extract_comment_hint(&mut self.contents) {
-            comment_path
-        } else if let Some(hint) = self.path_hint.take() {
-            hint
-        } else {
-            return Err(QuickctxError::Markdown(
-                "unable to determine file path".into(),
-            ));
-        };
-
-        let path = path_hint::sanitize_relative(&path)?;
+        let path = path_hint::extract_comment_hint(&mut self.contents)
+            .or_else(|| self.path_hint.take())
+            .map(|path| path_hint::sanitize_relative(&path))
+            .transpose()?;
 
         Ok(FileBlock {
             path,
             contents: self.contents,
+            append: self.append,
         })
     }
 }
 
-fn write_block(config: &PasteConfig, block: &FileBlock) -> Result<()> {
-    let destination = config.output_dir.join(&block.path);
+/// Writes a block under the output directory; returns false when the conflict strategy kept an existing file
+///
+/// With `hard_link` set, `written_by_hash` maps content hashes to the first
+/// file written with those contents, and later identical blocks are linked to it.
+fn write_block(
+    config: &PasteConfig,
+    path: &Utf8Path,
+    contents: &str,
+    written_by_hash: &mut HashMap<u64, Utf8PathBuf>,
+) -> Result<bool> {
+    let destination = config.output_dir.join(path);
+    ensure_within_output_dir(&config.output_dir, &destination)?;
+
+    // Rewriting identical contents would only bump the mtime and wake file watchers
+    if fs::read(destination.as_std_path()).is_ok_and(|existing| existing == contents.as_bytes()) {
+        info!(path = %destination, "unchanged");
+        if config.hard_link {
+            written_by_hash
+                .entry(content_hash(contents))
+                .or_insert(destination);
+        }
+        return Ok(false);
+    }
 
     if destination.exists() && !should_overwrite(&destination, config.conflict)? {
         warn!(path = %destination, "skipping existing file");
-        return Ok(());
+        return Ok(false);
+    }
+
+    if !config.hard_link {
+        utils::write_with_parent(&destination, contents.as_bytes())?;
+        info!(path = %destination, "wrote file");
+        return Ok(true);
+    }
+
+    let hash = content_hash(contents);
+    if let Some(original) = written_by_hash.get(&hash)
+        && original != &destination
+        && fs::read(original.as_std_path()).is_ok_and(|existing| existing == contents.as_bytes())
+    {
+        match link_to(original, &destination) {
+            Ok(()) => {
+                info!(path = %destination, original = %original, "hard-linked duplicate file");
+                return Ok(true);
+            }
+            Err(err) => {
+                warn!(path = %destination, error = %err, "hard link failed, writing a copy");
+            }
+        }
     }
 
-    utils::write_with_parent(&destination, block.contents.as_bytes())?;
+    // The destination may itself be a link made earlier in this run; replace
+    // it rather than writing through to the file it shares contents with
+    remove_if_exists(&destination)?;
+    utils::write_with_parent(&destination, contents.as_bytes())?;
     info!(path = %destination, "wrote file");
+    written_by_hash.entry(hash).or_insert(destination);
+    Ok(true)
+}
+
+/// Appends a block to the file at `path` under the output directory, creating it if needed
+///
+/// A newline separates the block from what the file already holds. Conflict
+/// strategies and `hard_link` do not apply, and the mtime manifest is not
+/// applied to the grown file.
+fn append_block(config: &PasteConfig, path: &Utf8Path, contents: &str) -> Result<bool> {
+    let destination = config.output_dir.join(path);
+    ensure_within_output_dir(&config.output_dir, &destination)?;
+
+    let existing = fs::metadata(destination.as_std_path()).map_or(0, |meta| meta.len());
+    let data = if existing > 0 {
+        format!("\n{contents}")
+    } else {
+        contents.to_string()
+    };
+    utils::append_with_parent(&destination, data.as_bytes())?;
+    info!(path = %destination, "appended to file");
+    Ok(false)
+}
+
+/// Refuses a destination that resolves outside `output_dir` through a symlink
+///
+/// `sanitize_relative` already rejects `..` and absolute paths, so only links
+/// inside `output_dir` can redirect a write. The deepest part of the
+/// destination that exists is resolved, since missing directories are
+/// created beneath it.
+fn ensure_within_output_dir(output_dir: &Utf8Path, destination: &Utf8Path) -> Result<()> {
+    // Nothing inside a missing output directory can be a symlink yet
+    let Ok(root) = output_dir.as_std_path().canonicalize() else {
+        return Ok(());
+    };
+
+    let mut existing = destination.as_std_path();
+    while existing.symlink_metadata().is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return Ok(()),
+        }
+    }
+
+    // A dangling link fails to resolve and is refused along with escaping ones
+    let inside = existing
+        .canonicalize()
+        .is_ok_and(|resolved| resolved.starts_with(&root));
+    if !inside {
+        return Err(QuickctxError::InvalidArgument(format!(
+            "refusing to write {destination}: it resolves outside {output_dir} through a symlink"
+        )));
+    }
     Ok(())
 }
 
+fn content_hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Replaces `destination` with a hard link to `original`
+fn link_to(original: &Utf8Path, destination: &Utf8Path) -> io::Result<()> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent.as_std_path())?;
+    }
+    remove_if_exists(destination)?;
+    fs::hard_link(original.as_std_path(), destination.as_std_path())
+}
+
+fn remove_if_exists(path: &Utf8Path) -> io::Result<()> {
+    match fs::remove_file(path.as_std_path()) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
 fn should_overwrite(path: &Utf8Path, strategy: ConflictStrategy) -> Result<bool> {
     match strategy {
         ConflictStrategy::Overwrite => Ok(true),
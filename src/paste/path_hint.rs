@@ -1,27 +1,51 @@
-use camino::{Utf8Component, Utf8PathBuf};
+use std::sync::LazyLock;
+
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use regex::Regex;
 
 use crate::error::{QuickctxError, Result};
 
+/// ` (size: 2.3 KB)` written by `copy --annotate-sizes`; the `size:` marker keeps
+/// real file names ending in a parenthesized size intact
+static SIZE_ANNOTATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" \(size: \d+(\.\d)? (B|KB|MB|GB)\)$").expect("valid regex"));
+
+/// `(last modified <sha> by <author> on <date>)` written by `copy --git-info`
+static GIT_INFO_NOTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\(last modified \S+ by .+ on \S+\)$").expect("valid regex"));
+
+/// `<!-- append: path -->`, asking for the block to be appended rather than written
+static APPEND_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^<!--\s*append:\s*(.+?)\s*-->$").expect("valid regex"));
+
+/// Path named by an `<!-- append: path -->` hint, `None` for any other hint
+pub fn append_target(hint: &str) -> Option<&str> {
+    APPEND_MARKER
+        .captures(hint)
+        .and_then(|caps| caps.get(1))
+        .map(|path| path.as_str())
+}
+
 /// Acquires a path hint from trailing text or heading
 ///
 /// Priority order:
 /// 1. Heading with backticks (most explicit)
-/// 2. Last non-empty line of trailing text
+/// 2. Last non-empty line of trailing text, skipping `--git-info` notes
 pub fn acquire_path_hint(trailing_text: &mut String, heading: Option<String>) -> Option<String> {
     // Heading takes priority if it was inline code (wrapped in backticks)
     if let Some(heading) = heading {
         trailing_text.clear();
-        return Some(heading);
+        return Some(strip_size_annotation(&heading).to_string());
     }
 
     // Otherwise, look for trailing text hint
     let candidate = trailing_text.trim();
     let hint = candidate.lines().rev().find_map(|line| {
         let trimmed = line.trim();
-        if trimmed.is_empty() {
+        if trimmed.is_empty() || GIT_INFO_NOTE.is_match(trimmed) {
             None
         } else {
-            Some(trimmed.to_string())
+            Some(strip_size_annotation(trimmed).to_string())
         }
     });
 
@@ -39,12 +63,12 @@ pub fn extract_comment_hint(contents: &mut String) -> Option<String> {
         let marker = format!("{prefix} ");
         if contents.starts_with(&marker) {
             if let Some(idx) = contents.find('\n') {
-                let path = contents[marker.len()..idx].trim().to_string();
+                let path = strip_size_annotation(contents[marker.len()..idx].trim()).to_string();
                 let remainder = contents[idx + 1..].to_string();
                 *contents = remainder;
                 return Some(path);
             } else {
-                let path = contents[marker.len()..].trim().to_string();
+                let path = strip_size_annotation(contents[marker.len()..].trim()).to_string();
                 contents.clear();
                 return Some(path);
             }
@@ -53,6 +77,13 @@ pub fn extract_comment_hint(contents: &mut String) -> Option<String> {
     None
 }
 
+/// Drops a trailing ` (size: 2.3 KB)` written by `copy --annotate-sizes`
+fn strip_size_annotation(hint: &str) -> &str {
+    SIZE_ANNOTATION
+        .find(hint)
+        .map_or(hint, |found| &hint[..found.start()])
+}
+
 /// Sanitizes and validates a relative path
 ///
 /// Ensures:
@@ -84,6 +115,43 @@ pub fn sanitize_relative(raw: &str) -> Result<Utf8PathBuf> {
     Ok(candidate)
 }
 
+/// Leading directory of the first path when it looks like a project root the other paths omit
+///
+/// For `myproject/src/lib.rs` followed by `src/main.rs`, returns `myproject`.
+/// This is a guess, so the first path has to share structure with the others:
+/// no other path may start with the base, and what remains of the first path
+/// must sit under a directory another path also uses. A top-level remainder
+/// never qualifies, since `docs/guide.md` next to `src/main.rs` is just an
+/// ordinary subdirectory.
+pub fn infer_base<'a>(mut paths: impl Iterator<Item = &'a Utf8Path>) -> Option<Utf8PathBuf> {
+    let first = paths.next()?;
+    let rest: Vec<&str> = paths.filter_map(leading_dir).collect();
+    let leading = leading_dir(first)?;
+    if rest.is_empty() || rest.contains(&leading) {
+        return None;
+    }
+
+    let base: Utf8PathBuf = first
+        .components()
+        .take_while(|c| !matches!(c, Utf8Component::Normal(name) if *name == leading))
+        .chain([Utf8Component::Normal(leading)])
+        .collect();
+    let remainder = first.strip_prefix(&base).ok()?;
+    leading_dir(remainder)
+        .filter(|dir| rest.contains(dir))
+        .map(|_| base)
+}
+
+/// First directory of a path, `None` for a bare file name
+fn leading_dir(path: &Utf8Path) -> Option<&str> {
+    let mut names = path.components().filter_map(|c| match c {
+        Utf8Component::Normal(name) => Some(name),
+        _ => None,
+    });
+    let first = names.next()?;
+    names.next().map(|_| first)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,6 +189,29 @@ mod tests {
         assert_eq!(contents, "def hello():");
     }
 
+    #[test]
+    fn test_path_hints_drop_size_annotations() {
+        let mut trailing = String::new();
+        let heading = Some("src/main.rs (size: 2.3 KB)".to_string());
+        assert_eq!(
+            acquire_path_hint(&mut trailing, heading),
+            Some("src/main.rs".to_string())
+        );
+
+        let mut contents = "// notes (v2).txt (size: 12 B)\nbody".to_string();
+        assert_eq!(
+            extract_comment_hint(&mut contents),
+            Some("notes (v2).txt".to_string())
+        );
+
+        // Without the marker the parenthesized size is part of the name
+        let mut trailing = "report (12 B).txt".to_string();
+        assert_eq!(
+            acquire_path_hint(&mut trailing, None),
+            Some("report (12 B).txt".to_string())
+        );
+    }
+
     #[test]
     fn test_sanitize_relative_valid() {
         let result = sanitize_relative("src/main.rs");
@@ -151,4 +242,29 @@ mod tests {
                 .contains("parent directory segments are not allowed")
         );
     }
+
+    #[test]
+    fn test_infer_base_from_first_block() {
+        let infer = |paths: &[&str]| infer_base(paths.iter().map(|p| Utf8Path::new(*p)));
+
+        assert_eq!(
+            infer(&["myproject/src/lib.rs", "src/main.rs", "Cargo.toml"]),
+            Some(Utf8PathBuf::from("myproject"))
+        );
+        assert_eq!(
+            infer(&["./myproject/src/lib.rs", "src/main.rs"]),
+            Some(Utf8PathBuf::from("./myproject"))
+        );
+
+        // The others share the prefix, the remainder fits nowhere, or nothing to compare
+        assert_eq!(
+            infer(&["myproject/README.md", "myproject/src/main.rs"]),
+            None
+        );
+        assert_eq!(infer(&["docs/guide/intro.md", "src/main.rs"]), None);
+        assert_eq!(infer(&["docs/guide.md", "src/main.rs"]), None);
+        assert_eq!(infer(&["myproject/README.md", "src/main.rs"]), None);
+        assert_eq!(infer(&["README.md", "src/main.rs"]), None);
+        assert_eq!(infer(&["myproject/README.md"]), None);
+    }
 }
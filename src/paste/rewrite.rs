@@ -0,0 +1,128 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use regex::Regex;
+
+use crate::error::{QuickctxError, Result};
+
+use super::path_hint;
+
+/// Separator between the pattern and replacement in a rewrite rule
+const RULE_SEPARATOR: &str = "=>";
+
+/// Prefix marking the pattern side of a rule as a regular expression
+const REGEX_PREFIX: &str = "re:";
+
+/// A single path rewrite rule
+///
+/// Rules are written as `FROM=>TO`. A plain `FROM` replaces every occurrence
+/// of that substring, while `re:FROM` is compiled as a regular expression and
+/// `TO` may reference capture groups (`$1`, `${name}`).
+#[derive(Debug, Clone)]
+pub enum PathRewrite {
+    Substring { from: String, to: String },
+    Regex { pattern: Regex, to: String },
+}
+
+impl PathRewrite {
+    /// Parse a rule in `FROM=>TO` form
+    pub fn parse(rule: &str) -> Result<Self> {
+        let (from, to) = rule.split_once(RULE_SEPARATOR).ok_or_else(|| {
+            QuickctxError::InvalidArgument(format!(
+                "invalid rewrite rule '{rule}': expected FROM=>TO"
+            ))
+        })?;
+
+        if let Some(pattern) = from.strip_prefix(REGEX_PREFIX) {
+            let pattern = Regex::new(pattern).map_err(|e| {
+                QuickctxError::InvalidArgument(format!("invalid rewrite regex '{pattern}': {e}"))
+            })?;
+            return Ok(PathRewrite::Regex {
+                pattern,
+                to: to.to_string(),
+            });
+        }
+
+        if from.is_empty() {
+            return Err(QuickctxError::InvalidArgument(format!(
+                "invalid rewrite rule '{rule}': pattern must not be empty"
+            )));
+        }
+
+        Ok(PathRewrite::Substring {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    fn apply(&self, path: &str) -> String {
+        match self {
+            PathRewrite::Substring { from, to } => path.replace(from.as_str(), to),
+            PathRewrite::Regex { pattern, to } => {
+                pattern.replace_all(path, to.as_str()).into_owned()
+            }
+        }
+    }
+}
+
+/// Parse every configured rule, failing on the first invalid one
+pub fn parse_rules(rules: &[String]) -> Result<Vec<PathRewrite>> {
+    rules.iter().map(|rule| PathRewrite::parse(rule)).collect()
+}
+
+/// Apply rewrites in order to an already-sanitized relative path
+///
+/// The result is sanitized again so a rewrite cannot produce an absolute
+/// path or escape the output directory.
+pub fn rewrite_path(path: &Utf8Path, rewrites: &[PathRewrite]) -> Result<Utf8PathBuf> {
+    if rewrites.is_empty() {
+        return Ok(path.to_path_buf());
+    }
+
+    let rewritten = rewrites
+        .iter()
+        .fold(path.as_str().to_string(), |current, rule| {
+            rule.apply(&current)
+        });
+
+    path_hint::sanitize_relative(&rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(path: &str, rules: &[&str]) -> Result<Utf8PathBuf> {
+        let rules: Vec<String> = rules.iter().map(|r| r.to_string()).collect();
+        rewrite_path(Utf8Path::new(path), &parse_rules(&rules)?)
+    }
+
+    #[test]
+    fn test_substring_rewrite() {
+        let path = rewrite("src/old/mod.rs", &["old=>new"]).unwrap();
+        assert_eq!(path, "src/new/mod.rs");
+    }
+
+    #[test]
+    fn test_regex_rewrite_with_captures() {
+        let path = rewrite("pkg/a/index.ts", &[r"re:^pkg/(\w+)/=>lib/$1/"]).unwrap();
+        assert_eq!(path, "lib/a/index.ts");
+    }
+
+    #[test]
+    fn test_rewrites_apply_in_order() {
+        let path = rewrite("a.txt", &["a=>b", "b=>c"]).unwrap();
+        assert_eq!(path, "c.txt");
+    }
+
+    #[test]
+    fn test_rewrite_cannot_escape_output_dir() {
+        assert!(rewrite("src/lib.rs", &["src=>.."]).is_err());
+        assert!(rewrite("src/lib.rs", &["re:^=>/"]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        assert!(PathRewrite::parse("no-separator").is_err());
+        assert!(PathRewrite::parse("=>to").is_err());
+        assert!(PathRewrite::parse("re:(=>x").is_err());
+    }
+}
@@ -1,47 +1,681 @@
-use crate::config::{CopyConfig, FencePreference, OutputFormat};
+use std::collections::HashMap;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use tracing::warn;
+
+use crate::config::{
+    BudgetStrategy, ChunkSize, CopyConfig, FenceLangMap, FencePreference, OutputFormat,
+};
 use crate::copy::FileEntry;
 use crate::error::Result;
+use crate::utils;
 
+/// Render entries into a single document
+///
+/// With `max_total_bytes` or `max_tokens` set, files are left out as chosen by
+/// `budget_strategy` and a truncation marker is appended. By default rendering
+/// stops before the first entry that would push the document past the cap.
+/// Tokens are estimated as by [`utils::estimate_tokens`].
+///
+/// With `rendering_workers` above 1, every block is rendered up front on that
+/// many threads and then joined in entry order, so the document is the same.
 pub fn render_entries(entries: &[FileEntry], config: &CopyConfig) -> Result<String> {
+    Ok(render_single(entries, config, "")?.0)
+}
+
+/// Documents rendered for one `copy` run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedDocuments {
+    /// One document, or one per chunk or part with `chunk` / `split_by_size`
+    pub documents: Vec<String>,
+    /// Indices of the entries in the documents, fewer than given when over budget
+    pub included: Vec<usize>,
+}
+
+/// Render entries into the documents `copy` writes
+///
+/// `reserved` is text the caller puts before the first document, such as the
+/// front matter. It is not included in the documents but counts against the
+/// budget and the first chunk's limit.
+pub fn render_documents(
+    entries: &[FileEntry],
+    config: &CopyConfig,
+    reserved: &str,
+) -> Result<RenderedDocuments> {
+    let documents = match (config.chunk, config.split_by_size) {
+        (Some(limit), _) => chunk_documents(entries, config, limit, reserved)?,
+        (None, Some(parts)) => part_documents(entries, config, parts, reserved)?,
+        (None, None) => {
+            let (document, included) = render_single(entries, config, reserved)?;
+            return Ok(RenderedDocuments {
+                documents: vec![document],
+                included,
+            });
+        }
+    };
+    Ok(RenderedDocuments {
+        documents,
+        included: (0..entries.len()).collect(),
+    })
+}
+
+/// Render one document after `reserved`, returning it with the indices of the entries it holds
+fn render_single(
+    entries: &[FileEntry],
+    config: &CopyConfig,
+    reserved: &str,
+) -> Result<(String, Vec<usize>)> {
     let mut buffer = String::new();
+    let paths = PathDisplay::from_config(config);
+    let limits = budget_limits(config);
 
-    for (idx, entry) in entries.iter().enumerate() {
+    // Other strategies pick the files to leave out up front; by-order cuts while rendering
+    let mut selected: Vec<usize> = (0..entries.len()).collect();
+    let mut dropped = None;
+    if config.budget_strategy != BudgetStrategy::ByOrder && !limits.is_empty() {
+        let full = render_blocks_parallel(
+            entries,
+            &selected,
+            &vec![None; entries.len()],
+            config,
+            &paths,
+            config.rendering_workers,
+        )?;
+        dropped = drop_over_budget(entries, &full, &limits, reserved, config, &mut selected);
+    }
+
+    // Only files in the document can be referred to, and they all precede the cut
+    let mut seen = SeenContents::new(config);
+    let mut originals = vec![None; entries.len()];
+    for &index in &selected {
+        originals[index] = seen.original(&entries[index]);
+    }
+
+    let blocks: Box<dyn Iterator<Item = Result<String>>> = if config.rendering_workers > 1 {
+        Box::new(
+            render_blocks_parallel(
+                entries,
+                &selected,
+                &originals,
+                config,
+                &paths,
+                config.rendering_workers,
+            )?
+            .into_iter()
+            .map(Ok),
+        )
+    } else {
+        Box::new(selected.iter().map(|&index| {
+            let mut block = String::new();
+            render_entry(
+                &entries[index],
+                index,
+                originals[index],
+                config,
+                &paths,
+                &mut block,
+            )?;
+            Ok(block)
+        }))
+    };
+
+    let mut included = selected.len();
+    let mut buffer_chars = 0;
+    let reserved_chars = reserved.chars().count();
+    // Length and block count of the longest prefix a truncation marker still fits after
+    let mut room_for_marker = (0, 0);
+    for (idx, rendered) in blocks.enumerate() {
+        let mut block = String::new();
         if idx > 0 {
-            buffer.push_str("\n\n");
+            block.push_str("\n\n");
+        }
+        block.push_str(&rendered?);
+        let block_chars = block.chars().count();
+
+        // Account for the trailing newline every document ends with
+        if let Some((flag, limit)) = exceeded_limit(
+            &limits,
+            reserved.len() + buffer.len() + block.len() + 1,
+            reserved_chars + buffer_chars + block_chars + 1,
+        ) {
+            let (length, kept) = room_for_marker;
+            warn!(
+                included = kept,
+                omitted = entries.len() - kept,
+                "output reached {flag}, omitting remaining files"
+            );
+            dropped = Some(limit);
+            included = kept;
+            buffer.truncate(length);
+            break;
+        }
+
+        buffer.push_str(&block);
+        buffer_chars += block_chars;
+        if exceeded_with_marker(
+            &limits,
+            config.format,
+            reserved.len() + buffer.len() + 2,
+            reserved_chars + buffer_chars + 2,
+        )
+        .is_none()
+        {
+            room_for_marker = (buffer.len(), idx + 1);
         }
-        render_entry(entry, config, &mut buffer)?;
     }
 
+    if let Some(limit) = dropped {
+        if !buffer.is_empty() {
+            buffer.push_str("\n\n");
+        }
+        buffer.push_str(&truncation_marker(limit, config.format));
+    }
     if !entries.is_empty() {
         buffer.push('\n');
     }
 
-    Ok(buffer)
+    selected.truncate(included);
+    Ok((buffer, selected))
+}
+
+/// `max_total_bytes` and `max_tokens` as limits, with the flag each is reported under
+fn budget_limits(config: &CopyConfig) -> Vec<(&'static str, ChunkSize)> {
+    let bytes = config
+        .max_total_bytes
+        .map(|cap| ("--max-total-bytes", ChunkSize::Bytes(cap)));
+    let tokens = config
+        .max_tokens
+        .map(|cap| ("--max-tokens", ChunkSize::Tokens(cap)));
+    bytes.into_iter().chain(tokens).collect()
+}
+
+/// The first limit that text of `bytes` bytes and `chars` characters exceeds
+fn exceeded_limit(
+    limits: &[(&'static str, ChunkSize)],
+    bytes: usize,
+    chars: usize,
+) -> Option<(&'static str, ChunkSize)> {
+    limits
+        .iter()
+        .copied()
+        .find(|(_, limit)| !limit.allows(bytes, chars))
+}
+
+/// The first limit exceeded once a truncation marker ends a cut-short document
+///
+/// `bytes` and `chars` measure the document up to the marker, separator
+/// included. Every limit's marker is tried, since the one shown depends on
+/// which limit the remaining files would have exceeded.
+fn exceeded_with_marker(
+    limits: &[(&'static str, ChunkSize)],
+    format: OutputFormat,
+    bytes: usize,
+    chars: usize,
+) -> Option<(&'static str, ChunkSize)> {
+    limits.iter().find_map(|&(_, limit)| {
+        let marker = truncation_marker(limit, format);
+        // Plus the trailing newline every document ends with
+        exceeded_limit(
+            limits,
+            bytes + marker.len() + 1,
+            chars + marker.chars().count() + 1,
+        )
+    })
+}
+
+/// Remove entries from `selected` in `budget_strategy` order until their full blocks fit
+///
+/// Once a file is left out, the kept files must also leave room for the
+/// truncation marker. Sizes are measured without `dedup_contents` references,
+/// which only shrink the document, so the kept files always fit. Each
+/// left-out file is warned about; the limit that was exceeded is returned
+/// when any was.
+fn drop_over_budget(
+    entries: &[FileEntry],
+    blocks: &[String],
+    limits: &[(&'static str, ChunkSize)],
+    reserved: &str,
+    config: &CopyConfig,
+    selected: &mut Vec<usize>,
+) -> Option<ChunkSize> {
+    // Blocks with the separators between them
+    let joined = |sizes: &dyn Fn(&String) -> usize, kept: &[usize]| {
+        kept.iter()
+            .map(|&index| sizes(&blocks[index]))
+            .sum::<usize>()
+            + 2 * kept.len().saturating_sub(1)
+    };
+    let bytes = |kept: &[usize]| reserved.len() + joined(&|block| block.len(), kept);
+    let chars =
+        |kept: &[usize]| reserved.chars().count() + joined(&|block| block.chars().count(), kept);
+
+    // With the trailing newline, as when nothing is left out
+    let (flag, limit) = exceeded_limit(limits, bytes(selected) + 1, chars(selected) + 1)?;
+
+    let mut victims: Vec<usize> = selected.clone();
+    match config.budget_strategy {
+        BudgetStrategy::ByOrder => return None,
+        BudgetStrategy::LastFirst => {
+            victims.sort_by(|&a, &b| entries[b].relative.cmp(&entries[a].relative));
+        }
+        BudgetStrategy::LargestFirst => {
+            victims.sort_by_key(|&index| {
+                (
+                    std::cmp::Reverse(blocks[index].len()),
+                    std::cmp::Reverse(index),
+                )
+            });
+        }
+    }
+
+    for victim in victims {
+        let separator = if selected.is_empty() { 0 } else { 2 };
+        let fits = exceeded_with_marker(
+            limits,
+            config.format,
+            bytes(selected) + separator,
+            chars(selected) + separator,
+        )
+        .is_none();
+        if fits {
+            break;
+        }
+        warn!(
+            path = %entries[victim].relative,
+            strategy = %config.budget_strategy,
+            "leaving out file to stay within {flag}"
+        );
+        selected.retain(|&index| index != victim);
+    }
+    Some(limit)
+}
+
+/// Render the selected entries' blocks on `workers` threads, returned in selection order
+fn render_blocks_parallel(
+    entries: &[FileEntry],
+    selected: &[usize],
+    originals: &[Option<&Utf8Path>],
+    config: &CopyConfig,
+    paths: &PathDisplay,
+    workers: usize,
+) -> Result<Vec<String>> {
+    let per_worker = selected.len().div_ceil(workers.max(1)).max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = selected
+            .chunks(per_worker)
+            .map(|batch| {
+                scope.spawn(move || {
+                    batch
+                        .iter()
+                        .map(|&index| {
+                            let mut block = String::new();
+                            render_entry(
+                                &entries[index],
+                                index,
+                                originals[index],
+                                config,
+                                paths,
+                                &mut block,
+                            )?;
+                            Ok(block)
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+            })
+            .collect();
+
+        let mut blocks = Vec::with_capacity(selected.len());
+        for handle in handles {
+            blocks.extend(handle.join().expect("render worker panicked")?);
+        }
+        Ok(blocks)
+    })
+}
+
+/// Render entries into a series of documents that each stay within `limit`
+///
+/// Documents are only split between files, so an entry that exceeds the limit
+/// on its own gets a document to itself. `max_total_bytes` and `max_tokens` do
+/// not apply. With `dedup_contents`, a duplicate only refers to an original in
+/// the same document.
+pub fn render_chunks(
+    entries: &[FileEntry],
+    config: &CopyConfig,
+    limit: ChunkSize,
+) -> Result<Vec<String>> {
+    chunk_documents(entries, config, limit, "")
+}
+
+fn chunk_documents(
+    entries: &[FileEntry],
+    config: &CopyConfig,
+    limit: ChunkSize,
+    reserved: &str,
+) -> Result<Vec<String>> {
+    let paths = PathDisplay::from_config(config);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    // Only the first chunk shares its limit with the reserved text
+    let mut reserved_bytes = reserved.len();
+    let mut reserved_chars = reserved.chars().count();
+    let mut current_chars = 0;
+    let mut seen = SeenContents::new(config);
+
+    for (index, entry) in entries.iter().enumerate() {
+        let original = seen.original(entry);
+        let mut block = String::new();
+        render_entry(entry, index, original, config, &paths, &mut block)?;
+        let mut block_chars = block.chars().count();
+
+        // Separator before the block plus the trailing newline of the document
+        if !current.is_empty()
+            && !limit.allows(
+                reserved_bytes + current.len() + 2 + block.len() + 1,
+                reserved_chars + current_chars + 2 + block_chars + 1,
+            )
+        {
+            current.push('\n');
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+            reserved_bytes = 0;
+            reserved_chars = 0;
+
+            // The original stays behind in the previous chunk
+            seen.clear();
+            seen.original(entry);
+            if original.is_some() {
+                block.clear();
+                render_entry(entry, index, None, config, &paths, &mut block)?;
+                block_chars = block.chars().count();
+            }
+        }
+
+        if !current.is_empty() {
+            current.push_str("\n\n");
+            current_chars += 2;
+        }
+        current.push_str(&block);
+        current_chars += block_chars;
+    }
+
+    if !current.is_empty() {
+        current.push('\n');
+        chunks.push(current);
+    }
+
+    Ok(chunks)
+}
+
+/// Render entries into exactly `parts` documents of roughly equal size
+///
+/// Files are assigned largest first to the currently smallest part, then each
+/// part lists its files in collection order. Files are never split, so parts
+/// can be empty when there are fewer files than parts. `max_total_bytes` and
+/// `max_tokens` do not apply. With `dedup_contents`, parts are balanced on full
+/// contents and a duplicate only refers to an original in the same part.
+pub fn render_parts(
+    entries: &[FileEntry],
+    config: &CopyConfig,
+    parts: usize,
+) -> Result<Vec<String>> {
+    part_documents(entries, config, parts, "")
+}
+
+fn part_documents(
+    entries: &[FileEntry],
+    config: &CopyConfig,
+    parts: usize,
+    reserved: &str,
+) -> Result<Vec<String>> {
+    let paths = PathDisplay::from_config(config);
+    let mut blocks = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let mut block = String::new();
+        render_entry(entry, index, None, config, &paths, &mut block)?;
+        blocks.push(block);
+    }
+
+    let mut order: Vec<usize> = (0..blocks.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(blocks[index].len()));
+
+    let mut bins: Vec<(usize, Vec<usize>)> = vec![(0, Vec::new()); parts.max(1)];
+    bins[0].0 = reserved.len();
+    for index in order {
+        // Ties go to the earliest part so the first files land in part 1
+        let (size, members) = bins
+            .iter_mut()
+            .min_by_key(|(size, _)| *size)
+            .expect("at least one part");
+        *size += blocks[index].len();
+        members.push(index);
+    }
+
+    bins.into_iter()
+        .map(|(_, mut members)| {
+            members.sort_unstable();
+            let mut seen = SeenContents::new(config);
+            for &index in &members {
+                if let Some(original) = seen.original(&entries[index]) {
+                    let block = &mut blocks[index];
+                    block.clear();
+                    render_entry(
+                        &entries[index],
+                        index,
+                        Some(original),
+                        config,
+                        &paths,
+                        block,
+                    )?;
+                }
+            }
+
+            let mut document = members
+                .iter()
+                .map(|&index| blocks[index].as_str())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            if !document.is_empty() {
+                document.push('\n');
+            }
+            Ok(document)
+        })
+        .collect()
+}
+
+/// First entry seen with each contents, for `dedup_contents`
+///
+/// Only entries rendered into the same document are tracked, so a duplicate
+/// never refers to a file that was omitted or lives in another document.
+struct SeenContents<'a> {
+    enabled: bool,
+    first: HashMap<&'a str, &'a Utf8Path>,
+}
+
+impl<'a> SeenContents<'a> {
+    fn new(config: &CopyConfig) -> Self {
+        Self {
+            enabled: config.dedup_contents,
+            first: HashMap::new(),
+        }
+    }
+
+    /// The earlier entry `entry` duplicates, recording `entry` as the first of its contents otherwise
+    fn original(&mut self, entry: &'a FileEntry) -> Option<&'a Utf8Path> {
+        if !self.enabled {
+            return None;
+        }
+        match self.first.get(entry.contents.as_str()) {
+            Some(&first) => Some(first),
+            None => {
+                self.first.insert(&entry.contents, &entry.relative);
+                None
+            }
+        }
+    }
+
+    /// Start a new document
+    fn clear(&mut self) {
+        self.first.clear();
+    }
+}
+
+/// Note ending a document cut short by `max_total_bytes` or `max_tokens`, as a comment in the output format
+fn truncation_marker(limit: ChunkSize, format: OutputFormat) -> String {
+    let cap = match limit {
+        ChunkSize::Bytes(cap) => format!("{cap} bytes"),
+        ChunkSize::Tokens(cap) => format!("~{cap} tokens"),
+    };
+    match format {
+        OutputFormat::Heredoc => format!("# truncated at {cap}"),
+        _ => format!("<!-- truncated at {cap} -->"),
+    }
+}
+
+/// Paths of each entry exactly as `render_entries` shows them
+pub fn display_paths(entries: &[FileEntry], config: &CopyConfig) -> Vec<String> {
+    let paths = PathDisplay::from_config(config);
+    entries.iter().map(|entry| paths.show(entry)).collect()
+}
+
+/// How entry paths are shown in the rendered output
+struct PathDisplay {
+    redact: bool,
+    home: Option<Utf8PathBuf>,
+    prefix: Option<Utf8PathBuf>,
+    normalize: bool,
 }
 
-fn render_entry(entry: &FileEntry, config: &CopyConfig, buffer: &mut String) -> Result<()> {
+impl PathDisplay {
+    fn from_config(config: &CopyConfig) -> Self {
+        let redact = config.redact_paths || config.deterministic;
+        let home = redact
+            .then(dirs::home_dir)
+            .flatten()
+            .and_then(|home| Utf8PathBuf::from_path_buf(home).ok());
+        Self {
+            redact,
+            home,
+            prefix: config.path_prefix.clone(),
+            normalize: config.normalize_paths,
+        }
+    }
+
+    fn show(&self, entry: &FileEntry) -> String {
+        self.show_path(&entry.relative)
+    }
+
+    fn show_path(&self, relative: &Utf8Path) -> String {
+        let shown = self.show_native(relative);
+        if self.normalize && shown.contains('\\') {
+            shown.replace('\\', "/")
+        } else {
+            shown
+        }
+    }
+
+    fn show_native(&self, relative: &Utf8Path) -> String {
+        // The virtual root only makes sense for paths inside the working directory
+        if let Some(prefix) = &self.prefix
+            && relative.is_relative()
+            && !relative.starts_with("..")
+        {
+            return prefix.join(relative).to_string();
+        }
+
+        if self.redact {
+            utils::redact_path(relative, self.home.as_deref())
+        } else {
+            relative.to_string()
+        }
+    }
+}
+
+/// Render one entry; `index` is its position among all collected entries
+///
+/// With `duplicate_of`, the contents are replaced by a reference to that earlier entry.
+fn render_entry(
+    entry: &FileEntry,
+    index: usize,
+    duplicate_of: Option<&Utf8Path>,
+    config: &CopyConfig,
+    paths: &PathDisplay,
+    buffer: &mut String,
+) -> Result<()> {
+    let path = paths.show(entry);
     match config.format {
-        OutputFormat::Heredoc => render_heredoc(entry, buffer),
+        OutputFormat::Heredoc => render_heredoc(entry, &path, buffer),
         _ => {
+            let size = if config.annotate_sizes {
+                format!(
+                    " (size: {})",
+                    utils::human_size(entry.contents.len() as u64)
+                )
+            } else {
+                String::new()
+            };
+
+            let note = entry
+                .git_info
+                .as_ref()
+                .map(|info| format!("({info})\n"))
+                .unwrap_or_default();
+
             // Strategy pattern: each format defines preamble (before fence) and code_prefix (inside fence)
-            let (preamble, code_prefix) = match config.format {
-                OutputFormat::Simple => (format!("{}\n\n", entry.relative), None),
-                OutputFormat::Comment => (String::new(), Some(format!("// {}\n", entry.relative))),
-                OutputFormat::Heading => (format!("## `{}`\n\n", entry.relative), None),
-                OutputFormat::Heredoc => unreachable!(),
+            let (preamble, code_prefix) = if let Some(template) = &config.header_template {
+                // The template replaces the format's own path line, wherever that sits
+                let header = template.expand(
+                    &path,
+                    entry.language.as_deref().unwrap_or_default(),
+                    &utils::human_size(entry.contents.len() as u64),
+                    index + 1,
+                );
+                (format!("{header}\n{note}\n"), None)
+            } else {
+                match config.format {
+                    OutputFormat::Simple => (format!("{path}{size}\n{note}\n"), None),
+                    OutputFormat::Comment => {
+                        let preamble = if note.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{note}\n")
+                        };
+                        let marker = config.comment_style.marker();
+                        (preamble, Some(format!("{marker} {path}{size}\n")))
+                    }
+                    OutputFormat::Heading => {
+                        let hashes = "#".repeat(usize::from(config.heading_level));
+                        let note = if note.is_empty() {
+                            note
+                        } else {
+                            format!("{note}\n")
+                        };
+                        (format!("{hashes} `{path}`{size}\n\n{note}"), None)
+                    }
+                    OutputFormat::Heredoc => unreachable!(),
+                }
             };
 
             buffer.push_str(&preamble);
+            if let Some(original) = duplicate_of {
+                // Lossy: the duplicate's contents are not repeated
+                buffer.push_str(code_prefix.as_deref().unwrap_or_default());
+                buffer.push_str(&format!("(identical to {})\n", paths.show_path(original)));
+                return Ok(());
+            }
             render_fenced(entry, config, buffer, code_prefix.as_deref())
         }
     }
 }
 
-fn render_heredoc(entry: &FileEntry, buffer: &mut String) -> Result<()> {
+fn render_heredoc(entry: &FileEntry, path: &str, buffer: &mut String) -> Result<()> {
     let delimiter = HeredocDelimiter::determine(&entry.contents);
 
     // Determine the output path: use basename for files outside cwd or above it
-    let output_path = compute_heredoc_path(&entry.relative);
+    let output_path = compute_heredoc_path(camino::Utf8Path::new(path));
+
+    if let Some(info) = &entry.git_info {
+        buffer.push_str(&format!("# ({info})\n"));
+    }
 
     // Add directory creation if the file is in a subdirectory
     if let Some(parent) = std::path::Path::new(output_path.as_str()).parent()
@@ -70,8 +704,8 @@ fn render_heredoc(entry: &FileEntry, buffer: &mut String) -> Result<()> {
 fn compute_heredoc_path(relative: &camino::Utf8Path) -> String {
     let path_str = relative.as_str();
 
-    // If it's an absolute path, use just the filename
-    if path_str.starts_with('/') {
+    // If it's an absolute or redacted path, use just the filename
+    if path_str.starts_with('/') || path_str.starts_with('~') || path_str.starts_with("<root>") {
         return relative.file_name().unwrap_or("output").to_string();
     }
 
@@ -90,17 +724,26 @@ fn render_fenced(
     buffer: &mut String,
     prefix: Option<&str>,
 ) -> Result<()> {
+    // Numbering can't introduce backticks, so the original contents decide the fence
     let fence = Fence::determine(&entry.contents, config.fence);
-    buffer.push_str(&fence.open_line(entry.language.as_deref()));
+    let language = entry
+        .language
+        .as_deref()
+        .map(|language| fence_label(language, config));
+    buffer.push_str(&fence.open_line(language));
     buffer.push('\n');
 
     if let Some(prefix) = prefix {
         buffer.push_str(prefix);
     }
 
-    buffer.push_str(&entry.contents);
-    if !entry.contents.ends_with('\n') {
-        buffer.push('\n');
+    if config.line_numbers {
+        push_numbered(&entry.contents, buffer);
+    } else {
+        buffer.push_str(&entry.contents);
+        if !entry.contents.ends_with('\n') {
+            buffer.push('\n');
+        }
     }
 
     buffer.push_str(fence.close_line());
@@ -108,10 +751,41 @@ fn render_fenced(
     Ok(())
 }
 
+/// Append `contents` with each line prefixed by its right-aligned number, starting at 1
+///
+/// Lines keep their own endings, so CRLF files stay CRLF.
+fn push_numbered(contents: &str, buffer: &mut String) {
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    let width = lines.len().max(1).to_string().len();
+    for (index, line) in lines.into_iter().enumerate() {
+        buffer.push_str(&format!("{:>width$} | {line}", index + 1));
+        if !line.ends_with('\n') {
+            buffer.push('\n');
+        }
+    }
+}
+
+/// Fence label for a language: a configured override, else the selected style
+fn fence_label<'a>(language: &'a str, config: &'a CopyConfig) -> &'a str {
+    if let Some(tag) = config.fence_lang_overrides.get(language) {
+        return tag;
+    }
+    match config.fence_lang_map {
+        FenceLangMap::Canonical => language,
+        FenceLangMap::Short => utils::short_language_tag(language),
+    }
+}
+
 struct Fence {
     delimiter: String,
 }
 
+/// Longest fence tried by the default strategies
+const MAX_FENCE_WIDTH: usize = 8;
+
+/// Longest backtick fence `FencePreference::Smart` will widen to before switching to tilde
+const SMART_MAX_BACKTICK_WIDTH: usize = 32;
+
 impl Fence {
     fn determine(content: &str, preference: FencePreference) -> Self {
         let ch = match preference {
@@ -124,16 +798,26 @@ impl Fence {
                     '`'
                 }
             }
+            FencePreference::Smart => {
+                return Self::safe_for_char(content, '`', SMART_MAX_BACKTICK_WIDTH)
+                    .unwrap_or_else(|| Self::for_char(content, '~'));
+            }
         };
         Self::for_char(content, ch)
     }
 
     fn for_char(content: &str, ch: char) -> Self {
-        let delimiter = (3..=8)
+        Self::safe_for_char(content, ch, MAX_FENCE_WIDTH).unwrap_or_else(|| Self {
+            delimiter: ch.to_string().repeat(MAX_FENCE_WIDTH),
+        })
+    }
+
+    /// Shortest fence of `ch` (at least 3, at most `max_width`) not found in the content
+    fn safe_for_char(content: &str, ch: char, max_width: usize) -> Option<Self> {
+        (3..=max_width)
             .map(|count| ch.to_string().repeat(count))
             .find(|delim| !content.contains(delim))
-            .unwrap_or_else(|| ch.to_string().repeat(8));
-        Self { delimiter }
+            .map(|delimiter| Self { delimiter })
     }
 
     fn open_line(&self, language: Option<&str>) -> String {
@@ -1,19 +1,32 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
 use dialoguer::Confirm;
+use toml_edit::DocumentMut;
 use tracing::{debug, info, warn};
 
-use crate::config::{AppContext, UpdateConfig};
+use crate::config::{self, AppContext, UpdateConfig};
 use crate::error::{QuickctxError, Result};
 
 const UPDATE_CHECK_INTERVAL_DAYS: u64 = 7;
 const REPO_OWNER: &str = "CaddyGlow";
 const REPO_NAME: &str = "quickctx";
+const FETCH_ATTEMPTS: u32 = 3;
+const FETCH_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
 
 /// Run the update command to check for and install updates
 pub fn run(_context: &AppContext, config: UpdateConfig) -> Result<()> {
+    if let Some(enabled) = config.set_check_updates {
+        let path = config::user_config_path().ok_or_else(|| {
+            QuickctxError::Config("failed to determine config directory".to_string())
+        })?;
+        set_check_updates(&path, enabled)?;
+        let state = if enabled { "enabled" } else { "disabled" };
+        println!("Background update checks {state} in {}", path.display());
+        return Ok(());
+    }
+
     let current_version = env!("CARGO_PKG_VERSION");
 
     info!("Current version: {}", current_version);
@@ -70,17 +83,53 @@ pub fn run(_context: &AppContext, config: UpdateConfig) -> Result<()> {
     }
 }
 
+/// Write `general.check_updates` into a config file, creating it if needed
+///
+/// The file is edited in place so other keys, comments and formatting survive.
+pub fn set_check_updates(path: &Path, enabled: bool) -> Result<()> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut document: DocumentMut = raw
+        .parse()
+        .map_err(|e| QuickctxError::ConfigParse(format!("{}: {e}", path.display())))?;
+
+    let general = document
+        .entry("general")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .ok_or_else(|| {
+            QuickctxError::Config(format!("{}: `general` is not a table", path.display()))
+        })?;
+    general.insert("check_updates", toml_edit::value(enabled));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, document.to_string())?;
+    Ok(())
+}
+
 /// Check if an update is available and return the status
 fn check_for_update() -> Result<UpdateStatus> {
     let current_version = env!("CARGO_PKG_VERSION");
 
-    let releases = self_update::backends::github::ReleaseList::configure()
-        .repo_owner(REPO_OWNER)
-        .repo_name(REPO_NAME)
-        .build()
-        .map_err(|e| QuickctxError::SelfUpdate(format!("failed to configure release list: {}", e)))?
-        .fetch()
-        .map_err(|e| QuickctxError::SelfUpdate(format!("failed to fetch releases: {}", e)))?;
+    let releases = retry_with_backoff(
+        FETCH_ATTEMPTS,
+        FETCH_INITIAL_BACKOFF,
+        is_transient_network_error,
+        || {
+            self_update::backends::github::ReleaseList::configure()
+                .repo_owner(REPO_OWNER)
+                .repo_name(REPO_NAME)
+                .build()?
+                .fetch()
+        },
+    )
+    .map_err(|e| QuickctxError::SelfUpdate(format!("failed to fetch releases: {}", e)))?;
 
     if let Some(latest) = releases.first() {
         let latest_version = latest.version.trim_start_matches('v');
@@ -100,6 +149,44 @@ fn check_for_update() -> Result<UpdateStatus> {
     Ok(UpdateStatus::NoUpdateAvailable)
 }
 
+/// Whether a release fetch failed for a reason worth retrying
+///
+/// Connection failures and timeouts are retried; HTTP error statuses such as
+/// a 404 and malformed responses are not.
+fn is_transient_network_error(error: &self_update::errors::Error) -> bool {
+    match error {
+        self_update::errors::Error::Reqwest(e) => e.is_timeout() || e.is_connect(),
+        _ => false,
+    }
+}
+
+/// Run `operation` up to `attempts` times, doubling the delay after each
+/// retryable failure and returning the first success or final error
+fn retry_with_backoff<T, E: std::fmt::Display>(
+    attempts: u32,
+    initial_delay: Duration,
+    is_retryable: impl Fn(&E) -> bool,
+    mut operation: impl FnMut() -> std::result::Result<T, E>,
+) -> std::result::Result<T, E> {
+    let mut delay = initial_delay;
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < attempts && is_retryable(&err) => {
+                debug!(
+                    "Attempt {}/{} failed: {}; retrying in {:?}",
+                    attempt, attempts, err, delay
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 /// Install the latest update
 fn install_update() -> Result<()> {
     let current_version = env!("CARGO_PKG_VERSION");
@@ -184,3 +271,58 @@ enum UpdateStatus {
         release_notes: String,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_succeeds_after_transient_failures() {
+        let mut calls = 0;
+        let result = retry_with_backoff(
+            3,
+            Duration::ZERO,
+            |_: &String| true,
+            || {
+                calls += 1;
+                if calls < 3 {
+                    Err(format!("failure {calls}"))
+                } else {
+                    Ok(calls)
+                }
+            },
+        );
+
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up_on_permanent_errors_and_after_last_attempt() {
+        let mut calls = 0;
+        let result: std::result::Result<(), String> = retry_with_backoff(
+            3,
+            Duration::ZERO,
+            |e: &String| e != "404",
+            || {
+                calls += 1;
+                Err("404".to_string())
+            },
+        );
+        assert_eq!(result, Err("404".to_string()));
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result: std::result::Result<(), String> = retry_with_backoff(
+            3,
+            Duration::ZERO,
+            |_: &String| true,
+            || {
+                calls += 1;
+                Err("timeout".to_string())
+            },
+        );
+        assert_eq!(result, Err("timeout".to_string()));
+        assert_eq!(calls, 3);
+    }
+}
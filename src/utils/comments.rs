@@ -0,0 +1,313 @@
+/// Comment syntax for a family of languages
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+    nested_blocks: bool,
+    quotes: &'static [char],
+    rust_char_literals: bool,
+}
+
+const RUST: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    nested_blocks: true,
+    quotes: &['"', '\''],
+    rust_char_literals: true,
+};
+
+const C_STYLE: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    nested_blocks: false,
+    quotes: &['"', '\''],
+    rust_char_literals: false,
+};
+
+const C_STYLE_TEMPLATES: CommentSyntax = CommentSyntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+    nested_blocks: false,
+    quotes: &['"', '\'', '`'],
+    rust_char_literals: false,
+};
+
+const PHP: CommentSyntax = CommentSyntax {
+    line: &["//", "#"],
+    block: Some(("/*", "*/")),
+    nested_blocks: false,
+    quotes: &['"', '\''],
+    rust_char_literals: false,
+};
+
+const CSS: CommentSyntax = CommentSyntax {
+    line: &[],
+    block: Some(("/*", "*/")),
+    nested_blocks: false,
+    quotes: &['"', '\''],
+    rust_char_literals: false,
+};
+
+const HASH: CommentSyntax = CommentSyntax {
+    line: &["#"],
+    block: None,
+    nested_blocks: false,
+    quotes: &['"', '\''],
+    rust_char_literals: false,
+};
+
+const SQL: CommentSyntax = CommentSyntax {
+    line: &["--"],
+    block: Some(("/*", "*/")),
+    nested_blocks: false,
+    quotes: &['"', '\''],
+    rust_char_literals: false,
+};
+
+const LUA: CommentSyntax = CommentSyntax {
+    line: &["--"],
+    block: None,
+    nested_blocks: false,
+    quotes: &['"', '\''],
+    rust_char_literals: false,
+};
+
+fn syntax_for(language: &str) -> Option<&'static CommentSyntax> {
+    let syntax = match language {
+        "rust" => &RUST,
+        "c" | "cpp" | "java" | "kotlin" | "swift" => &C_STYLE,
+        "javascript" | "jsx" | "typescript" | "tsx" | "go" => &C_STYLE_TEMPLATES,
+        "scss" | "less" => &C_STYLE,
+        "php" => &PHP,
+        "css" => &CSS,
+        "python" | "ruby" | "bash" | "perl" | "yaml" | "toml" | "makefile" | "dockerfile"
+        | "cmake" | "conf" | "starlark" | "powershell" => &HASH,
+        "sql" => &SQL,
+        "lua" => &LUA,
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// Strips comments from source code in the given language
+///
+/// This is a best-effort tokenizer, not a parser: it knows line and block
+/// comment markers and skips over quoted string literals, but constructs such
+/// as raw strings, heredocs or `#` inside unquoted YAML values may be
+/// mangled. Lines that only contained a comment are removed entirely. The
+/// result is lossy and should not be pasted back over the original files.
+/// Unknown languages are returned unchanged.
+pub fn strip_comments(language: &str, contents: &str) -> String {
+    let Some(syntax) = syntax_for(language) else {
+        return contents.to_string();
+    };
+
+    let mut stripper = Stripper::new(syntax, contents);
+    stripper.run();
+    stripper.out
+}
+
+struct Stripper<'a> {
+    syntax: &'a CommentSyntax,
+    chars: Vec<char>,
+    pos: usize,
+    out: String,
+    line: String,
+    line_had_comment: bool,
+}
+
+impl<'a> Stripper<'a> {
+    fn new(syntax: &'a CommentSyntax, contents: &str) -> Self {
+        Self {
+            syntax,
+            chars: contents.chars().collect(),
+            pos: 0,
+            out: String::with_capacity(contents.len()),
+            line: String::new(),
+            line_had_comment: false,
+        }
+    }
+
+    fn run(&mut self) {
+        // Keep shebang lines even for `#`-comment languages
+        if self.starts_with("#!") {
+            self.copy_until_newline();
+        }
+
+        while let Some(&ch) = self.chars.get(self.pos) {
+            if ch == '\n' {
+                self.pos += 1;
+                self.flush_line(true);
+            } else if let Some((open, close)) = self.syntax.block
+                && self.starts_with(open)
+            {
+                self.skip_block_comment(open, close);
+            } else if self
+                .syntax
+                .line
+                .iter()
+                .any(|marker| self.starts_with(marker))
+            {
+                self.line_had_comment = true;
+                while self.chars.get(self.pos).is_some_and(|&c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if ch == '\'' && self.syntax.rust_char_literals {
+                self.copy_rust_quote();
+            } else if self.syntax.quotes.contains(&ch) {
+                self.copy_string(ch);
+            } else {
+                self.line.push(ch);
+                self.pos += 1;
+            }
+        }
+
+        self.flush_line(false);
+    }
+
+    fn starts_with(&self, marker: &str) -> bool {
+        marker
+            .chars()
+            .enumerate()
+            .all(|(offset, expected)| self.chars.get(self.pos + offset) == Some(&expected))
+    }
+
+    fn copy_until_newline(&mut self) {
+        while let Some(&ch) = self.chars.get(self.pos) {
+            if ch == '\n' {
+                break;
+            }
+            self.line.push(ch);
+            self.pos += 1;
+        }
+    }
+
+    fn skip_block_comment(&mut self, open: &str, close: &str) {
+        self.line_had_comment = true;
+        self.pos += open.chars().count();
+        let mut depth = 1;
+
+        while self.pos < self.chars.len() {
+            if self.starts_with(close) {
+                self.pos += close.chars().count();
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            } else if self.syntax.nested_blocks && self.starts_with(open) {
+                self.pos += open.chars().count();
+                depth += 1;
+            } else if self.chars[self.pos] == '\n' {
+                self.pos += 1;
+                self.flush_line(true);
+                self.line_had_comment = true;
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn copy_string(&mut self, quote: char) {
+        self.line.push(quote);
+        self.pos += 1;
+
+        while let Some(&ch) = self.chars.get(self.pos) {
+            self.line.push(ch);
+            self.pos += 1;
+            if ch == '\\' {
+                if let Some(&escaped) = self.chars.get(self.pos) {
+                    self.line.push(escaped);
+                    self.pos += 1;
+                }
+            } else if ch == quote {
+                return;
+            }
+        }
+    }
+
+    /// Rust uses `'` for both char literals and lifetimes; only the former is quoted
+    fn copy_rust_quote(&mut self) {
+        let is_char_literal = match self.chars.get(self.pos + 1) {
+            Some('\\') => true,
+            Some(_) => self.chars.get(self.pos + 2) == Some(&'\''),
+            None => false,
+        };
+
+        if is_char_literal {
+            self.copy_string('\'');
+        } else {
+            self.line.push('\'');
+            self.pos += 1;
+        }
+    }
+
+    fn flush_line(&mut self, newline: bool) {
+        if self.line_had_comment {
+            let kept = self.line.trim_end();
+            if !kept.is_empty() {
+                self.out.push_str(kept);
+                if newline {
+                    self.out.push('\n');
+                }
+            }
+        } else {
+            self.out.push_str(&self.line);
+            if newline {
+                self.out.push('\n');
+            }
+        }
+
+        self.line.clear();
+        self.line_had_comment = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_rust_line_and_block_comments() {
+        let source = "// header\nfn main() { /* inline */ let x = 1; // trailing\n    /* multi\n       line */\n}\n";
+        let stripped = strip_comments("rust", source);
+        assert_eq!(stripped, "fn main() {  let x = 1;\n}\n");
+    }
+
+    #[test]
+    fn test_preserves_comment_markers_in_strings() {
+        let source = "let s = \"// not a comment\"; // real\nlet t = \"/* nor this */\";\n";
+        let stripped = strip_comments("rust", source);
+        assert_eq!(
+            stripped,
+            "let s = \"// not a comment\";\nlet t = \"/* nor this */\";\n"
+        );
+    }
+
+    #[test]
+    fn test_rust_lifetimes_and_char_literals() {
+        let source = "fn f<'a>(s: &'a str) -> char { '/' } // done\nlet q = '\\''; // quote\n";
+        let stripped = strip_comments("rust", source);
+        assert_eq!(
+            stripped,
+            "fn f<'a>(s: &'a str) -> char { '/' }\nlet q = '\\'';\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_block_comments_in_rust() {
+        let stripped = strip_comments("rust", "/* outer /* inner */ still */ a\n");
+        assert_eq!(stripped, " a\n");
+    }
+
+    #[test]
+    fn test_strips_hash_comments_and_keeps_shebang() {
+        let source = "#!/usr/bin/env python\n# comment\nx = \"# kept\"  # dropped\n";
+        let stripped = strip_comments("python", source);
+        assert_eq!(stripped, "#!/usr/bin/env python\nx = \"# kept\"\n");
+    }
+
+    #[test]
+    fn test_unknown_language_is_unchanged() {
+        let source = "// whatever\n";
+        assert_eq!(strip_comments("markdown", source), source);
+    }
+}
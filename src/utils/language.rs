@@ -66,6 +66,34 @@ static LANGUAGE_FILENAMES: LazyLock<HashMap<&'static str, &'static str>> = LazyL
     ])
 });
 
+/// Short fence labels for renderers that do not know the canonical names
+static SHORT_LANGUAGE_TAGS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    HashMap::from([
+        ("rust", "rs"),
+        ("python", "py"),
+        ("ruby", "rb"),
+        ("javascript", "js"),
+        ("typescript", "ts"),
+        ("kotlin", "kt"),
+        ("bash", "sh"),
+        ("powershell", "ps1"),
+        ("perl", "pl"),
+        ("yaml", "yml"),
+        ("markdown", "md"),
+        ("makefile", "make"),
+    ])
+});
+
+/// Short tag for a language name from `language_for_path` (e.g. `typescript` -> `ts`)
+///
+/// Languages without a common short form are returned unchanged.
+pub fn short_language_tag(language: &str) -> &str {
+    SHORT_LANGUAGE_TAGS
+        .get(language)
+        .copied()
+        .unwrap_or(language)
+}
+
 pub fn language_for_path(path: &Utf8Path) -> Option<&'static str> {
     if let Some(name) = path.file_name() {
         if let Some(lang) = LANGUAGE_FILENAMES.get(name) {
@@ -1,12 +1,21 @@
+mod comments;
 mod language;
+mod mtime;
+mod notebook;
 
 use std::fs;
+use std::io::Write;
 
-use camino::{Utf8Path, Utf8PathBuf};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 
-use crate::error::Result;
+use crate::error::{QuickctxError, Result};
 
-pub use language::language_for_path;
+pub use comments::strip_comments;
+pub use language::{language_for_path, short_language_tag};
+pub use mtime::{
+    MtimeManifest, modified_secs, read_mtime_manifest, set_mtime, write_mtime_manifest,
+};
+pub use notebook::render_notebook;
 
 pub fn looks_like_glob(pattern: &str) -> bool {
     pattern.contains('*') || pattern.contains('?') || pattern.contains('[')
@@ -18,6 +27,59 @@ pub fn relative_to(path: &Utf8Path, base: &Utf8Path) -> Utf8PathBuf {
         .unwrap_or_else(|_| path.to_owned())
 }
 
+/// Lexical path from `base` to `path`, using `..` segments to climb out of `base`
+///
+/// Paths sharing no prefix with `base` (another drive on Windows) are
+/// returned unchanged.
+pub fn relative_path_from(path: &Utf8Path, base: &Utf8Path) -> Utf8PathBuf {
+    let path_parts: Vec<_> = path.components().collect();
+    let base_parts: Vec<_> = base.components().collect();
+    let common = path_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_owned();
+    }
+
+    let mut relative = Utf8PathBuf::new();
+    for _ in common..base_parts.len() {
+        relative.push("..");
+    }
+    for part in &path_parts[common..] {
+        relative.push(part.as_str());
+    }
+    relative
+}
+
+/// Hide machine-specific prefixes of an absolute path for display
+///
+/// Paths under `home` become `~/...` and other absolute paths have their
+/// root (and drive prefix on Windows) replaced with `<root>`. Relative paths
+/// are returned unchanged.
+pub fn redact_path(path: &Utf8Path, home: Option<&Utf8Path>) -> String {
+    if !path.is_absolute() {
+        return path.to_string();
+    }
+
+    if let Some(rest) = home.and_then(|home| path.strip_prefix(home).ok()) {
+        return if rest.as_str().is_empty() {
+            "~".to_string()
+        } else {
+            format!("~/{rest}")
+        };
+    }
+
+    let rest: Utf8PathBuf = path
+        .components()
+        .skip_while(|component| {
+            matches!(component, Utf8Component::Prefix(_) | Utf8Component::RootDir)
+        })
+        .collect();
+    format!("<root>/{rest}")
+}
+
 pub fn is_probably_binary(data: &[u8]) -> bool {
     const SAMPLE_LIMIT: usize = 1024;
     let sample = if data.len() > SAMPLE_LIMIT {
@@ -44,6 +106,75 @@ pub fn is_probably_binary(data: &[u8]) -> bool {
     control_count > sample.len() / 10
 }
 
+/// Rough token count for LLM context sizing, at about four characters per token
+pub fn estimate_tokens(text: &str) -> usize {
+    estimate_tokens_for_chars(text.chars().count())
+}
+
+/// [`estimate_tokens`] for text whose character count is already known
+pub fn estimate_tokens_for_chars(chars: usize) -> usize {
+    chars.div_ceil(4)
+}
+
+/// Human-readable byte size using binary units, e.g. `512 B`, `2.3 KB`, `1.0 MB`
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // Compare the size as printed, so 1 MiB - 1 shows as `1.0 MB` rather than `1024.0 KB`
+    while (size * 10.0).round() / 10.0 >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Byte count from a size such as `512`, `10k`, `1.5M` or `2 GB`, the inverse of [`human_size`]
+///
+/// Suffixes are case-insensitive and binary like `human_size`: `k` is 1024
+/// bytes, `m` 1024² and `g` 1024³, with `KB`/`KiB` accepted as spellings of
+/// `k` (decimal 1000-based units are not supported). Fractions are rounded
+/// down to whole bytes.
+pub fn parse_size(raw: &str) -> Result<u64> {
+    let invalid = || {
+        QuickctxError::InvalidArgument(format!(
+            "invalid size '{raw}': expected a number with an optional k, m or g suffix"
+        ))
+    };
+
+    let trimmed = raw.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, suffix) = trimmed.split_at(split);
+    let shift = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 10,
+        "m" | "mb" | "mib" => 20,
+        "g" | "gb" | "gib" => 30,
+        _ => return Err(invalid()),
+    };
+
+    if number.contains('.') {
+        let bytes = number.parse::<f64>().map_err(|_| invalid())? * (1u64 << shift) as f64;
+        // Saturating casts would quietly turn an overflow into u64::MAX
+        if bytes >= u64::MAX as f64 {
+            return Err(invalid());
+        }
+        Ok(bytes as u64)
+    } else {
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|count| count.checked_mul(1 << shift))
+            .ok_or_else(invalid)
+    }
+}
+
 /// Ensure parent directories exist for the given path
 pub fn ensure_parent(path: &Utf8Path) -> Result<()> {
     if let Some(parent) = path.parent() {
@@ -52,6 +183,17 @@ pub fn ensure_parent(path: &Utf8Path) -> Result<()> {
     Ok(())
 }
 
+/// Append data to a file, creating it and its parent directories if needed
+pub fn append_with_parent(path: &Utf8Path, data: &[u8]) -> Result<()> {
+    ensure_parent(path)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path.as_std_path())?;
+    file.write_all(data)?;
+    Ok(())
+}
+
 /// Write data to a file, creating parent directories if needed
 pub fn write_with_parent(path: &Utf8Path, data: &[u8]) -> Result<()> {
     ensure_parent(path)?;
@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use camino::Utf8Path;
+use filetime::FileTime;
+
+use crate::error::{QuickctxError, Result};
+
+/// Modification times in unix seconds, keyed by the path shown in the copied output
+pub type MtimeManifest = BTreeMap<String, i64>;
+
+/// Modification time of a file in whole unix seconds
+pub fn modified_secs(path: impl AsRef<Path>) -> Result<i64> {
+    let modified = fs::metadata(path)?.modified()?;
+    let secs = match modified.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    };
+    Ok(secs)
+}
+
+/// Write a manifest as a JSON object of `{ "<path>": <unix_secs> }`
+pub fn write_mtime_manifest(path: &Utf8Path, manifest: &MtimeManifest) -> Result<()> {
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| {
+        QuickctxError::InvalidArgument(format!("{path}: failed to serialize mtimes: {e}"))
+    })?;
+    super::write_with_parent(path, json.as_bytes())
+}
+
+/// Read a manifest written by [`write_mtime_manifest`]
+pub fn read_mtime_manifest(path: &Utf8Path) -> Result<MtimeManifest> {
+    let raw = fs::read_to_string(path.as_std_path())?;
+    serde_json::from_str(&raw)
+        .map_err(|e| QuickctxError::InvalidArgument(format!("{path}: invalid mtime manifest: {e}")))
+}
+
+/// Set a file's modification time to the given unix seconds
+pub fn set_mtime(path: &Utf8Path, secs: i64) -> Result<()> {
+    filetime::set_file_mtime(path.as_std_path(), FileTime::from_unix_time(secs, 0))?;
+    Ok(())
+}
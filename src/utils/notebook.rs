@@ -0,0 +1,84 @@
+use serde_json::Value;
+
+use crate::error::{QuickctxError, Result};
+
+/// Language used for code cells when the notebook metadata names none
+const DEFAULT_KERNEL_LANGUAGE: &str = "python";
+
+/// Renders a Jupyter notebook (`.ipynb` JSON) as markdown
+///
+/// Markdown cells are kept as prose and each code cell becomes a fenced block
+/// labelled with the kernel language. Raw cells and outputs are dropped.
+pub fn render_notebook(json: &str) -> Result<String> {
+    let notebook: Value = serde_json::from_str(json)
+        .map_err(|err| QuickctxError::InvalidArgument(format!("invalid notebook: {err}")))?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| QuickctxError::InvalidArgument("notebook has no cells array".into()))?;
+
+    let metadata = notebook.get("metadata");
+    let language = metadata
+        .and_then(|m| m.pointer("/kernelspec/language"))
+        .or_else(|| metadata.and_then(|m| m.pointer("/language_info/name")))
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_KERNEL_LANGUAGE);
+
+    let mut sections = Vec::new();
+    for cell in cells {
+        let source = cell_source(cell);
+        if source.trim().is_empty() {
+            continue;
+        }
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") => sections.push(source.trim_end().to_string()),
+            Some("code") => {
+                let fence = code_fence(&source);
+                sections.push(format!("{fence}{language}\n{}\n{fence}", source.trim_end()));
+            }
+            _ => {}
+        }
+    }
+
+    let mut rendered = sections.join("\n\n");
+    rendered.push('\n');
+    Ok(rendered)
+}
+
+/// Cell source, stored either as one string or as a list of lines
+fn cell_source(cell: &Value) -> String {
+    match cell.get("source") {
+        Some(Value::String(source)) => source.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Backtick fence longer than any backtick run in the cell
+fn code_fence(source: &str) -> String {
+    let longest = source.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_notebook_cells() {
+        let notebook = r##"{
+            "metadata": {"kernelspec": {"language": "julia"}},
+            "cells": [
+                {"cell_type": "markdown", "source": ["# Intro\n", "Some prose"]},
+                {"cell_type": "code", "source": "x = 1", "outputs": []},
+                {"cell_type": "raw", "source": "ignored"}
+            ]
+        }"##;
+
+        assert_eq!(
+            render_notebook(notebook).unwrap(),
+            "# Intro\nSome prose\n\n```julia\nx = 1\n```\n"
+        );
+        assert!(render_notebook("[]").is_err());
+    }
+}
@@ -5,10 +5,10 @@ use std::path::{Path, PathBuf};
 use camino::Utf8PathBuf;
 
 use quickctx::config::{
-    AppContext, ConflictStrategy, CopyConfig, FencePreference, InputSource, OutputFormat,
-    PasteConfig,
+    AppContext, ChunkSize, CommentStyle, ConflictStrategy, CopyConfig, FencePreference,
+    InputEncoding, InputSource, OutputFormat, PasteConfig,
 };
-use quickctx::copy;
+use quickctx::copy::{self, LanguageStat};
 use quickctx::paste;
 
 struct TempDir {
@@ -65,6 +65,7 @@ fn aggregate_single_file_to_markdown_file() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     copy::run(&context, config).unwrap();
@@ -74,6 +75,34 @@ fn aggregate_single_file_to_markdown_file() {
     assert_eq!(markdown, expected);
 }
 
+#[test]
+fn run_to_string_returns_document_without_writing() {
+    let temp = TempDir::new();
+    let src_dir = temp.path().join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("lib.rs"), "pub fn a() {}\n").unwrap();
+    fs::write(src_dir.join("notes.txt"), "hello\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("ignored.md"));
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        output: Some(output_path.clone()),
+        format: OutputFormat::Simple,
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    let expected =
+        "src/lib.rs\n\n```rust\npub fn a() {}\n```\n\n\nsrc/notes.txt\n\n```text\nhello\n```\n\n";
+    assert_eq!(markdown, expected);
+    assert!(!output_path.exists());
+}
+
 #[test]
 fn aggregate_respects_gitignore_patterns() {
     let temp = TempDir::new();
@@ -95,6 +124,7 @@ fn aggregate_respects_gitignore_patterns() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     copy::run(&context, config).unwrap();
@@ -129,6 +159,7 @@ fn extract_round_trip_recreates_files() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
     copy::run(&context, aggregate_config).unwrap();
 
@@ -136,6 +167,7 @@ fn extract_round_trip_recreates_files() {
         source: InputSource::File(aggregate_output.clone()),
         output_dir: utf8(temp.path().join("restored")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -145,6 +177,209 @@ fn extract_round_trip_recreates_files() {
     assert!(contents.contains("pub fn add"));
 }
 
+#[test]
+fn extract_decodes_latin1_input() {
+    let temp = TempDir::new();
+    let markdown = temp.path().join("latin1.md");
+    // "café" and "naïve" encoded as ISO-8859-1
+    fs::write(
+        &markdown,
+        b"notes.txt\n\n```text\ncaf\xe9 na\xefve\n```\n".as_slice(),
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = |input_encoding| PasteConfig {
+        source: InputSource::File(utf8(&markdown)),
+        output_dir: utf8(temp.path().join("restored")),
+        conflict: ConflictStrategy::Overwrite,
+        input_encoding,
+        ..Default::default()
+    };
+
+    assert!(paste::run(&context, config(InputEncoding::Utf8)).is_err());
+
+    paste::run(&context, config(InputEncoding::Latin1)).unwrap();
+    let contents = fs::read_to_string(temp.path().join("restored/notes.txt")).unwrap();
+    assert_eq!(contents, "caf\u{e9} na\u{ef}ve\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_hard_links_identical_blocks() {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp = TempDir::new();
+    let markdown = temp.path().join("dup.md");
+    fs::write(
+        &markdown,
+        "a/LICENSE\n\n```text\nsame\n```\n\nb/LICENSE\n\n```text\nsame\n```\n\nc.txt\n\n```text\nother\n```\n",
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = PasteConfig {
+        source: InputSource::File(utf8(&markdown)),
+        output_dir: utf8(temp.path().join("out")),
+        conflict: ConflictStrategy::Overwrite,
+        hard_link: true,
+        ..Default::default()
+    };
+
+    paste::run(&context, config).unwrap();
+
+    let inode = |path: &str| {
+        fs::metadata(temp.path().join("out").join(path))
+            .unwrap()
+            .ino()
+    };
+    assert_eq!(inode("a/LICENSE"), inode("b/LICENSE"));
+    assert_ne!(inode("a/LICENSE"), inode("c.txt"));
+    assert_eq!(
+        fs::read_to_string(temp.path().join("out/b/LICENSE")).unwrap(),
+        "same\n"
+    );
+}
+
+#[test]
+fn extract_to_map_returns_sanitized_paths_and_contents() {
+    let markdown = "src/main.rs\n\n```rust\nfn main() {}\n```\n\n## `docs/notes.txt`\n\n```text\nremember\n```\n\n## `../escape.txt`\n\n```text\nnope\n```\n";
+
+    let files = paste::extract_to_map(markdown).unwrap();
+
+    assert_eq!(
+        files.into_iter().collect::<Vec<_>>(),
+        vec![
+            (
+                Utf8PathBuf::from("docs/notes.txt"),
+                "remember\n".to_string()
+            ),
+            (
+                Utf8PathBuf::from("src/main.rs"),
+                "fn main() {}\n".to_string()
+            ),
+        ]
+    );
+    assert!(paste::extract_to_map("```rust\nfn main() {}\n```\n").is_err());
+}
+
+#[test]
+fn extract_keep_going_writes_blocks_after_a_failure() {
+    let temp = TempDir::new();
+    let markdown = temp.path().join("mixed.md");
+    fs::write(
+        &markdown,
+        "blocked/a.txt\n\n```text\na\n```\n\nok.txt\n\n```text\nok\n```\n",
+    )
+    .unwrap();
+    // A plain file where a directory is needed makes the first block unwritable
+    let out = temp.path().join("out");
+    fs::create_dir_all(&out).unwrap();
+    fs::write(out.join("blocked"), "not a directory").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = |keep_going| PasteConfig {
+        source: InputSource::File(utf8(&markdown)),
+        output_dir: utf8(&out),
+        conflict: ConflictStrategy::Overwrite,
+        keep_going,
+        ..Default::default()
+    };
+
+    assert!(paste::run(&context, config(false)).is_err());
+    assert!(!out.join("ok.txt").exists());
+
+    let err = paste::run(&context, config(true)).unwrap_err();
+    assert!(err.to_string().contains("1 of 2 code block(s) failed"));
+    assert_eq!(fs::read_to_string(out.join("ok.txt")).unwrap(), "ok\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_refuses_to_write_through_symlink_out_of_output_dir() {
+    let temp = TempDir::new();
+    let markdown = temp.path().join("dump.md");
+    fs::write(
+        &markdown,
+        "linked/evil.txt\n\n```text\npwned\n```\n\nsafe.txt\n\n```text\nok\n```\n",
+    )
+    .unwrap();
+    let outside = temp.path().join("outside");
+    let out = temp.path().join("out");
+    fs::create_dir_all(&outside).unwrap();
+    fs::create_dir_all(&out).unwrap();
+    std::os::unix::fs::symlink(&outside, out.join("linked")).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = PasteConfig {
+        source: InputSource::File(utf8(&markdown)),
+        output_dir: utf8(&out),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    let err = paste::run(&context, config).unwrap_err().to_string();
+    assert!(err.contains("resolves outside"), "unexpected error: {err}");
+    assert!(!outside.join("evil.txt").exists());
+}
+
+#[test]
+fn extract_skips_files_that_already_match() {
+    let temp = TempDir::new();
+    let markdown = temp.path().join("dump.md");
+    fs::write(
+        &markdown,
+        "same.txt\n\n```text\nsame\n```\n\nchanged.txt\n\n```text\nnew\n```\n",
+    )
+    .unwrap();
+    let out = temp.path().join("out");
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = || PasteConfig {
+        source: InputSource::File(utf8(&markdown)),
+        output_dir: utf8(&out),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    paste::run(&context, config()).unwrap();
+    fs::write(out.join("changed.txt"), "old\n").unwrap();
+    let past = 1_000_000_000;
+    for name in ["same.txt", "changed.txt"] {
+        quickctx::utils::set_mtime(&utf8(out.join(name)), past).unwrap();
+    }
+
+    paste::run(&context, config()).unwrap();
+
+    assert_eq!(
+        quickctx::utils::modified_secs(out.join("same.txt")).unwrap(),
+        past
+    );
+    assert_ne!(
+        quickctx::utils::modified_secs(out.join("changed.txt")).unwrap(),
+        past
+    );
+    assert_eq!(
+        fs::read_to_string(out.join("changed.txt")).unwrap(),
+        "new\n"
+    );
+}
+
 /// Regression test: Headings without backticks should NOT be used as file paths
 #[test]
 fn extract_ignores_headings_without_backticks() {
@@ -171,6 +406,7 @@ fn main() {}
         source: InputSource::File(utf8(&md_path)),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -214,6 +450,7 @@ pub fn hello() {}
         source: InputSource::File(utf8(&md_path)),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -255,6 +492,7 @@ fn main() {}
         source: InputSource::File(utf8(&md_path)),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -304,6 +542,7 @@ fn extract_supports_multiple_comment_styles() {
             source: InputSource::File(utf8(&md_path)),
             output_dir: utf8(&output_dir),
             conflict: ConflictStrategy::Overwrite,
+            ..Default::default()
         };
 
         paste::run(&context, extract_config).unwrap();
@@ -333,6 +572,7 @@ fn extract_error_includes_file_path() {
         source: InputSource::File(nonexistent.clone()),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     let result = paste::run(&context, extract_config);
@@ -375,6 +615,7 @@ fn main() {}
         source: InputSource::File(utf8(&md_path)),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -417,6 +658,7 @@ fn main() {}
         source: InputSource::File(utf8(&md_path)),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -480,6 +722,7 @@ fn test_it() {}
         source: InputSource::File(utf8(&md_path)),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -534,6 +777,7 @@ fn code() {}
         source: InputSource::File(utf8(&md_path)),
         output_dir: utf8(temp.path().join("output")),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -569,6 +813,7 @@ fn aggregate_with_glob_patterns() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     copy::run(&context, config).unwrap();
@@ -604,6 +849,7 @@ fn aggregate_with_exclude_patterns() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: vec!["**/test.rs".to_string()],
+        ..Default::default()
     };
 
     copy::run(&context, config).unwrap();
@@ -615,400 +861,2507 @@ fn aggregate_with_exclude_patterns() {
     assert!(!markdown.contains("test.rs"));
 }
 
-/// Test aggregate skips binary files
+/// Test aggregate with directory-name excludes
 #[test]
-fn aggregate_skips_binary_files() {
+fn aggregate_with_exclude_dirs() {
     let temp = TempDir::new();
-    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::create_dir_all(temp.path().join("src/web/node_modules/pkg")).unwrap();
     fs::write(temp.path().join("src/main.rs"), "fn main() {}").unwrap();
-    // Write a binary file (with null bytes)
     fs::write(
-        temp.path().join("src/binary.bin"),
-        b"\x00\x01\x02\x03\x00\xFF",
+        temp.path().join("src/web/node_modules/pkg/index.js"),
+        "module.exports = 1;",
     )
     .unwrap();
+    fs::write(temp.path().join("src/web/node_modules.txt"), "notes").unwrap();
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
 
-    let output_path = utf8(temp.path().join("output.md"));
     let config = CopyConfig {
         inputs: vec!["src/".to_string()],
-        output: Some(output_path.clone()),
         format: OutputFormat::Simple,
-        fence: FencePreference::Auto,
-        respect_gitignore: true,
-        ignore_files: Vec::new(),
-        excludes: Vec::new(),
+        exclude_dirs: vec!["node_modules".to_string()],
+        ..Default::default()
     };
 
-    copy::run(&context, config).unwrap();
-    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+    let markdown = copy::run_to_string(&context, &config).unwrap();
 
-    assert!(markdown.contains("main.rs"));
-    // Binary file should be skipped
-    assert!(!markdown.contains("binary.bin"));
+    assert!(markdown.contains("src/main.rs"));
+    assert!(markdown.contains("src/web/node_modules.txt"));
+    assert!(!markdown.contains("index.js"));
 }
 
-/// Test aggregate with no gitignore
+/// Test excluded directories are pruned from the walk and ancestors never match
 #[test]
-fn aggregate_without_gitignore() {
+fn aggregate_exclude_dirs_prunes_walk_but_not_ancestors() {
     let temp = TempDir::new();
-    fs::write(temp.path().join(".gitignore"), "ignored.txt").unwrap();
-    fs::write(temp.path().join("ignored.txt"), "ignored content").unwrap();
-    fs::write(temp.path().join("included.txt"), "included content").unwrap();
+    let project = temp.path().join("target/proj");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::create_dir_all(project.join("target/debug")).unwrap();
+    fs::write(project.join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(project.join("target/debug/out.rs"), "// built").unwrap();
 
     let context = AppContext {
-        cwd: utf8(temp.path()),
+        cwd: utf8(&project),
         verbosity: 0,
     };
-
-    let output_path = utf8(temp.path().join("output.md"));
+    let summary_path = utf8(temp.path().join("summary.json"));
     let config = CopyConfig {
         inputs: vec![".".to_string()],
-        output: Some(output_path.clone()),
+        output: Some(utf8(temp.path().join("out.md"))),
         format: OutputFormat::Simple,
-        fence: FencePreference::Auto,
-        respect_gitignore: false, // Disable gitignore
-        ignore_files: Vec::new(),
-        excludes: Vec::new(),
+        exclude_dirs: vec!["target".to_string()],
+        summary_json: Some(summary_path.clone()),
+        ..Default::default()
     };
 
-    copy::run(&context, config).unwrap();
-    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/main.rs"), "{markdown}");
+    assert!(!markdown.contains("out.rs"), "{markdown}");
 
-    // Both files should be included when gitignore is disabled
-    assert!(markdown.contains("ignored.txt"));
-    assert!(markdown.contains("included.txt"));
+    // Pruned files are never visited, so they are not counted as excluded either
+    copy::run(&context, config).unwrap();
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary_path).unwrap()).unwrap();
+    assert_eq!(summary["skipped_excluded"], 0);
 }
 
-/// Test extract error: empty path
+/// Test --front-matter prepends a YAML block describing the run
 #[test]
-fn extract_error_empty_path() {
+fn aggregate_with_front_matter() {
     let temp = TempDir::new();
-
-    let markdown = r#"
-```rust
-
-```
-"#;
-
-    let md_path = temp.path().join("input.md");
-    fs::write(&md_path, markdown).unwrap();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("src/b.rs"), "fn b() {}\n").unwrap();
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
-
-    let extract_config = PasteConfig {
-        source: InputSource::File(utf8(&md_path)),
-        output_dir: utf8(temp.path().join("output")),
-        conflict: ConflictStrategy::Overwrite,
+    let config = |format| CopyConfig {
+        inputs: vec!["src/".to_string()],
+        format,
+        front_matter: true,
+        ..Default::default()
     };
 
-    let result = paste::run(&context, extract_config);
-    assert!(result.is_err());
-    assert!(
-        result
-            .unwrap_err()
-            .to_string()
-            .contains("unable to determine file path")
-    );
+    let markdown = copy::run_to_string(&context, &config(OutputFormat::Simple)).unwrap();
+    let header = markdown
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---\n"))
+        .map(|(header, _)| header)
+        .expect("front matter fence");
+    assert!(header.contains("files: 2"));
+    assert!(header.lines().any(|line| line.starts_with("generated: ")));
+    assert!(markdown.contains("src/a.rs"));
+
+    let script = copy::run_to_string(&context, &config(OutputFormat::Heredoc)).unwrap();
+    assert!(!script.starts_with("---"));
 }
 
-/// Test extract error: absolute path
+/// Test the front matter counts only rendered files and fits within the caps
 #[test]
-fn extract_error_absolute_path() {
+fn aggregate_front_matter_counts_rendered_files_within_caps() {
     let temp = TempDir::new();
-
-    let markdown = r#"/etc/passwd
-
-```text
-should not work
-```
-"#;
-
-    let md_path = temp.path().join("input.md");
-    fs::write(&md_path, markdown).unwrap();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(
+            temp.path().join(format!("src/{name}.rs")),
+            format!("fn {name}() {{}}\n"),
+        )
+        .unwrap();
+    }
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
-
-    let extract_config = PasteConfig {
-        source: InputSource::File(utf8(&md_path)),
-        output_dir: utf8(temp.path().join("output")),
-        conflict: ConflictStrategy::Overwrite,
+    let full = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        format: OutputFormat::Simple,
+        front_matter: true,
+        deterministic: true,
+        ..Default::default()
     };
+    let document = copy::run_to_string(&context, &full).unwrap();
+    assert!(document.contains("files: 3\n"));
 
-    let result = paste::run(&context, extract_config);
-    assert!(result.is_err());
-    assert!(
-        result
-            .unwrap_err()
-            .to_string()
-            .contains("absolute paths are not allowed")
-    );
+    // A cap one byte short of everything drops the last file and the count with it
+    let capped = CopyConfig {
+        max_total_bytes: Some(document.len() as u64 - 1),
+        ..full.clone()
+    };
+    let document = copy::run_to_string(&context, &capped).unwrap();
+    assert!(document.contains("files: 2\n"), "{document}");
+    assert!(!document.contains("src/c.rs"));
+    let cap = capped.max_total_bytes.unwrap();
+    assert!(document.ends_with(&format!("<!-- truncated at {cap} bytes -->\n")));
+
+    // The header counts against the first chunk's limit
+    let output_path = utf8(temp.path().join("ctx.md"));
+    let first_file = copy::run_to_string(
+        &context,
+        &CopyConfig {
+            inputs: vec!["src/a.rs".to_string()],
+            ..full.clone()
+        },
+    )
+    .unwrap();
+    let chunked = CopyConfig {
+        output: Some(output_path),
+        chunk: Some(ChunkSize::Bytes(first_file.len() as u64)),
+        ..full
+    };
+    copy::run(&context, chunked).unwrap();
+    let first = fs::read_to_string(temp.path().join("ctx.001.md")).unwrap();
+    assert!(first.len() <= first_file.len(), "{first}");
+    assert!(first.contains("src/a.rs") && !first.contains("src/b.rs"));
 }
 
-/// Test extract error: parent directory traversal
+/// Test --deterministic makes repeated runs byte-identical
 #[test]
-fn extract_error_parent_directory() {
+fn aggregate_deterministic_runs_are_identical() {
     let temp = TempDir::new();
-
-    let markdown = r#"../../../etc/passwd
-
-```text
-should not work
-```
-"#;
-
-    let md_path = temp.path().join("input.md");
-    fs::write(&md_path, markdown).unwrap();
+    fs::create_dir_all(temp.path().join("src/nested")).unwrap();
+    fs::write(temp.path().join("src/z.rs"), "fn z() {}\n").unwrap();
+    fs::write(temp.path().join("src/nested/a.rs"), "fn a() {}\n").unwrap();
+    // An absolute input outside both the working directory and $HOME
+    let outside = TempDir::new();
+    fs::write(outside.path().join("shared.rs"), "fn shared() {}\n").unwrap();
+    let outside_file = utf8(outside.path().join("shared.rs"));
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
-
-    let extract_config = PasteConfig {
-        source: InputSource::File(utf8(&md_path)),
-        output_dir: utf8(temp.path().join("output")),
-        conflict: ConflictStrategy::Overwrite,
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string(), outside_file.to_string()],
+        front_matter: true,
+        deterministic: true,
+        ..Default::default()
     };
 
-    let result = paste::run(&context, extract_config);
-    assert!(result.is_err());
-    assert!(
-        result
-            .unwrap_err()
-            .to_string()
-            .contains("parent directory segments are not allowed")
-    );
+    let first = copy::run_to_string(&context, &config).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let second = copy::run_to_string(&context, &config).unwrap();
+
+    assert_eq!(first, second);
+    assert!(first.contains("generated: 1970-01-01T00:00:00Z\n"));
+    assert!(first.contains("cwd: \".\"\n"));
+    assert!(!first.contains(temp.path().to_str().unwrap()));
+    assert!(!first.contains(outside.path().to_str().unwrap()));
+    assert!(!first.contains("<root>"));
+    let shown = quickctx::utils::relative_path_from(&outside_file, &utf8(temp.path()));
+    assert!(shown.starts_with(".."));
+    assert!(first.contains(shown.as_str()), "{first}");
 }
 
-/// Test extract with skip strategy
+/// Collected files keep plain path order: directories are not hoisted above files
 #[test]
-fn extract_conflict_skip_strategy() {
+fn aggregate_orders_files_by_plain_path() {
     let temp = TempDir::new();
-
-    // Create existing file
-    fs::create_dir_all(temp.path().join("output/src")).unwrap();
-    fs::write(temp.path().join("output/src/main.rs"), "original content").unwrap();
-
-    let markdown = r#"src/main.rs
-
-```rust
-new content
-```
-"#;
-
-    let md_path = temp.path().join("input.md");
-    fs::write(&md_path, markdown).unwrap();
+    fs::create_dir_all(temp.path().join("b")).unwrap();
+    fs::write(temp.path().join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("b/x.rs"), "fn x() {}\n").unwrap();
+    fs::write(temp.path().join("c.rs"), "fn c() {}\n").unwrap();
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
-
-    let extract_config = PasteConfig {
-        source: InputSource::File(utf8(&md_path)),
-        output_dir: utf8(temp.path().join("output")),
-        conflict: ConflictStrategy::Skip,
+    let config = CopyConfig {
+        inputs: vec![".".to_string()],
+        format: OutputFormat::Simple,
+        ..Default::default()
     };
 
-    paste::run(&context, extract_config).unwrap();
+    let output = copy::run_to_string(&context, &config).unwrap();
+    let positions: Vec<usize> = ["a.rs\n", "b/x.rs\n", "c.rs\n"]
+        .iter()
+        .map(|path| {
+            output
+                .find(path)
+                .unwrap_or_else(|| panic!("{path} in {output}"))
+        })
+        .collect();
+    assert!(positions.is_sorted(), "{output}");
+}
 
-    // File should still have original content
-    let content = fs::read_to_string(temp.path().join("output/src/main.rs")).unwrap();
-    assert_eq!(content, "original content");
+/// Test --heading-level changes the per-file heading in the heading format
+#[test]
+fn aggregate_heading_level() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("src/b.rs"), "fn b() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        format: OutputFormat::Heading,
+        heading_level: 3,
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+
+    assert!(markdown.starts_with("### `src/a.rs`\n"));
+    assert!(markdown.contains("\n### `src/b.rs`\n"));
+    assert!(!markdown.contains("\n## `"));
 }
 
-/// Test extract with overwrite strategy
+/// Test --render-notebooks turns notebook JSON into prose and code fences
 #[test]
-fn extract_conflict_overwrite_strategy() {
+fn aggregate_renders_notebook_cells() {
     let temp = TempDir::new();
+    fs::write(
+        temp.path().join("analysis.ipynb"),
+        r#"{
+  "metadata": {"kernelspec": {"language": "python"}},
+  "cells": [
+    {"cell_type": "markdown", "source": ["Load the data\n"]},
+    {"cell_type": "code", "source": ["import csv\n", "rows = list(csv.reader(open('a.csv')))"]}
+  ]
+}"#,
+    )
+    .unwrap();
 
-    // Create existing file
-    fs::create_dir_all(temp.path().join("output/src")).unwrap();
-    fs::write(temp.path().join("output/src/main.rs"), "original content").unwrap();
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = |render_notebooks| CopyConfig {
+        inputs: vec!["analysis.ipynb".to_string()],
+        format: OutputFormat::Simple,
+        render_notebooks,
+        ..Default::default()
+    };
 
-    let markdown = r#"src/main.rs
+    let raw = copy::run_to_string(&context, &config(false)).unwrap();
+    assert!(raw.contains("\"cell_type\""));
 
-```rust
-new content
-```
-"#;
+    let rendered = copy::run_to_string(&context, &config(true)).unwrap();
+    assert!(!rendered.contains("\"cell_type\""));
+    assert!(rendered.contains("Load the data\n"));
+    assert_eq!(rendered.matches("```python\n").count(), 1);
+    assert!(rendered.contains("```python\nimport csv\nrows = list("));
+}
 
-    let md_path = temp.path().join("input.md");
-    fs::write(&md_path, markdown).unwrap();
+/// Test per-directory copier.exclude files only apply within their subtree
+#[test]
+fn aggregate_with_nested_exclude_files() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::create_dir_all(temp.path().join("tests")).unwrap();
+    fs::write(temp.path().join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(temp.path().join("src/gen.rs"), "// generated").unwrap();
+    fs::write(temp.path().join("src/copier.exclude"), "gen.rs\n").unwrap();
+    fs::write(temp.path().join("tests/gen.rs"), "// test helper").unwrap();
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
 
-    let extract_config = PasteConfig {
-        source: InputSource::File(utf8(&md_path)),
-        output_dir: utf8(temp.path().join("output")),
-        conflict: ConflictStrategy::Overwrite,
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string(), "tests/".to_string()],
+        format: OutputFormat::Simple,
+        ..Default::default()
     };
 
-    paste::run(&context, extract_config).unwrap();
+    let markdown = copy::run_to_string(&context, &config).unwrap();
 
-    // File should have new content
-    let content = fs::read_to_string(temp.path().join("output/src/main.rs")).unwrap();
-    assert_eq!(content, "new content\n");
+    assert!(markdown.contains("src/main.rs"));
+    assert!(!markdown.contains("src/gen.rs"));
+    assert!(markdown.contains("tests/gen.rs"));
+    assert!(!markdown.contains("copier.exclude"));
 }
 
-/// Test extract single-line comment hint
+/// Test .copierignore applies without gitignores, from the walked directory and cwd
 #[test]
-fn extract_single_line_comment_hint() {
+fn aggregate_copierignore_excludes_files_without_gitignore() {
     let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src/build")).unwrap();
+    fs::write(temp.path().join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(temp.path().join("src/build/out.rs"), "// artifact").unwrap();
+    fs::write(temp.path().join("src/schema.gen.rs"), "// generated").unwrap();
+    fs::write(temp.path().join(".copierignore"), "build/\n").unwrap();
+    fs::write(temp.path().join("src/.copierignore"), "*.gen.rs\n").unwrap();
 
-    let markdown = r#"
-```rust
-// src/single.rs
-```
-"#;
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        format: OutputFormat::Simple,
+        respect_gitignore: false,
+        ..Default::default()
+    };
 
-    let md_path = temp.path().join("input.md");
-    fs::write(&md_path, markdown).unwrap();
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/main.rs"));
+    assert!(!markdown.contains("src/build/out.rs"));
+    assert!(!markdown.contains("schema.gen.rs"));
+
+    let config = CopyConfig {
+        respect_copierignore: false,
+        ..config
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/build/out.rs"));
+    assert!(markdown.contains("schema.gen.rs"));
+}
+
+/// Test --chunk splits output between files into numbered documents
+#[test]
+fn aggregate_chunks_output_between_files() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(
+            temp.path().join(format!("src/{name}.rs")),
+            format!("fn {name}() {{}}\n"),
+        )
+        .unwrap();
+    }
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
 
-    let extract_config = PasteConfig {
-        source: InputSource::File(utf8(&md_path)),
-        output_dir: utf8(temp.path().join("output")),
-        conflict: ConflictStrategy::Overwrite,
+    // The cap is exactly the size of a document holding two of the files
+    let two_files = CopyConfig {
+        inputs: vec!["src/a.rs".to_string(), "src/b.rs".to_string()],
+        format: OutputFormat::Simple,
+        ..Default::default()
     };
+    let cap = copy::run_to_string(&context, &two_files).unwrap().len() as u64;
 
-    paste::run(&context, extract_config).unwrap();
+    let output_path = utf8(temp.path().join("out/ctx.md"));
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        output: Some(output_path.clone()),
+        format: OutputFormat::Simple,
+        chunk: Some(ChunkSize::Bytes(cap)),
+        ..Default::default()
+    };
+    copy::run(&context, config).unwrap();
 
-    let extracted = temp.path().join("output/src/single.rs");
-    assert!(extracted.exists());
+    let first = fs::read_to_string(temp.path().join("out/ctx.001.md")).unwrap();
+    let second = fs::read_to_string(temp.path().join("out/ctx.002.md")).unwrap();
+    assert!(first.contains("src/a.rs") && first.contains("src/b.rs"));
+    assert_eq!(first.len() as u64, cap);
+    assert!(second.starts_with("src/c.rs"));
+    assert!(!temp.path().join("out/ctx.003.md").exists());
+    assert!(!output_path.exists());
+}
 
-    // Content should be empty after comment is removed
-    let content = fs::read_to_string(extracted).unwrap();
-    assert_eq!(content.trim(), "");
+/// Test run, run_to_string and estimate agree on chunked output with front matter
+#[test]
+fn aggregate_chunked_run_matches_run_to_string_and_estimate() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(
+            temp.path().join(format!("src/{name}.rs")),
+            format!("fn {name}() {{}}\n"),
+        )
+        .unwrap();
+    }
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let output_path = utf8(temp.path().join("ctx.md"));
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        output: Some(output_path),
+        format: OutputFormat::Simple,
+        chunk: Some(ChunkSize::Bytes(1)),
+        front_matter: true,
+        deterministic: true,
+        ..Default::default()
+    };
+
+    let document = copy::run_to_string(&context, &config).unwrap();
+    let estimate = copy::estimate(&context, &config).unwrap();
+    copy::run(&context, config).unwrap();
+
+    let written: String = (1..=3)
+        .map(|n| fs::read_to_string(temp.path().join(format!("ctx.00{n}.md"))).unwrap())
+        .collect();
+    assert!(written.starts_with("---\n"));
+    assert_eq!(written, document);
+    assert_eq!(estimate.files, 3);
+    assert_eq!(estimate.bytes, document.len());
 }
 
-// ============================================================================
-// Heredoc Format Tests
-// ============================================================================
+/// Test --split-by-size balances whole files across a fixed number of parts
+#[test]
+fn aggregate_split_by_size_balances_files_across_parts() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    for (name, lines) in [("a", 12), ("b", 8), ("c", 6)] {
+        let body: String = (0..lines).map(|i| format!("// line {i}\n")).collect();
+        fs::write(temp.path().join(format!("src/{name}.rs")), body).unwrap();
+    }
 
-/// Test heredoc format generates valid bash syntax
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let output_path = utf8(temp.path().join("out/ctx.md"));
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        output: Some(output_path.clone()),
+        format: OutputFormat::Simple,
+        split_by_size: Some(2),
+        ..Default::default()
+    };
+    copy::run(&context, config).unwrap();
+
+    let first = fs::read_to_string(temp.path().join("out/ctx.1.md")).unwrap();
+    let second = fs::read_to_string(temp.path().join("out/ctx.2.md")).unwrap();
+    assert!(!temp.path().join("out/ctx.3.md").exists());
+    assert!(!output_path.exists());
+
+    // The largest file alone balances the other two together
+    assert!(first.contains("src/a.rs") && !first.contains("src/b.rs"));
+    assert!(second.starts_with("src/b.rs") && second.contains("src/c.rs"));
+    let (big, small) = (first.len().max(second.len()), first.len().min(second.len()));
+    assert!(big - small < small / 2, "{big} vs {small}");
+}
+
+/// Test aggregate skips binary files
 #[test]
-fn heredoc_format_generates_valid_bash() {
+fn aggregate_skips_binary_files() {
     let temp = TempDir::new();
-    let src_dir = temp.path().join("src");
-    fs::create_dir_all(&src_dir).unwrap();
-    fs::write(src_dir.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/main.rs"), "fn main() {}").unwrap();
+    // Write a binary file (with null bytes)
+    fs::write(
+        temp.path().join("src/binary.bin"),
+        b"\x00\x01\x02\x03\x00\xFF",
+    )
+    .unwrap();
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
 
-    let output_path = utf8(temp.path().join("script.sh"));
+    let output_path = utf8(temp.path().join("output.md"));
     let config = CopyConfig {
-        inputs: vec!["src/main.rs".to_string()],
+        inputs: vec!["src/".to_string()],
         output: Some(output_path.clone()),
-        format: OutputFormat::Heredoc,
+        format: OutputFormat::Simple,
         fence: FencePreference::Auto,
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
 
-    let script = fs::read_to_string(output_path.as_std_path()).unwrap();
-
-    // Verify basic structure
-    assert!(script.contains("mkdir -p 'src'"));
-    assert!(script.contains("cat > 'src/main.rs'"));
-    assert!(script.contains("EOF"));
-    assert!(script.contains("fn main() {}"));
+    assert!(markdown.contains("main.rs"));
+    // Binary file should be skipped
+    assert!(!markdown.contains("binary.bin"));
 }
 
-/// Test heredoc format with path normalization
+/// Test the JSON summary written alongside a copy run
 #[test]
-fn heredoc_format_normalizes_paths() {
+fn aggregate_writes_summary_json() {
     let temp = TempDir::new();
-
-    // Create a file outside the temp dir to test absolute path handling
-    let external_file = env::temp_dir().join("quickctx-test-external.txt");
-    fs::write(&external_file, "external content\n").unwrap();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(temp.path().join("src/lib.rs"), "pub fn lib() {}").unwrap();
+    fs::write(temp.path().join("src/skip.tmp"), "scratch").unwrap();
+    fs::write(
+        temp.path().join("src/binary.bin"),
+        b"\x00\x01\x02\x03\x00\xFF",
+    )
+    .unwrap();
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
 
-    let output_path = utf8(temp.path().join("script.sh"));
+    let output_path = utf8(temp.path().join("output.md"));
+    let summary_path = utf8(temp.path().join("stats/summary.json"));
     let config = CopyConfig {
-        inputs: vec![external_file.to_string_lossy().to_string()],
+        inputs: vec!["src/".to_string()],
         output: Some(output_path.clone()),
-        format: OutputFormat::Heredoc,
-        fence: FencePreference::Auto,
-        respect_gitignore: true,
-        ignore_files: Vec::new(),
-        excludes: Vec::new(),
+        format: OutputFormat::Simple,
+        excludes: vec!["**/*.tmp".to_string()],
+        summary_json: Some(summary_path.clone()),
+        ..Default::default()
     };
 
     copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(summary_path.as_std_path()).unwrap()).unwrap();
+
+    assert_eq!(summary["files"], 2);
+    assert_eq!(summary["bytes"], markdown.len());
+    assert_eq!(summary["skipped_binary"], 1);
+    assert_eq!(summary["skipped_excluded"], 1);
+    assert_eq!(summary["output"], output_path.as_str());
+    assert!(summary["tokens_estimate"].as_u64().unwrap() > 0);
+}
 
-    let script = fs::read_to_string(output_path.as_std_path()).unwrap();
+/// Test extension overrides win over the binary heuristic in both directions
+#[test]
+fn aggregate_treat_as_text_and_binary_overrides() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    // Text with a stray NUL that the heuristic would reject
+    fs::write(temp.path().join("src/records.dat"), b"alpha\x00beta\n").unwrap();
+    fs::write(temp.path().join("src/notes.txt"), "plain notes\n").unwrap();
 
-    // Should use just filename, not absolute path
-    assert!(!script.contains("cat > '/"));
-    assert!(script.contains("cat > 'quickctx-test-external.txt'"));
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
 
-    // Cleanup
-    let _ = fs::remove_file(external_file);
+    let output_path = utf8(temp.path().join("output.md"));
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        output: Some(output_path.clone()),
+        treat_as_text: vec!["dat".to_string()],
+        treat_as_binary: vec![".TXT".to_string()],
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    assert!(markdown.contains("records.dat"));
+    assert!(markdown.contains("alpha"));
+    assert!(!markdown.contains("notes.txt"));
 }
 
-/// Test heredoc delimiter selection avoids conflicts
+/// Test aggregate with no gitignore
 #[test]
-fn heredoc_format_avoids_delimiter_conflicts() {
+fn aggregate_without_gitignore() {
     let temp = TempDir::new();
-    let file_path = temp.path().join("file.txt");
-
-    // Create content that contains EOF
-    fs::write(&file_path, "This has EOF\nand more EOF\nEOF\n").unwrap();
+    fs::write(temp.path().join(".gitignore"), "ignored.txt").unwrap();
+    fs::write(temp.path().join("ignored.txt"), "ignored content").unwrap();
+    fs::write(temp.path().join("included.txt"), "included content").unwrap();
 
     let context = AppContext {
         cwd: utf8(temp.path()),
         verbosity: 0,
     };
 
-    let output_path = utf8(temp.path().join("script.sh"));
+    let output_path = utf8(temp.path().join("output.md"));
     let config = CopyConfig {
-        inputs: vec!["file.txt".to_string()],
+        inputs: vec![".".to_string()],
         output: Some(output_path.clone()),
-        format: OutputFormat::Heredoc,
+        format: OutputFormat::Simple,
         fence: FencePreference::Auto,
-        respect_gitignore: true,
+        respect_gitignore: false, // Disable gitignore
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
 
-    let script = fs::read_to_string(output_path.as_std_path()).unwrap();
+    // Both files should be included when gitignore is disabled
+    assert!(markdown.contains("ignored.txt"));
+    assert!(markdown.contains("included.txt"));
+}
 
-    // Should use a different delimiter than EOF
-    assert!(
-        script.contains("cat > 'file.txt' << 'END'")
-            || script.contains("cat > 'file.txt' << 'HEREDOC'")
-            || script.contains("cat > 'file.txt' << 'CONTENT'")
-    );
+/// Test extract error: empty path
+#[test]
+fn extract_error_empty_path() {
+    let temp = TempDir::new();
+
+    let markdown = r#"
+```rust
+
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    let result = paste::run(&context, extract_config);
+    assert!(result.is_err());
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("unable to determine file path")
+    );
+}
+
+/// Test extract error: absolute path
+#[test]
+fn extract_error_absolute_path() {
+    let temp = TempDir::new();
+
+    let markdown = r#"/etc/passwd
+
+```text
+should not work
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    let result = paste::run(&context, extract_config);
+    assert!(result.is_err());
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("absolute paths are not allowed")
+    );
+}
+
+/// Test extract error: parent directory traversal
+#[test]
+fn extract_error_parent_directory() {
+    let temp = TempDir::new();
+
+    let markdown = r#"../../../etc/passwd
+
+```text
+should not work
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    let result = paste::run(&context, extract_config);
+    assert!(result.is_err());
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("parent directory segments are not allowed")
+    );
+}
+
+/// Test extract with skip strategy
+#[test]
+fn extract_conflict_skip_strategy() {
+    let temp = TempDir::new();
+
+    // Create existing file
+    fs::create_dir_all(temp.path().join("output/src")).unwrap();
+    fs::write(temp.path().join("output/src/main.rs"), "original content").unwrap();
+
+    let markdown = r#"src/main.rs
+
+```rust
+new content
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Skip,
+        ..Default::default()
+    };
+
+    paste::run(&context, extract_config).unwrap();
+
+    // File should still have original content
+    let content = fs::read_to_string(temp.path().join("output/src/main.rs")).unwrap();
+    assert_eq!(content, "original content");
+}
+
+/// Test extract with overwrite strategy
+#[test]
+fn extract_conflict_overwrite_strategy() {
+    let temp = TempDir::new();
+
+    // Create existing file
+    fs::create_dir_all(temp.path().join("output/src")).unwrap();
+    fs::write(temp.path().join("output/src/main.rs"), "original content").unwrap();
+
+    let markdown = r#"src/main.rs
+
+```rust
+new content
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    paste::run(&context, extract_config).unwrap();
+
+    // File should have new content
+    let content = fs::read_to_string(temp.path().join("output/src/main.rs")).unwrap();
+    assert_eq!(content, "new content\n");
+}
+
+/// Test extract single-line comment hint
+#[test]
+fn extract_single_line_comment_hint() {
+    let temp = TempDir::new();
+
+    let markdown = r#"
+```rust
+// src/single.rs
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    paste::run(&context, extract_config).unwrap();
+
+    let extracted = temp.path().join("output/src/single.rs");
+    assert!(extracted.exists());
+
+    // Content should be empty after comment is removed
+    let content = fs::read_to_string(extracted).unwrap();
+    assert_eq!(content.trim(), "");
+}
+
+// ============================================================================
+// Heredoc Format Tests
+// ============================================================================
+
+/// Test heredoc format generates valid bash syntax
+#[test]
+fn heredoc_format_generates_valid_bash() {
+    let temp = TempDir::new();
+    let src_dir = temp.path().join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("main.rs"), "fn main() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("script.sh"));
+    let config = CopyConfig {
+        inputs: vec!["src/main.rs".to_string()],
+        output: Some(output_path.clone()),
+        format: OutputFormat::Heredoc,
+        fence: FencePreference::Auto,
+        respect_gitignore: true,
+        ignore_files: Vec::new(),
+        excludes: Vec::new(),
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+
+    let script = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    // Verify basic structure
+    assert!(script.contains("mkdir -p 'src'"));
+    assert!(script.contains("cat > 'src/main.rs'"));
+    assert!(script.contains("EOF"));
+    assert!(script.contains("fn main() {}"));
+}
+
+/// Test heredoc format with path normalization
+#[test]
+fn heredoc_format_normalizes_paths() {
+    let temp = TempDir::new();
+
+    // Create a file outside the temp dir to test absolute path handling
+    let external_file = env::temp_dir().join("quickctx-test-external.txt");
+    fs::write(&external_file, "external content\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("script.sh"));
+    let config = CopyConfig {
+        inputs: vec![external_file.to_string_lossy().to_string()],
+        output: Some(output_path.clone()),
+        format: OutputFormat::Heredoc,
+        fence: FencePreference::Auto,
+        respect_gitignore: true,
+        ignore_files: Vec::new(),
+        excludes: Vec::new(),
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+
+    let script = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    // Should use just filename, not absolute path
+    assert!(!script.contains("cat > '/"));
+    assert!(script.contains("cat > 'quickctx-test-external.txt'"));
+
+    // Cleanup
+    let _ = fs::remove_file(external_file);
+}
+
+/// Test heredoc delimiter selection avoids conflicts
+#[test]
+fn heredoc_format_avoids_delimiter_conflicts() {
+    let temp = TempDir::new();
+    let file_path = temp.path().join("file.txt");
+
+    // Create content that contains EOF
+    fs::write(&file_path, "This has EOF\nand more EOF\nEOF\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("script.sh"));
+    let config = CopyConfig {
+        inputs: vec!["file.txt".to_string()],
+        output: Some(output_path.clone()),
+        format: OutputFormat::Heredoc,
+        fence: FencePreference::Auto,
+        respect_gitignore: true,
+        ignore_files: Vec::new(),
+        excludes: Vec::new(),
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+
+    let script = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    // Should use a different delimiter than EOF
+    assert!(
+        script.contains("cat > 'file.txt' << 'END'")
+            || script.contains("cat > 'file.txt' << 'HEREDOC'")
+            || script.contains("cat > 'file.txt' << 'CONTENT'")
+    );
+}
+
+#[test]
+fn aggregate_skips_empty_files_by_default() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("__init__.py"), "").unwrap();
+    fs::write(temp.path().join("main.py"), "print('hi')\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("out.md"));
+    let config = CopyConfig {
+        inputs: vec!["__init__.py".to_string(), "main.py".to_string()],
+        output: Some(output_path.clone()),
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    assert!(!markdown.contains("__init__.py"));
+    assert!(markdown.contains("main.py"));
+}
+
+#[test]
+fn aggregate_include_empty_keeps_zero_byte_files() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("__init__.py"), "").unwrap();
+    fs::write(temp.path().join("main.py"), "print('hi')\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("out.md"));
+    let config = CopyConfig {
+        inputs: vec!["__init__.py".to_string(), "main.py".to_string()],
+        output: Some(output_path.clone()),
+        include_empty: true,
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    assert!(markdown.contains("__init__.py\n\n```python\n\n```\n"));
+    assert!(markdown.contains("main.py"));
+}
+
+#[test]
+fn extract_rewrite_strips_path_prefix() {
+    let temp = TempDir::new();
+
+    let markdown = r#"`frontend/src/app.ts`
+
+```typescript
+export const app = 1;
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        rewrites: vec!["frontend/=>".to_string()],
+        ..Default::default()
+    };
+
+    paste::run(&context, extract_config).unwrap();
+
+    assert!(temp.path().join("output/src/app.ts").exists());
+    assert!(!temp.path().join("output/frontend").exists());
+}
+
+#[test]
+fn extract_rewrite_regex_changes_extension() {
+    let temp = TempDir::new();
+
+    let markdown = r#"`src/index.ts`
+
+```typescript
+export {};
+```
+
+`src/index.test.ts`
+
+```typescript
+test("ok", () => {});
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        rewrites: vec![r"re:\.ts$=>.js".to_string()],
+        ..Default::default()
+    };
+
+    paste::run(&context, extract_config).unwrap();
+
+    let output = temp.path().join("output/src");
+    assert!(output.join("index.js").exists());
+    assert!(output.join("index.test.js").exists());
+    assert!(!output.join("index.ts").exists());
+}
+
+#[test]
+fn aggregate_manifest_preserves_listed_order() {
+    let temp = TempDir::new();
+    let src_dir = temp.path().join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("a.rs"), "// a\n").unwrap();
+    fs::write(src_dir.join("m.rs"), "// m\n").unwrap();
+    fs::write(src_dir.join("z.rs"), "// z\n").unwrap();
+    fs::write(src_dir.join("unlisted.rs"), "// unlisted\n").unwrap();
+    fs::write(
+        temp.path().join("manifest.txt"),
+        "# curated order\nsrc/z.rs\n\nsrc/a.rs\nsrc/m.rs\n",
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("out.md"));
+    let config = CopyConfig {
+        output: Some(output_path.clone()),
+        manifest: Some(Utf8PathBuf::from("manifest.txt")),
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    let z = markdown.find("src/z.rs").unwrap();
+    let a = markdown.find("src/a.rs").unwrap();
+    let m = markdown.find("src/m.rs").unwrap();
+    assert!(z < a && a < m, "output should follow manifest order");
+    assert!(!markdown.contains("unlisted.rs"));
+}
+
+#[test]
+fn aggregate_manifest_missing_file_reports_line() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("present.txt"), "here\n").unwrap();
+    fs::write(
+        temp.path().join("manifest.txt"),
+        "present.txt\nmissing.txt\n",
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let config = CopyConfig {
+        output: Some(utf8(temp.path().join("out.md"))),
+        manifest: Some(Utf8PathBuf::from("manifest.txt")),
+        ..Default::default()
+    };
+
+    let err = copy::run(&context, config).unwrap_err().to_string();
+    assert!(err.contains("manifest.txt:2"), "unexpected error: {err}");
+    assert!(err.contains("missing.txt"));
+}
+
+#[test]
+fn aggregate_paths_from0_reads_nul_separated_list() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("my notes.txt"), "spaced\n").unwrap();
+    fs::write(temp.path().join("plain.txt"), "plain\n").unwrap();
+    fs::write(temp.path().join("unlisted.txt"), "unlisted\n").unwrap();
+    fs::write(
+        temp.path().join("list.bin"),
+        "./my notes.txt\0./plain.txt\0",
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("out.md"));
+    let config = CopyConfig {
+        output: Some(output_path.clone()),
+        paths_from: Some(Utf8PathBuf::from("list.bin")),
+        null_separated: true,
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    assert!(
+        markdown.contains("spaced"),
+        "path with a space should be collected"
+    );
+    assert!(markdown.contains("plain"));
+    assert!(!markdown.contains("unlisted"));
+}
+
+#[test]
+fn aggregate_estimate_matches_rendered_length() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(temp.path().join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("out.md"));
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        output: Some(output_path.clone()),
+        ..Default::default()
+    };
+
+    let estimate = copy::estimate(&context, &config).unwrap();
+    assert!(!output_path.exists(), "estimate must not write output");
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    assert_eq!(estimate.files, 2);
+    assert_eq!(estimate.bytes, markdown.len());
+    assert_eq!(estimate.tokens, markdown.chars().count().div_ceil(4));
+}
+
+#[cfg(unix)]
+#[test]
+fn aggregate_output_template_expands_date() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("notes.txt"), "dated\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let config = CopyConfig {
+        inputs: vec!["notes.txt".to_string()],
+        output: Some(utf8(temp.path().join("{date}-ctx.md"))),
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+
+    let today = std::process::Command::new("date")
+        .args(["-u", "+%Y-%m-%d"])
+        .output()
+        .unwrap();
+    let today = String::from_utf8(today.stdout).unwrap();
+    let expected = temp.path().join(format!("{}-ctx.md", today.trim()));
+    let markdown = fs::read_to_string(&expected).unwrap();
+    assert!(markdown.contains("dated"));
+}
+
+#[test]
+fn heredoc_copy_and_paste_round_trip() {
+    let temp = TempDir::new();
+    let src_dir = temp.path().join("project/src");
+    fs::create_dir_all(&src_dir).unwrap();
+    let main_rs = "fn main() {\n    println!(\"EOF\");\n}\n";
+    let readme = "# Notes\n\n```sh\necho hi\n```\n";
+    fs::write(src_dir.join("main.rs"), main_rs).unwrap();
+    fs::write(temp.path().join("project/README.md"), readme).unwrap();
+
+    let script_path = utf8(temp.path().join("context.sh"));
+    let copy_config = CopyConfig {
+        inputs: vec!["src".to_string(), "README.md".to_string()],
+        output: Some(script_path.clone()),
+        format: OutputFormat::Heredoc,
+        ..Default::default()
+    };
+    let copy_context = AppContext {
+        cwd: utf8(temp.path().join("project")),
+        verbosity: 0,
+    };
+    copy::run(&copy_context, copy_config).unwrap();
+
+    let restored = utf8(temp.path().join("restored"));
+    let paste_config = PasteConfig {
+        source: InputSource::File(script_path),
+        output_dir: restored.clone(),
+        ..Default::default()
+    };
+    let paste_context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let summary = paste::run(&paste_context, paste_config).unwrap();
+
+    assert_eq!(summary.written, 2);
+    assert_eq!(
+        fs::read_to_string(restored.join("src/main.rs")).unwrap(),
+        main_rs
+    );
+    assert_eq!(
+        fs::read_to_string(restored.join("README.md")).unwrap(),
+        readme
+    );
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args([
+            "-c",
+            "user.name=quickctx",
+            "-c",
+            "user.email=quickctx@example.com",
+            "-c",
+            "commit.gpgsign=false",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {args:?} failed");
+}
+
+#[test]
+fn aggregate_since_commit_only_includes_changed_files() {
+    let temp = TempDir::new();
+    let repo = temp.path();
+    git(repo, &["init", "-q"]);
+
+    fs::write(repo.join("keep.rs"), "fn keep() {}\n").unwrap();
+    fs::write(repo.join("changed.rs"), "fn changed() {}\n").unwrap();
+    fs::write(repo.join("gone.rs"), "fn gone() {}\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "first"]);
+
+    fs::write(repo.join("changed.rs"), "fn changed() { todo!() }\n").unwrap();
+    fs::remove_file(repo.join("gone.rs")).unwrap();
+    git(repo, &["add", "-A"]);
+    git(repo, &["commit", "-q", "-m", "second"]);
+
+    let context = AppContext {
+        cwd: utf8(repo),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(repo.join("out.md"));
+    let config = CopyConfig {
+        output: Some(output_path.clone()),
+        since_commit: Some("HEAD~1..HEAD".to_string()),
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    assert!(markdown.contains("changed.rs"));
+    assert!(markdown.contains("todo!()"));
+    assert!(!markdown.contains("keep.rs"));
+    assert!(!markdown.contains("gone.rs"));
+}
+
+#[test]
+fn aggregate_since_commit_surfaces_git_errors() {
+    let temp = TempDir::new();
+    git(temp.path(), &["init", "-q"]);
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let config = CopyConfig {
+        since_commit: Some("nope..HEAD".to_string()),
+        ..Default::default()
+    };
+
+    let err = copy::run(&context, config).unwrap_err().to_string();
+    assert!(err.starts_with("git error:"), "unexpected error: {err}");
+}
+
+#[test]
+fn aggregate_comment_style_round_trips_through_paste() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("hosts.txt"), "127.0.0.1 localhost\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["hosts.txt".to_string()],
+        format: OutputFormat::Comment,
+        comment_style: CommentStyle::Hash,
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(
+        markdown.contains("# hosts.txt\n127.0.0.1 localhost\n"),
+        "{markdown}"
+    );
+
+    let files = paste::extract_to_map(&markdown).unwrap();
+    assert_eq!(
+        files
+            .get(camino::Utf8Path::new("hosts.txt"))
+            .map(String::as_str),
+        Some("127.0.0.1 localhost\n")
+    );
+}
+
+#[test]
+fn aggregate_fail_if_empty_rejects_inputs_matching_nothing() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let output_path = utf8(temp.path().join("out.md"));
+    let config = |fail_if_empty| CopyConfig {
+        inputs: vec!["*.py".to_string()],
+        output: Some(output_path.clone()),
+        fail_if_empty,
+        ..Default::default()
+    };
+
+    copy::run(&context, config(false)).unwrap();
+    assert_eq!(fs::read_to_string(output_path.as_std_path()).unwrap(), "");
+
+    let err = copy::run(&context, config(true)).unwrap_err().to_string();
+    assert!(err.contains("no files matched"), "unexpected error: {err}");
+}
+
+#[test]
+fn aggregate_pack_selects_globs_minus_negations_in_order() {
+    let temp = TempDir::new();
+    let root = temp.path();
+    fs::create_dir_all(root.join("src/generated")).unwrap();
+    fs::create_dir_all(root.join("src/util")).unwrap();
+    fs::write(root.join("README.md"), "Demo project\n").unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("src/util/io.rs"), "pub fn io() {}\n").unwrap();
+    fs::write(root.join("src/generated/api.rs"), "pub struct Api;\n").unwrap();
+    fs::write(root.join("src/notes.txt"), "not rust\n").unwrap();
+    fs::write(
+        root.join(".copierpack"),
+        "# curated pack\nREADME.md\n\nsrc/**/*.rs\n!src/generated/**\n",
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(root),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        pack: Some(Utf8PathBuf::from(".copierpack")),
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    let files = paste::extract_to_map(&markdown).unwrap();
+    assert_eq!(
+        files.keys().map(|path| path.as_str()).collect::<Vec<_>>(),
+        vec!["README.md", "src/main.rs", "src/util/io.rs"]
+    );
+    let readme = markdown.find("README.md").unwrap();
+    let main = markdown.find("src/main.rs").unwrap();
+    let io = markdown.find("src/util/io.rs").unwrap();
+    assert!(readme < main && main < io);
+}
+
+#[test]
+fn language_stats_counts_files_and_bytes_per_language() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("b.rs"), "fn b() {}\n").unwrap();
+    fs::write(temp.path().join("tool.py"), "print('hello, world')\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec![".".to_string()],
+        ..Default::default()
+    };
+
+    let stats = copy::language_stats(&context, &config).unwrap();
+
+    assert_eq!(
+        stats,
+        vec![
+            LanguageStat {
+                language: "python".to_string(),
+                files: 1,
+                bytes: 22,
+            },
+            LanguageStat {
+                language: "rust".to_string(),
+                files: 2,
+                bytes: 20,
+            },
+        ]
+    );
+}
+
+#[test]
+fn aggregate_prefer_tracked_includes_force_added_ignored_files() {
+    let temp = TempDir::new();
+    let repo = temp.path();
+    git(repo, &["init", "-q"]);
+    fs::write(repo.join(".gitignore"), "*.gen.rs\n").unwrap();
+    fs::write(repo.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(repo.join("schema.gen.rs"), "pub struct Schema;\n").unwrap();
+    fs::write(repo.join("scratch.gen.rs"), "// untracked\n").unwrap();
+    git(repo, &["add", ".gitignore", "main.rs"]);
+    git(repo, &["add", "-f", "schema.gen.rs"]);
+    git(repo, &["commit", "-q", "-m", "first"]);
+
+    let context = AppContext {
+        cwd: utf8(repo),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec![".".to_string()],
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("fn main() {}"));
+    assert!(!markdown.contains("schema.gen.rs"));
+
+    let config = CopyConfig {
+        prefer_tracked: true,
+        ..config
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("fn main() {}"));
+    assert!(markdown.contains("pub struct Schema;"));
+    assert!(!markdown.contains("scratch.gen.rs"));
+}
+
+#[test]
+fn aggregate_append_keeps_earlier_documents() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("first.rs"), "fn first() {}\n").unwrap();
+    fs::write(temp.path().join("second.rs"), "fn second() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let output_path = utf8(temp.path().join("ctx.md"));
+    let append = |input: &str| {
+        let config = CopyConfig {
+            inputs: vec![input.to_string()],
+            output: Some(output_path.clone()),
+            append: true,
+            append_separator: "<!-- next -->".to_string(),
+            ..Default::default()
+        };
+        copy::run(&context, config).unwrap();
+    };
+
+    append("first.rs");
+    append("second.rs");
+
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+    let (before, after) = markdown.split_once("\n<!-- next -->\n\n").unwrap();
+    assert!(before.starts_with("first.rs\n") && before.contains("fn first() {}"));
+    assert!(after.starts_with("second.rs\n") && after.contains("fn second() {}"));
+    assert_eq!(markdown.matches("<!-- next -->").count(), 1);
+}
+
+#[test]
+fn aggregate_dedup_contents_references_first_copy() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("a.rs"), "// generated\npub struct Stub;\n").unwrap();
+    fs::write(temp.path().join("b.rs"), "// generated\npub struct Stub;\n").unwrap();
+    fs::write(temp.path().join("c.rs"), "fn unique() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec![".".to_string()],
+        dedup_contents: true,
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+
+    assert_eq!(markdown.matches("pub struct Stub;").count(), 1);
+    assert!(
+        markdown.contains("b.rs\n\n(identical to a.rs)\n"),
+        "{markdown}"
+    );
+    assert!(markdown.contains("fn unique() {}"));
+
+    let heredoc = CopyConfig {
+        format: OutputFormat::Heredoc,
+        ..config
+    };
+    let err = copy::run_to_string(&context, &heredoc)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("heredoc"), "unexpected error: {err}");
+}
+
+#[test]
+fn aggregate_since_tag_includes_only_changed_files() {
+    let temp = TempDir::new();
+    let repo = temp.path();
+    git(repo, &["init", "-q"]);
+
+    fs::write(repo.join("stable.rs"), "fn stable() {}\n").unwrap();
+    fs::write(repo.join("changed.rs"), "fn changed() {}\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "release"]);
+    git(repo, &["tag", "v1.0"]);
+
+    fs::write(repo.join("changed.rs"), "fn changed() { todo!() }\n").unwrap();
+    git(repo, &["commit", "-q", "-am", "after release"]);
+
+    let context = AppContext {
+        cwd: utf8(repo),
+        verbosity: 0,
+    };
+
+    for tag in [None, Some("v1.0".to_string())] {
+        let config = CopyConfig {
+            since_tag: Some(tag),
+            ..Default::default()
+        };
+
+        let markdown = copy::run_to_string(&context, &config).unwrap();
+        assert!(markdown.contains("changed.rs"));
+        assert!(markdown.contains("todo!()"));
+        assert!(!markdown.contains("stable.rs"));
+    }
+}
+
+#[test]
+fn aggregate_since_tag_without_tags_explains_how_to_fix() {
+    let temp = TempDir::new();
+    git(temp.path(), &["init", "-q"]);
+    fs::write(temp.path().join("a.rs"), "fn a() {}\n").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-q", "-m", "first"]);
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        since_tag: Some(None),
+        ..Default::default()
+    };
+
+    let err = copy::run_to_string(&context, &config)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("no git tags found"), "unexpected error: {err}");
+}
+
+#[test]
+fn aggregate_since_tag_outside_a_repository_reports_the_git_error() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("a.rs"), "fn a() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        since_tag: Some(None),
+        ..Default::default()
+    };
+
+    let err = copy::run_to_string(&context, &config)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        !err.contains("no git tags found"),
+        "unexpected error: {err}"
+    );
+    assert!(
+        err.contains("git tag --list failed"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn aggregate_git_info_notes_each_files_own_last_commit() {
+    let temp = TempDir::new();
+    git(temp.path(), &["init", "-q"]);
+    fs::create_dir(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("src/b.rs"), "fn b() {}\n").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-q", "-m", "add both"]);
+    fs::write(temp.path().join("src/b.rs"), "fn b2() {}\n").unwrap();
+    git(temp.path(), &["commit", "-q", "-am", "change b"]);
+
+    let last_sha = |file: &str| {
+        let output = std::process::Command::new("git")
+            .args(["log", "-1", "--format=%h", "--", file])
+            .current_dir(temp.path())
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+
+    let context = AppContext {
+        cwd: utf8(temp.path().join("src")),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["a.rs".to_string(), "b.rs".to_string()],
+        format: OutputFormat::Simple,
+        git_info: true,
+        ..Default::default()
+    };
+
+    let output = copy::run_to_string(&context, &config).unwrap();
+
+    let notes: Vec<&str> = output
+        .lines()
+        .filter(|line| line.starts_with("(last modified "))
+        .collect();
+    assert_eq!(notes.len(), 2, "{output}");
+    assert!(notes[0].contains(&last_sha("src/a.rs")), "{output}");
+    assert!(notes[1].contains(&last_sha("src/b.rs")), "{output}");
+    assert_ne!(last_sha("src/a.rs"), last_sha("src/b.rs"));
+}
+
+#[test]
+fn aggregate_annotated_sizes_round_trip_without_renaming_files() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("notes (12 B).txt"), "twelve bytes").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    for format in [OutputFormat::Simple, OutputFormat::Heading] {
+        let config = CopyConfig {
+            inputs: vec!["a.rs".to_string(), "notes (12 B).txt".to_string()],
+            format,
+            annotate_sizes: true,
+            ..Default::default()
+        };
+        let markdown = copy::run_to_string(&context, &config).unwrap();
+        assert!(markdown.contains("(size: 10 B)"), "{markdown}");
+
+        let files = paste::extract_to_map(&markdown).unwrap();
+        let paths: Vec<&str> = files.keys().map(|path| path.as_str()).collect();
+        assert_eq!(paths, vec!["a.rs", "notes (12 B).txt"], "{markdown}");
+    }
+
+    // A hand-written hint ending in a bare size keeps it as part of the name
+    let files = paste::extract_to_map("notes (12 B).txt\n\n```\ntwelve bytes\n```\n").unwrap();
+    assert!(files.contains_key(&Utf8PathBuf::from("notes (12 B).txt")));
+}
+
+#[test]
+fn aggregate_git_info_notes_last_commit() {
+    let temp = TempDir::new();
+    git(temp.path(), &["init", "-q"]);
+    fs::write(temp.path().join("tracked.rs"), "fn tracked() {}\n").unwrap();
+    git(temp.path(), &["add", "tracked.rs"]);
+    git(temp.path(), &["commit", "-q", "-m", "add tracked"]);
+    fs::write(temp.path().join("untracked.rs"), "fn untracked() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["tracked.rs".to_string(), "untracked.rs".to_string()],
+        format: OutputFormat::Simple,
+        git_info: true,
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+
+    let note = markdown
+        .lines()
+        .find(|line| line.starts_with("(last modified "))
+        .expect("git note");
+    assert!(
+        note.ends_with(")") && note.contains(" by quickctx on "),
+        "{note}"
+    );
+    assert!(markdown.starts_with("tracked.rs\n(last modified "));
+    assert_eq!(markdown.matches("(last modified ").count(), 1);
+
+    // The note does not confuse the path hint when pasting back
+    let files = paste::extract_to_map(&markdown).unwrap();
+    assert_eq!(
+        files.keys().map(|path| path.as_str()).collect::<Vec<_>>(),
+        vec!["tracked.rs", "untracked.rs"]
+    );
+}
+
+fn render_with_fence(temp: &TempDir, file: &str, fence: FencePreference) -> String {
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join(format!("{fence}.md")));
+    let config = CopyConfig {
+        inputs: vec![file.to_string()],
+        output: Some(output_path.clone()),
+        fence,
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    fs::read_to_string(output_path.as_std_path()).unwrap()
+}
+
+#[test]
+fn aggregate_smart_fence_widens_backticks_where_auto_switches_to_tilde() {
+    let temp = TempDir::new();
+    fs::write(
+        temp.path().join("nested.md"),
+        "````text\n```rust\nlet x = 1;\n```\n````\n",
+    )
+    .unwrap();
+
+    let auto = render_with_fence(&temp, "nested.md", FencePreference::Auto);
+    assert!(auto.contains("\n~~~markdown\n"));
+
+    let smart = render_with_fence(&temp, "nested.md", FencePreference::Smart);
+    assert!(smart.contains("\n`````markdown\n"));
+    assert_eq!(smart.matches("`````").count(), 2);
+    assert!(!smart.contains("~~~"));
+}
+
+#[test]
+fn aggregate_smart_fence_falls_back_to_tilde_past_backtick_cap() {
+    let temp = TempDir::new();
+    let long_run = "`".repeat(40);
+    fs::write(temp.path().join("wide.txt"), format!("{long_run}\n")).unwrap();
+
+    let smart = render_with_fence(&temp, "wide.txt", FencePreference::Smart);
+    assert!(smart.contains("\n~~~text\n"));
+}
+
+#[test]
+fn extract_skips_pathless_block_and_writes_the_rest() {
+    let temp = TempDir::new();
+
+    let markdown = r#"`src/good.rs`
+
+```rust
+fn good() {}
+```
+
+```rust
+fn orphan() {}
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+
+    let summary = paste::run(&context, extract_config).unwrap();
+
+    assert_eq!(summary.written, 1);
+    assert_eq!(summary.skipped_missing_path, 1);
+    assert_eq!(
+        fs::read_to_string(temp.path().join("output/src/good.rs")).unwrap(),
+        "fn good() {}\n"
+    );
+}
+
+#[test]
+fn extract_invalid_path_is_fatal_unless_keep_going() {
+    let temp = TempDir::new();
+
+    let markdown = r#"`src/good.rs`
+
+```rust
+fn good() {}
+```
+
+`../escape.rs`
+
+```rust
+fn escape() {}
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = |keep_going| PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        keep_going,
+        ..Default::default()
+    };
+
+    let err = paste::run(&context, config(false)).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("parent directory segments are not allowed")
+    );
+
+    let summary = paste::run(&context, config(true)).unwrap();
+    assert_eq!(summary.written, 1);
+    assert_eq!(summary.skipped_invalid, 1);
+    assert_eq!(summary.skipped_missing_path, 0);
+    assert!(!temp.path().join("escape.rs").exists());
+}
+
+#[test]
+fn extract_default_name_for_single_unnamed_block() {
+    let temp = TempDir::new();
+
+    let markdown = r#"
+```python
+print("hello")
+```
+"#;
+
+    let md_path = temp.path().join("input.md");
+    fs::write(&md_path, markdown).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let extract_config = PasteConfig {
+        source: InputSource::File(utf8(&md_path)),
+        output_dir: utf8(temp.path().join("output")),
+        conflict: ConflictStrategy::Overwrite,
+        default_name: Some("snippet.py".to_string()),
+        ..Default::default()
+    };
+
+    let summary = paste::run(&context, extract_config).unwrap();
+
+    assert_eq!(summary.written, 1);
+    assert_eq!(summary.skipped_missing_path, 0);
+    assert!(temp.path().join("output/snippet.py").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn aggregate_note_symlinks_records_target_without_inlining() {
+    let temp = TempDir::new();
+    let src = temp.path().join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("real.txt"), "secret contents\n").unwrap();
+    fs::write(temp.path().join("outside.txt"), "huge target\n").unwrap();
+    std::os::unix::fs::symlink("../outside.txt", src.join("link.txt")).unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let output_path = utf8(temp.path().join("out.md"));
+    let config = CopyConfig {
+        inputs: vec!["src".to_string()],
+        output: Some(output_path.clone()),
+        note_symlinks: true,
+        ..Default::default()
+    };
+
+    copy::run(&context, config).unwrap();
+    let markdown = fs::read_to_string(output_path.as_std_path()).unwrap();
+
+    assert!(markdown.contains("src/link.txt"));
+    assert!(markdown.contains("symlink -> ../outside.txt"));
+    assert!(!markdown.contains("huge target"));
+    assert!(markdown.contains("secret contents"));
+}
+
+#[test]
+fn roundtrip_mtime_manifest_restores_modification_times() {
+    let temp = TempDir::new();
+    let src = temp.path().join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("old.rs"), "fn old() {}\n").unwrap();
+    let recorded = 1_000_000_000;
+    filetime::set_file_mtime(
+        src.join("old.rs"),
+        filetime::FileTime::from_unix_time(recorded, 0),
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let markdown_path = utf8(temp.path().join("out.md"));
+    let manifest_path = utf8(temp.path().join("mtimes.json"));
+    let copy_config = CopyConfig {
+        inputs: vec!["src".to_string()],
+        output: Some(markdown_path.clone()),
+        mtime_manifest: Some(manifest_path.clone()),
+        ..Default::default()
+    };
+    copy::run(&context, copy_config).unwrap();
+
+    let paste_config = PasteConfig {
+        source: InputSource::File(markdown_path),
+        output_dir: utf8(temp.path().join("restored")),
+        conflict: ConflictStrategy::Overwrite,
+        mtime_manifest: Some(manifest_path),
+        ..Default::default()
+    };
+    paste::run(&context, paste_config).unwrap();
+
+    let restored = fs::metadata(temp.path().join("restored/src/old.rs"))
+        .unwrap()
+        .modified()
+        .unwrap()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    assert!(
+        (restored - recorded).abs() <= 1,
+        "mtime {restored} != {recorded}"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn aggregate_allow_lossy_paths_includes_non_utf8_names() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let temp = TempDir::new();
+    let src_dir = temp.path().join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(src_dir.join("ok.txt"), "valid name\n").unwrap();
+    fs::write(
+        src_dir.join(OsStr::from_bytes(b"bad\xFFname.txt")),
+        "lossy name\n",
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+
+    let render = |allow_lossy_paths: bool| {
+        let output_path = utf8(temp.path().join("out.md"));
+        let config = CopyConfig {
+            inputs: vec!["src/".to_string()],
+            output: Some(output_path.clone()),
+            allow_lossy_paths,
+            ..Default::default()
+        };
+        copy::run(&context, config).unwrap();
+        fs::read_to_string(output_path.as_std_path()).unwrap()
+    };
+
+    let skipped = render(false);
+    assert!(skipped.contains("valid name"));
+    assert!(!skipped.contains("lossy name"));
+
+    let included = render(true);
+    assert!(included.contains("valid name"));
+    assert!(included.contains("lossy name"));
+    assert!(included.contains("src/bad\u{FFFD}name.txt"));
+}
+
+#[test]
+fn aggregate_with_overview_leads_with_readme_and_manifest() {
+    let temp = TempDir::new();
+    let root = temp.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("AUTHORS"), "Jo Doe\n").unwrap();
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    fs::write(root.join("README.md"), "Demo project\n").unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn demo() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(root),
+        verbosity: 0,
+    };
+    let config = |inputs: &[&str]| CopyConfig {
+        inputs: inputs.iter().map(ToString::to_string).collect(),
+        format: OutputFormat::Simple,
+        with_overview: true,
+        ..Default::default()
+    };
+
+    // Added even though the inputs do not cover them
+    let markdown = copy::run_to_string(&context, &config(&["src/"])).unwrap();
+    assert!(markdown.starts_with("README.md\n"), "{markdown}");
+    let manifest = markdown.find("Cargo.toml\n").unwrap();
+    assert!(manifest < markdown.find("src/lib.rs\n").unwrap());
+
+    // Moved to the front, not repeated, when the inputs already include them
+    let markdown = copy::run_to_string(&context, &config(&["."])).unwrap();
+    assert!(markdown.starts_with("README.md\n"), "{markdown}");
+    assert_eq!(markdown.matches("Cargo.toml\n").count(), 1);
+    assert_eq!(markdown.matches("README.md\n").count(), 1);
+    assert!(markdown.find("Cargo.toml\n").unwrap() < markdown.find("AUTHORS\n").unwrap());
+}
+
+#[test]
+fn extract_infer_base_strips_root_only_the_first_block_names() {
+    let temp = TempDir::new();
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let paste_into = |name: &str, markdown: &str| {
+        let input = temp.path().join(format!("{name}.md"));
+        fs::write(&input, markdown).unwrap();
+        let config = PasteConfig {
+            source: InputSource::File(utf8(&input)),
+            output_dir: utf8(temp.path().join(name)),
+            conflict: ConflictStrategy::Overwrite,
+            infer_base: true,
+            ..Default::default()
+        };
+        paste::run(&context, config).unwrap();
+        temp.path().join(name)
+    };
+
+    let out = paste_into(
+        "inferred",
+        "myproject/src/lib.rs\n\n```rust\npub fn lib() {}\n```\n\nsrc/main.rs\n\n```rust\nfn main() {}\n```\n\nREADME.txt\n\n```text\nDemo\n```\n",
+    );
+    assert!(out.join("src/lib.rs").is_file());
+    assert!(out.join("src/main.rs").is_file());
+    assert!(out.join("README.txt").is_file());
+    assert!(!out.join("myproject").exists());
+
+    // A first block in an ordinary subdirectory shares no structure with the rest
+    let out = paste_into(
+        "subdirectory",
+        "docs/guide.txt\n\n```text\nGuide\n```\n\nsrc/main.rs\n\n```rust\nfn main() {}\n```\n",
+    );
+    assert!(out.join("docs/guide.txt").is_file());
+    assert!(!out.join("guide.txt").exists());
+
+    // Every block already carries the prefix, so there is nothing to strip
+    let out = paste_into(
+        "declined",
+        "myproject/README.txt\n\n```text\nDemo\n```\n\nmyproject/src/main.rs\n\n```rust\nfn main() {}\n```\n",
+    );
+    assert!(out.join("myproject/README.txt").is_file());
+    assert!(out.join("myproject/src/main.rs").is_file());
+}
+
+#[test]
+fn extract_append_hint_keeps_existing_file_contents() {
+    let temp = TempDir::new();
+    let out = temp.path().join("out");
+    fs::create_dir_all(out.join("tests")).unwrap();
+    fs::write(out.join("tests/cases.txt"), "case one\n").unwrap();
+    fs::write(out.join("notes.txt"), "old notes\n").unwrap();
+
+    let input = temp.path().join("input.md");
+    fs::write(
+        &input,
+        "<!-- append: tests/cases.txt -->\n\n```text\ncase two\n```\n\n\
+         <!-- append: tests/new.txt -->\n\n```text\nfirst\n```\n\n\
+         notes.txt\n\n```text\nnew notes\n```\n",
+    )
+    .unwrap();
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = PasteConfig {
+        source: InputSource::File(utf8(&input)),
+        output_dir: utf8(&out),
+        conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
+    };
+    let summary = paste::run(&context, config.clone()).unwrap();
+
+    assert_eq!(summary.written, 3);
+    assert_eq!(
+        fs::read_to_string(out.join("tests/cases.txt")).unwrap(),
+        "case one\n\ncase two\n"
+    );
+    assert_eq!(
+        fs::read_to_string(out.join("tests/new.txt")).unwrap(),
+        "first\n"
+    );
+    assert_eq!(
+        fs::read_to_string(out.join("notes.txt")).unwrap(),
+        "new notes\n"
+    );
+
+    // --append-blocks appends unmarked blocks too
+    fs::write(&input, "notes.txt\n\n```text\nmore notes\n```\n").unwrap();
+    let config = PasteConfig {
+        append_blocks: true,
+        ..config
+    };
+    paste::run(&context, config).unwrap();
+    assert_eq!(
+        fs::read_to_string(out.join("notes.txt")).unwrap(),
+        "new notes\n\nmore notes\n"
+    );
+
+    // The marker path is sanitized like any other hint
+    fs::write(
+        &input,
+        "<!-- append: ../escape.txt -->\n\n```text\nx\n```\n",
+    )
+    .unwrap();
+    let config = PasteConfig {
+        source: InputSource::File(utf8(&input)),
+        output_dir: utf8(&out),
+        ..Default::default()
+    };
+    assert!(paste::run(&context, config).is_err());
+    assert!(!temp.path().join("escape.txt").exists());
+}
+
+#[test]
+fn aggregate_max_file_fraction_drops_files_hogging_the_budget() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("src/big.rs"), "// filler\n".repeat(300)).unwrap();
+    fs::write(temp.path().join("src/c.rs"), "fn c() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        format: OutputFormat::Simple,
+        max_total_bytes: Some(4000),
+        max_file_fraction: Some(0.5),
+        ..Default::default()
+    };
+
+    // big.rs (3000 bytes) fits the budget but not half of it
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/a.rs") && markdown.contains("src/c.rs"));
+    assert!(!markdown.contains("src/big.rs"));
+    assert!(!markdown.contains("truncated"));
+
+    let config = CopyConfig {
+        max_file_fraction: Some(0.9),
+        ..config
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/big.rs"));
+}
+
+#[test]
+fn aggregate_max_file_fraction_applies_to_the_token_budget() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp.path().join("src/big.rs"), "// filler\n".repeat(300)).unwrap();
+    fs::write(temp.path().join("src/c.rs"), "fn c() {}\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let summary_path = utf8(temp.path().join("summary.json"));
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        output: Some(utf8(temp.path().join("out.md"))),
+        format: OutputFormat::Simple,
+        max_tokens: Some(1000),
+        max_file_fraction: Some(0.5),
+        summary_json: Some(summary_path.clone()),
+        ..Default::default()
+    };
+
+    // big.rs (~750 tokens) fits the budget but not half of it
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/a.rs") && markdown.contains("src/c.rs"));
+    assert!(!markdown.contains("src/big.rs"));
+
+    copy::run(&context, config).unwrap();
+    let summary: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&summary_path).unwrap()).unwrap();
+    assert_eq!(summary["skipped_over_budget"], 1);
+    assert_eq!(summary["skipped_excluded"], 0);
+}
+
+#[test]
+fn aggregate_max_file_size_skips_large_files() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::write(temp.path().join("src/small.rs"), "fn small() {}\n").unwrap();
+    fs::write(
+        temp.path().join("src/generated.rs"),
+        "// x\n".repeat(300 * 1024 / 5),
+    )
+    .unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["src/".to_string()],
+        format: OutputFormat::Simple,
+        max_file_size: Some(200 * 1024),
+        ..Default::default()
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/small.rs"));
+    assert!(!markdown.contains("src/generated.rs"));
+
+    // Both files named explicitly are still limited
+    let config = CopyConfig {
+        inputs: vec!["src/small.rs".to_string(), "src/generated.rs".to_string()],
+        ..config
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/small.rs"));
+    assert!(!markdown.contains("src/generated.rs"));
+
+    // A single file argument is copied anyway
+    let config = CopyConfig {
+        inputs: vec!["src/generated.rs".to_string()],
+        ..config
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/generated.rs"));
+}
+
+#[test]
+fn aggregate_parallel_reading_matches_serial_output() {
+    let temp = TempDir::new();
+    for dir in ["src", "src/nested", "docs"] {
+        fs::create_dir_all(temp.path().join(dir)).unwrap();
+    }
+    for index in 0..23 {
+        let dir = ["src", "src/nested", "docs"][index % 3];
+        fs::write(
+            temp.path().join(format!("{dir}/file{index:02}.rs")),
+            format!("fn f{index}() {{}}\n"),
+        )
+        .unwrap();
+    }
+    fs::write(temp.path().join("src/blob.rs"), [0u8, 159, 146, 150]).unwrap();
+    fs::write(temp.path().join("notes.txt"), "loose file\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let serial = CopyConfig {
+        inputs: vec!["src/".into(), "docs".into(), "notes.txt".into()],
+        format: OutputFormat::Simple,
+        jobs: 1,
+        ..Default::default()
+    };
+    let parallel = CopyConfig {
+        jobs: 4,
+        ..serial.clone()
+    };
+
+    let expected = copy::run_to_string(&context, &serial).unwrap();
+    assert!(expected.contains("src/nested/file01.rs"));
+    assert!(!expected.contains("src/blob.rs"));
+    assert_eq!(copy::run_to_string(&context, &parallel).unwrap(), expected);
+}
+
+#[cfg(unix)]
+#[test]
+fn aggregate_pipe_through_replaces_contents_with_command_output() {
+    let temp = TempDir::new();
+    fs::write(temp.path().join("hello.txt"), "hello world\n").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec!["hello.txt".to_string()],
+        format: OutputFormat::Simple,
+        pipe_through: Some("tr a-z A-Z".to_string()),
+        ..Default::default()
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("HELLO WORLD\n"), "{markdown}");
+    assert!(markdown.contains("hello.txt"));
+
+    // The shell keeps a quoted argument in one piece
+    let quoted = CopyConfig {
+        pipe_through: Some("sed 's/hello world/goodbye now/'".to_string()),
+        ..config.clone()
+    };
+    let markdown = copy::run_to_string(&context, &quoted).unwrap();
+    assert!(markdown.contains("goodbye now\n"), "{markdown}");
+
+    // A failing command keeps the original contents
+    let config = CopyConfig {
+        pipe_through: Some("false".to_string()),
+        ..config
+    };
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("hello world\n"), "{markdown}");
+}
+
+/// Test --exclude-tests drops common test files on top of user excludes
+#[test]
+fn aggregate_exclude_tests_drops_test_files() {
+    let temp = TempDir::new();
+    fs::create_dir_all(temp.path().join("src")).unwrap();
+    fs::create_dir_all(temp.path().join("tests")).unwrap();
+    fs::write(temp.path().join("src/lib.rs"), "pub fn lib() {}").unwrap();
+    fs::write(temp.path().join("src/notes.tmp"), "scratch").unwrap();
+    fs::write(temp.path().join("tests/foo.rs"), "#[test] fn foo() {}").unwrap();
+    fs::write(temp.path().join("test_bar.py"), "def test_bar(): pass").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(temp.path()),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec![".".to_string()],
+        format: OutputFormat::Simple,
+        excludes: vec!["**/*.tmp".to_string()],
+        exclude_tests: true,
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/lib.rs"), "{markdown}");
+    assert!(!markdown.contains("tests/foo.rs"), "{markdown}");
+    assert!(!markdown.contains("test_bar.py"), "{markdown}");
+    assert!(!markdown.contains("notes.tmp"), "{markdown}");
+}
+
+/// Test --exclude-tests ignores a `tests` directory above the project
+#[test]
+fn aggregate_exclude_tests_ignores_ancestor_tests_directory() {
+    let temp = TempDir::new();
+    let project = temp.path().join("tests/proj");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::create_dir_all(project.join("tests")).unwrap();
+    fs::write(project.join("src/lib.rs"), "pub fn lib() {}").unwrap();
+    fs::write(project.join("tests/foo.rs"), "#[test] fn foo() {}").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(&project),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec![".".to_string()],
+        format: OutputFormat::Simple,
+        exclude_tests: true,
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/lib.rs"), "{markdown}");
+    assert!(!markdown.contains("tests/foo.rs"), "{markdown}");
+}
+
+/// Test an absolute --exclude pattern still matches files under the working directory
+#[test]
+fn aggregate_exclude_matches_absolute_patterns() {
+    let temp = TempDir::new();
+    let project = temp.path().join("proj");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::create_dir_all(project.join("generated")).unwrap();
+    fs::write(project.join("src/lib.rs"), "pub fn lib() {}").unwrap();
+    fs::write(project.join("generated/api.rs"), "pub fn api() {}").unwrap();
+
+    let context = AppContext {
+        cwd: utf8(&project),
+        verbosity: 0,
+    };
+    let config = CopyConfig {
+        inputs: vec![".".to_string()],
+        format: OutputFormat::Simple,
+        excludes: vec![format!("{}/**", utf8(project.join("generated")))],
+        ..Default::default()
+    };
+
+    let markdown = copy::run_to_string(&context, &config).unwrap();
+    assert!(markdown.contains("src/lib.rs"), "{markdown}");
+    assert!(!markdown.contains("generated/api.rs"), "{markdown}");
 }
@@ -0,0 +1,56 @@
+// Tests for `quickctx-analyze --diff`, which narrows the expanded inputs to
+// files changed versus a git revision before they are grouped by project.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use quickctx::analyze::retain_changed_files;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=quickctx",
+            "-c",
+            "user.email=quickctx@example.com",
+            "-c",
+            "commit.gpgsign=false",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run git")
+        .status;
+    assert!(status.success(), "git {args:?} failed");
+}
+
+#[test]
+fn diff_keeps_only_files_changed_since_ref() {
+    let temp = TempDir::new().unwrap();
+    let repo = temp.path();
+    git(repo, &["init", "-q"]);
+    fs::create_dir_all(repo.join("src")).unwrap();
+    fs::write(repo.join("src/stable.py"), "def stable():\n    pass\n").unwrap();
+    fs::write(repo.join("src/edited.py"), "def edited():\n    pass\n").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "initial"]);
+
+    fs::write(repo.join("src/edited.py"), "def edited():\n    return 1\n").unwrap();
+
+    let files = vec![repo.join("src/stable.py"), repo.join("src/edited.py")];
+    let changed = retain_changed_files(files, repo, "HEAD").unwrap();
+
+    assert_eq!(changed, vec![repo.join("src/edited.py")]);
+}
+
+#[test]
+fn diff_outside_git_repo_errors() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("main.py"), "print('hi')\n").unwrap();
+
+    let files = vec![temp.path().join("main.py")];
+    let err = retain_changed_files(files, temp.path(), "HEAD").unwrap_err();
+    assert!(err.to_string().contains("git"), "unexpected error: {err}");
+}
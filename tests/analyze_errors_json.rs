@@ -0,0 +1,98 @@
+// Tests for `quickctx-analyze --errors-json`, which records failing projects
+// and skipped files as JSON and keeps analyzing the remaining projects.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use tempfile::TempDir;
+
+fn should_run_lsp_tests() -> bool {
+    std::env::var("QUICKCTX_TEST_LSP").is_ok()
+        || Command::new("rust-analyzer")
+            .arg("--version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+}
+
+/// A Python project whose configured language server does not exist
+fn broken_python_project(root: &Path) -> std::path::PathBuf {
+    let project = root.join("py");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("pyproject.toml"), "[project]\nname = \"py\"\n").unwrap();
+    fs::write(project.join("main.py"), "def main():\n    pass\n").unwrap();
+
+    let config = root.join("quickctx.toml");
+    fs::write(
+        &config,
+        "[analyze.lsp_servers]\npython = \"quickctx-missing-language-server\"\n",
+    )
+    .unwrap();
+    config
+}
+
+fn analyze(root: &Path, config: &Path, inputs: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_quickctx-analyze"))
+        .args(inputs)
+        .arg("--config")
+        .arg(config)
+        .arg("--errors-json")
+        .arg(root.join("errors.json"))
+        .args(["--no-cache", "--plain", "--format", "json"])
+        .output()
+        .unwrap()
+}
+
+fn read_records(root: &Path) -> Vec<serde_json::Value> {
+    let json = fs::read_to_string(root.join("errors.json")).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn missing_language_server_is_recorded() {
+    let temp = TempDir::new().unwrap();
+    let config = broken_python_project(temp.path());
+
+    let output = analyze(temp.path(), &config, &[&temp.path().join("py")]);
+
+    assert!(!output.status.success());
+    let records = read_records(temp.path());
+    assert_eq!(records.len(), 1, "{records:?}");
+    assert_eq!(records[0]["kind"], "spawn");
+    assert!(records[0].get("file").is_none());
+    assert!(
+        records[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("quickctx-missing-language-server")
+    );
+}
+
+#[test]
+fn other_projects_still_produce_output() {
+    if !should_run_lsp_tests() {
+        eprintln!("Skipping LSP test (rust-analyzer not available)");
+        return;
+    }
+
+    let temp = TempDir::new().unwrap();
+    let config = broken_python_project(temp.path());
+    let rust = temp.path().join("rs");
+    fs::create_dir_all(&rust).unwrap();
+    fs::write(rust.join("lib.rs"), "pub fn working() -> i32 {\n    1\n}\n").unwrap();
+
+    let output = analyze(
+        temp.path(),
+        &config,
+        &[&temp.path().join("py"), &rust.join("lib.rs")],
+    );
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("working"), "{stdout}");
+
+    let records = read_records(temp.path());
+    assert_eq!(records.len(), 1, "{records:?}");
+    assert_eq!(records[0]["project"], "py");
+}
@@ -0,0 +1,268 @@
+// `quickctx-analyze` driven end to end against a mock language server.
+//
+// The server command is a shell script that relays its stdio through two
+// FIFOs, so the test thread can answer LSP requests in-process.
+
+#![cfg(target_os = "linux")]
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::thread::{self, JoinHandle};
+
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+/// Read one `Content-Length` framed message, or `None` at EOF
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+
+    let mut content = vec![0u8; length?];
+    reader.read_exact(&mut content).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    writer.flush().unwrap();
+}
+
+type Responder = Box<dyn Fn(&str, &Value) -> Value + Send>;
+
+/// Answer requests with `respond(method, params)` until the client hangs up
+fn serve(requests: &Path, responses: &Path, respond: Responder) {
+    let mut reader = BufReader::new(File::open(requests).unwrap());
+    let mut writer = File::create(responses).unwrap();
+
+    while let Some(message) = read_message(&mut reader) {
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+        let result = match message["method"].as_str() {
+            Some("initialize") => json!({ "capabilities": { "documentSymbolProvider": true } }),
+            Some(method) => respond(method, &message["params"]),
+            None => Value::Null,
+        };
+        write_message(
+            &mut writer,
+            &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        );
+    }
+}
+
+fn mkfifo(path: &Path) {
+    let status = Command::new("mkfifo").arg(path).status().unwrap();
+    assert!(status.success(), "mkfifo {}", path.display());
+}
+
+fn range() -> Value {
+    json!({
+        "start": { "line": 0, "character": 0 },
+        "end": { "line": 0, "character": 30 },
+    })
+}
+
+fn symbol(name: &str, kind: u32, detail: &str) -> Value {
+    json!({
+        "name": name,
+        "kind": kind,
+        "detail": detail,
+        "range": range(),
+        "selectionRange": range(),
+    })
+}
+
+/// A Rust project at `<dir>/demo` whose `src/lib.rs` holds `source`, served by a mock
+struct MockRun {
+    dir: TempDir,
+    server: PathBuf,
+    mock: JoinHandle<()>,
+}
+
+impl MockRun {
+    fn new(source: &str, respond: impl Fn(&Path, &str, &Value) -> Value + Send + 'static) -> Self {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("demo");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(project.join("src/lib.rs"), source).unwrap();
+
+        let requests = dir.path().join("requests");
+        let responses = dir.path().join("responses");
+        mkfifo(&requests);
+        mkfifo(&responses);
+
+        // Background jobs get /dev/null as stdin, so hand the pipe over as fd 3
+        let server = dir.path().join("mock-lsp.sh");
+        fs::write(
+            &server,
+            format!(
+                "#!/bin/sh\nexec 3<&0\ncat <&3 > '{}' &\nexec cat '{}' 3<&-\n",
+                requests.display(),
+                responses.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&server, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let root = dir.path().to_path_buf();
+        let respond: Responder = Box::new(move |method, params| respond(&root, method, params));
+        let mock = thread::spawn(move || serve(&requests, &responses, respond));
+        Self { dir, server, mock }
+    }
+
+    /// Analyze `src/lib.rs` with `args`; the run must exit as `success` says
+    fn run(self, args: &[&str], success: bool) -> (String, String) {
+        let output: Output = Command::new(env!("CARGO_BIN_EXE_quickctx-analyze"))
+            .arg(self.dir.path().join("demo/src/lib.rs"))
+            .arg("--lsp-server")
+            .arg(&self.server)
+            .args(["--no-cache", "--plain"])
+            .args(args)
+            .output()
+            .unwrap();
+
+        // Checked before joining: the mock stays blocked on its FIFOs if the server never started
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            output.status.code().is_some(),
+            "analyze was killed: {stderr}"
+        );
+        self.mock.join().unwrap();
+        assert_eq!(output.status.success(), success, "{stderr}");
+        (stdout, stderr)
+    }
+}
+
+#[test]
+fn timings_report_symbol_extraction_from_a_symbol_run() {
+    let run = MockRun::new(
+        "pub fn answer() -> u32 { 42 }\n",
+        |_, method, _| match method {
+            "textDocument/documentSymbol" => {
+                json!([symbol("answer", 12, "pub fn answer() -> u32")])
+            }
+            _ => Value::Null,
+        },
+    );
+    let (stdout, stderr) = run.run(&["--timings", "--format", "json"], true);
+    assert!(stdout.contains("\"answer\""), "{stdout}");
+
+    let timings = stderr
+        .split_once("Timings:\n")
+        .map(|(_, report)| report)
+        .expect("timings report");
+    let extraction = timings
+        .lines()
+        .find(|line| line.trim_start().starts_with("symbol extraction"))
+        .expect("symbol extraction timing");
+    assert!(extraction.trim_end().ends_with('s'), "{extraction}");
+    assert!(timings.contains("LSP startup"), "{timings}");
+}
+
+/// `answer` references two types defined in a file outside the project
+fn external_types_run() -> MockRun {
+    let run = MockRun::new(
+        "pub fn answer(a: Alpha) -> Beta { todo!() }\n",
+        |root, method, params| {
+            let external = root.join("ext/types.rs");
+            match method {
+                "textDocument/documentSymbol" => {
+                    let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                    if uri.ends_with("ext/types.rs") {
+                        json!([
+                            symbol("Alpha", 23, "pub struct Alpha"),
+                            symbol("Beta", 23, "pub struct Beta"),
+                        ])
+                    } else {
+                        json!([symbol("answer", 12, "pub fn answer(a: Alpha) -> Beta")])
+                    }
+                }
+                "textDocument/typeDefinition" => json!({
+                    "uri": format!("file://{}", external.display()),
+                    "range": range(),
+                }),
+                _ => Value::Null,
+            }
+        },
+    );
+    fs::create_dir_all(run.dir.path().join("ext")).unwrap();
+    fs::write(
+        run.dir.path().join("ext/types.rs"),
+        "pub struct Alpha;\npub struct Beta;\n",
+    )
+    .unwrap();
+    run
+}
+
+#[test]
+fn max_symbols_ignores_the_external_dependencies_pseudo_file() {
+    // The pseudo-file lists two external types, over the limit on its own
+    let (stdout, stderr) = external_types_run().run(
+        &["--max-symbols", "1", "--fail-over", "--format", "json"],
+        true,
+    );
+    assert!(stdout.contains("_external_dependencies_demo"), "{stdout}");
+    assert!(stdout.contains("\"Beta\""), "{stdout}");
+    assert!(!stderr.contains("top-level symbols"), "{stderr}");
+
+    // The gate still fires for the real file, and only for it
+    let (_, stderr) = external_types_run().run(
+        &["--max-symbols", "0", "--fail-over", "--format", "json"],
+        false,
+    );
+    assert!(
+        stderr.contains("src/lib.rs: 1 top-level symbols (max 0)"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("_external_dependencies_"), "{stderr}");
+}
+
+#[test]
+fn max_symbols_counts_files_before_symbol_filters() {
+    let run = MockRun::new("pub fn a() {}\nfn b() {}\n", |_, method, _| match method {
+        "textDocument/documentSymbol" => {
+            json!([symbol("a", 12, "pub fn a()"), symbol("b", 12, "fn b()"),])
+        }
+        _ => Value::Null,
+    });
+    // Both the private filter and --filter-symbols leave one symbol in the report
+    let (stdout, stderr) = run.run(
+        &[
+            "--max-symbols",
+            "1",
+            "--fail-over",
+            "--filter-symbols",
+            "a",
+            "--format",
+            "json",
+        ],
+        false,
+    );
+    assert!(!stdout.contains("\"b\""), "{stdout}");
+    assert!(
+        stderr.contains("src/lib.rs: 2 top-level symbols (max 1)"),
+        "{stderr}"
+    );
+}
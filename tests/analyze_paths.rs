@@ -0,0 +1,59 @@
+// Tests for `quickctx-analyze --paths-relative-to`, which reports files
+// relative to a shared base (such as a monorepo root) instead of each
+// project's own root.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+fn should_run_lsp_tests() -> bool {
+    std::env::var("QUICKCTX_TEST_LSP").is_ok()
+        || Command::new("rust-analyzer")
+            .arg("--version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+}
+
+fn crate_at(dir: &Path, name: &str) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        format!("pub fn {name}() -> i32 {{\n    1\n}}\n"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn projects_share_the_monorepo_prefix() {
+    if !should_run_lsp_tests() {
+        eprintln!("Skipping LSP test (rust-analyzer not available)");
+        return;
+    }
+
+    let temp = TempDir::new().unwrap();
+    let mono = temp.path();
+    crate_at(&mono.join("crates/alpha"), "alpha");
+    crate_at(&mono.join("crates/beta"), "beta");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_quickctx-analyze"))
+        .arg(mono.join("crates/alpha/src/lib.rs"))
+        .arg(mono.join("crates/beta/src/lib.rs"))
+        .arg("--paths-relative-to")
+        .arg(mono)
+        .args(["--no-cache", "--plain", "--format", "json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("crates/alpha/src/lib.rs"), "{stdout}");
+    assert!(stdout.contains("crates/beta/src/lib.rs"), "{stdout}");
+}
@@ -0,0 +1,31 @@
+// Tests for `quickctx-analyze --plain`, which hides progress bars and the
+// `[n/m] ✓ ...` stage lines so only log output reaches stderr.
+
+use std::fs;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+/// Run the analyze binary over a directory and return its stderr
+fn analyze_stderr(extra_args: &[&str]) -> String {
+    // No file here has LSP support, so the run stops right after the file scan
+    // and never needs a language server
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("notes.txt"), "hello\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_quickctx-analyze"))
+        .arg(dir.path())
+        .args(extra_args)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn plain_mode_hides_stage_banners() {
+    assert!(analyze_stderr(&[]).contains("[1/4] ✓ Finding source files"));
+
+    let plain = analyze_stderr(&["--plain"]);
+    assert!(!plain.contains("[1/4]"));
+    assert!(plain.contains("No valid source files found"));
+}
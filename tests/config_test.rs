@@ -3,9 +3,12 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+use clap::Parser;
+
 use quickctx::cli::{Cli, Commands, CopyArgs, PasteArgs};
 use quickctx::config::{
-    self, ConflictStrategy, CopyConfig, FencePreference, ModeConfig, OutputFormat,
+    self, ByteSize, ChunkSize, ConflictStrategy, CopyConfig, FencePreference, HeaderTemplate,
+    ModeConfig, OutputFormat,
 };
 
 // Mutex to serialize tests that change current directory
@@ -65,6 +68,7 @@ fn test_aggregate_config_require_inputs_empty() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     let result = config.require_inputs();
@@ -82,6 +86,7 @@ fn test_aggregate_config_require_inputs_with_paths() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     assert!(config.require_inputs().is_ok());
@@ -95,8 +100,9 @@ fn test_load_config_default_aggregate_mode() {
     env::set_current_dir(temp.path()).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 0,
+        env: None,
         copy: CopyArgs {
             paths: vec![PathBuf::from("src/")],
             output: None,
@@ -105,6 +111,7 @@ fn test_load_config_default_aggregate_mode() {
             no_gitignore: false,
             ignore_file: vec![],
             exclude: vec![],
+            ..Default::default()
         },
         command: None,
     };
@@ -137,10 +144,11 @@ fn test_load_config_explicit_aggregate_mode() {
     env::set_current_dir(temp.path()).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 1,
+        env: None,
         copy: CopyArgs::default(),
-        command: Some(Commands::Copy(CopyArgs {
+        command: Some(Commands::Copy(Box::new(CopyArgs {
             paths: vec![PathBuf::from("lib/")],
             output: Some(PathBuf::from("out.md")),
             format: Some(OutputFormat::Comment),
@@ -148,7 +156,8 @@ fn test_load_config_explicit_aggregate_mode() {
             no_gitignore: true,
             ignore_file: vec![],
             exclude: vec!["*.log".to_string()],
-        })),
+            ..Default::default()
+        }))),
     };
 
     let result = config::load(&cli);
@@ -184,13 +193,15 @@ fn test_load_config_extract_mode_from_file() {
     fs::write(&input_path, "# test\n```rust\nfn main() {}\n```").unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 0,
+        env: None,
         copy: CopyArgs::default(),
         command: Some(Commands::Paste(PasteArgs {
             input: Some(input_path.clone()),
             output_dir: Some(PathBuf::from("extracted/")),
             conflict: Some(ConflictStrategy::Overwrite),
+            ..Default::default()
         })),
     };
 
@@ -218,13 +229,15 @@ fn test_load_config_extract_mode_from_stdin() {
     env::set_current_dir(temp.path()).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 2,
+        env: None,
         copy: CopyArgs::default(),
         command: Some(Commands::Paste(PasteArgs {
             input: None,
             output_dir: None,
             conflict: Some(ConflictStrategy::Skip),
+            ..Default::default()
         })),
     };
 
@@ -271,8 +284,9 @@ conflict = "overwrite"
     fs::write(temp.path().join("quickctx.toml"), config_content).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 0,
+        env: None,
         copy: CopyArgs::default(),
         command: None,
     };
@@ -317,8 +331,9 @@ format = "simple"
     fs::write(temp.path().join("quickctx.toml"), config_content).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 0,
+        env: None,
         copy: CopyArgs {
             paths: vec![PathBuf::from("from-cli/")],
             format: Some(OutputFormat::Comment),
@@ -327,6 +342,7 @@ format = "simple"
             no_gitignore: false,
             ignore_file: vec![],
             exclude: vec![],
+            ..Default::default()
         },
         command: None,
     };
@@ -365,8 +381,9 @@ verbose = 3
     fs::write(&custom_config_path, config_content).unwrap();
 
     let cli = Cli {
-        config: Some(custom_config_path),
+        config: vec![custom_config_path],
         verbose: 0,
+        env: None,
         copy: CopyArgs {
             paths: vec![PathBuf::from("src/")],
             output: None,
@@ -375,6 +392,7 @@ verbose = 3
             no_gitignore: false,
             ignore_file: vec![],
             exclude: vec![],
+            ..Default::default()
         },
         command: None,
     };
@@ -400,8 +418,9 @@ fn test_load_config_invalid_toml() {
     fs::write(&config_path, invalid_config).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 0,
+        env: None,
         copy: CopyArgs {
             paths: vec![PathBuf::from("src/")],
             output: None,
@@ -410,6 +429,7 @@ fn test_load_config_invalid_toml() {
             no_gitignore: false,
             ignore_file: vec![],
             exclude: vec![],
+            ..Default::default()
         },
         command: None,
     };
@@ -434,8 +454,9 @@ fn test_aggregate_config_with_multiple_ignore_files() {
     fs::write(&ignore2, "*.tmp").unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 0,
+        env: None,
         copy: CopyArgs {
             paths: vec![PathBuf::from("src/")],
             output: None,
@@ -444,6 +465,7 @@ fn test_aggregate_config_with_multiple_ignore_files() {
             no_gitignore: false,
             ignore_file: vec![ignore1.clone(), ignore2.clone()],
             exclude: vec![],
+            ..Default::default()
         },
         command: None,
     };
@@ -471,8 +493,9 @@ fn test_aggregate_config_no_gitignore_flag() {
     env::set_current_dir(temp.path()).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 0,
+        env: None,
         copy: CopyArgs {
             paths: vec![PathBuf::from("src/")],
             output: None,
@@ -481,6 +504,7 @@ fn test_aggregate_config_no_gitignore_flag() {
             no_gitignore: true,
             ignore_file: vec![],
             exclude: vec![],
+            ..Default::default()
         },
         command: None,
     };
@@ -515,8 +539,9 @@ verbose = 2
     fs::write(temp.path().join("quickctx.toml"), config_content).unwrap();
 
     let cli = Cli {
-        config: None,
+        config: Vec::new(),
         verbose: 1,
+        env: None,
         copy: CopyArgs {
             paths: vec![PathBuf::from("src/")],
             output: None,
@@ -525,6 +550,7 @@ verbose = 2
             no_gitignore: false,
             ignore_file: vec![],
             exclude: vec![],
+            ..Default::default()
         },
         command: None,
     };
@@ -539,6 +565,89 @@ verbose = 2
     env::set_current_dir(original_dir).unwrap();
 }
 
+#[test]
+fn test_load_config_include_empty_from_file_and_cli() {
+    let _lock = CWD_LOCK.lock().unwrap();
+    let temp = TempDir::new();
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp.path()).unwrap();
+
+    let cli = Cli {
+        config: Vec::new(),
+        verbose: 0,
+        env: None,
+        copy: CopyArgs::default(),
+        command: None,
+    };
+    match config::load(&cli).unwrap().mode {
+        ModeConfig::Copy(cfg) => assert!(!cfg.include_empty),
+        _ => panic!("Expected Copy mode"),
+    }
+
+    fs::write(
+        temp.path().join("quickctx.toml"),
+        "[copy]\ninclude_empty = true\n",
+    )
+    .unwrap();
+    match config::load(&cli).unwrap().mode {
+        ModeConfig::Copy(cfg) => assert!(cfg.include_empty),
+        _ => panic!("Expected Copy mode"),
+    }
+
+    fs::remove_file(temp.path().join("quickctx.toml")).unwrap();
+    let cli = Cli {
+        config: Vec::new(),
+        verbose: 0,
+        env: None,
+        copy: CopyArgs {
+            include_empty: true,
+            ..Default::default()
+        },
+        command: None,
+    };
+    match config::load(&cli).unwrap().mode {
+        ModeConfig::Copy(cfg) => assert!(cfg.include_empty),
+        _ => panic!("Expected Copy mode"),
+    }
+
+    env::set_current_dir(original_dir).unwrap();
+}
+
+#[test]
+fn test_load_config_rejects_out_of_range_heading_level() {
+    let _lock = CWD_LOCK.lock().unwrap();
+    let temp = TempDir::new();
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp.path()).unwrap();
+
+    let cli = Cli {
+        config: Vec::new(),
+        verbose: 0,
+        env: None,
+        copy: CopyArgs::default(),
+        command: None,
+    };
+
+    fs::write(
+        temp.path().join("quickctx.toml"),
+        "[copy]\nheading_level = 3\n",
+    )
+    .unwrap();
+    match config::load(&cli).unwrap().mode {
+        ModeConfig::Copy(cfg) => assert_eq!(cfg.heading_level, 3),
+        _ => panic!("Expected Copy mode"),
+    }
+
+    fs::write(
+        temp.path().join("quickctx.toml"),
+        "[copy]\nheading_level = 7\n",
+    )
+    .unwrap();
+    assert!(config::load(&cli).is_err());
+
+    env::set_current_dir(original_dir).unwrap();
+}
+
 #[test]
 fn test_output_format_display() {
     assert_eq!(format!("{}", OutputFormat::Simple), "simple");
@@ -553,9 +662,292 @@ fn test_fence_preference_display() {
     assert_eq!(format!("{}", FencePreference::Tilde), "tilde");
 }
 
+#[test]
+fn test_chunk_size_parse() {
+    assert_eq!("4096".parse(), Ok(ChunkSize::Bytes(4096)));
+    assert_eq!("8000t".parse(), Ok(ChunkSize::Tokens(8000)));
+    assert!("0".parse::<ChunkSize>().is_err());
+    assert_eq!("8k".parse(), Ok(ChunkSize::Bytes(8 * 1024)));
+    assert_eq!("1.5m".parse(), Ok(ChunkSize::Bytes(1536 * 1024)));
+    assert!("0k".parse::<ChunkSize>().is_err());
+    assert!("0t".parse::<ChunkSize>().is_err());
+    assert!("8kt".parse::<ChunkSize>().is_err());
+}
+
+#[test]
+fn test_byte_size_parse() {
+    assert_eq!("512".parse(), Ok(ByteSize(512)));
+    assert_eq!("200k".parse(), Ok(ByteSize(200 * 1024)));
+    assert_eq!("2M".parse(), Ok(ByteSize(2 * 1024 * 1024)));
+    assert_eq!("1g".parse(), Ok(ByteSize(1 << 30)));
+    assert!("10x".parse::<ByteSize>().is_err());
+    assert!("k".parse::<ByteSize>().is_err());
+}
+
+#[test]
+fn test_header_template_rejects_unknown_placeholders() {
+    assert!(
+        "{index}. {path} ({lang}, {size})"
+            .parse::<HeaderTemplate>()
+            .is_ok()
+    );
+    assert!("plain header".parse::<HeaderTemplate>().is_ok());
+
+    let err = "{path} by {author}".parse::<HeaderTemplate>().unwrap_err();
+    assert!(err.contains("{author}"), "{err}");
+    assert!("{path".parse::<HeaderTemplate>().is_err());
+}
+
 #[test]
 fn test_conflict_strategy_display() {
     assert_eq!(format!("{}", ConflictStrategy::Prompt), "prompt");
     assert_eq!(format!("{}", ConflictStrategy::Skip), "skip");
     assert_eq!(format!("{}", ConflictStrategy::Overwrite), "overwrite");
 }
+
+#[test]
+fn test_load_config_merges_repeated_config_files() {
+    let _lock = CWD_LOCK.lock().unwrap();
+    let temp = TempDir::new();
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp.path()).unwrap();
+
+    let base_path = temp.path().join("base.toml");
+    fs::write(
+        &base_path,
+        r#"
+[copy]
+format = "simple"
+fence = "tilde"
+exclude = ["*.tmp"]
+"#,
+    )
+    .unwrap();
+
+    let overlay_path = temp.path().join("overlay.toml");
+    fs::write(
+        &overlay_path,
+        r#"
+[copy]
+format = "heading"
+exclude = ["*.bak"]
+"#,
+    )
+    .unwrap();
+
+    let cli = Cli {
+        config: vec![base_path, overlay_path],
+        verbose: 0,
+        env: None,
+        copy: CopyArgs {
+            paths: vec![PathBuf::from("src/")],
+            ..Default::default()
+        },
+        command: None,
+    };
+
+    let runtime_config = config::load(&cli).unwrap();
+    env::set_current_dir(original_dir).unwrap();
+
+    match runtime_config.mode {
+        ModeConfig::Copy(cfg) => {
+            // Later file wins for scalars, earlier values survive when unset
+            assert_eq!(cfg.format, OutputFormat::Heading);
+            assert_eq!(cfg.fence, FencePreference::Tilde);
+            // Vecs concatenate in file order
+            assert_eq!(cfg.excludes, vec!["*.tmp".to_string(), "*.bak".to_string()]);
+        }
+        _ => panic!("Expected Copy mode"),
+    }
+}
+
+#[test]
+fn test_load_config_applies_env_overlay() {
+    let _lock = CWD_LOCK.lock().unwrap();
+    let temp = TempDir::new();
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp.path()).unwrap();
+
+    fs::write(
+        temp.path().join("quickctx.toml"),
+        r#"
+[copy]
+output = "context.md"
+format = "simple"
+
+[env.staging.copy]
+output = "staging.md"
+"#,
+    )
+    .unwrap();
+
+    let cli = |env: Option<&str>| Cli {
+        config: Vec::new(),
+        verbose: 0,
+        env: env.map(str::to_string),
+        copy: CopyArgs {
+            paths: vec![PathBuf::from("src/")],
+            ..Default::default()
+        },
+        command: None,
+    };
+    let output_of = |cli: &Cli| match config::load(cli).map(|runtime| runtime.mode) {
+        Ok(ModeConfig::Copy(cfg)) => Ok((cfg.output, cfg.format)),
+        Ok(_) => panic!("Expected Copy mode"),
+        Err(err) => Err(err),
+    };
+
+    let original_env = env::var_os("QUICKCTX_ENV");
+    // SAFETY: tests that read the environment through config::load hold CWD_LOCK
+    unsafe { env::remove_var("QUICKCTX_ENV") };
+    let base = output_of(&cli(None));
+    unsafe { env::set_var("QUICKCTX_ENV", "staging") };
+    let from_var = output_of(&cli(None));
+    let missing = output_of(&cli(Some("production")));
+    match original_env {
+        Some(value) => unsafe { env::set_var("QUICKCTX_ENV", value) },
+        None => unsafe { env::remove_var("QUICKCTX_ENV") },
+    }
+    env::set_current_dir(original_dir).unwrap();
+
+    let (output, format) = base.unwrap();
+    assert_eq!(output.unwrap(), "context.md");
+    assert_eq!(format, OutputFormat::Simple);
+
+    // The overlay replaces the output but keeps unrelated base settings
+    let (output, format) = from_var.unwrap();
+    assert_eq!(output.unwrap(), "staging.md");
+    assert_eq!(format, OutputFormat::Simple);
+
+    // --env takes precedence over the variable and must name a known table
+    assert!(missing.is_err());
+}
+
+#[test]
+fn test_config_schema_lists_output_formats() {
+    let schema = config::file_config_schema().unwrap();
+    let schema: serde_json::Value = serde_json::from_str(&schema).unwrap();
+
+    let formats = schema["$defs"]["OutputFormat"]["enum"]
+        .as_array()
+        .expect("OutputFormat enum in schema");
+    let formats: Vec<&str> = formats.iter().filter_map(|v| v.as_str()).collect();
+    assert_eq!(formats, ["simple", "comment", "heading", "heredoc"]);
+
+    for section in ["copy", "paste", "general", "analyze"] {
+        assert!(
+            schema["properties"][section].is_object(),
+            "missing {section}"
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_update_disable_persists_check_updates_in_user_config() {
+    use quickctx::config::{AppContext, UpdateConfig};
+    use quickctx::update;
+
+    let _lock = CWD_LOCK.lock().unwrap();
+    let temp = TempDir::new();
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp.path()).unwrap();
+    let original_xdg = env::var_os("XDG_CONFIG_HOME");
+    // SAFETY: tests that read the environment through config::load hold CWD_LOCK
+    unsafe { env::set_var("XDG_CONFIG_HOME", temp.path().join("xdg")) };
+
+    let user_config = config::user_config_path().unwrap();
+    fs::create_dir_all(user_config.parent().unwrap()).unwrap();
+    fs::write(&user_config, "# my settings\n[general]\nverbose = 1\n").unwrap();
+
+    let context = AppContext {
+        cwd: temp.path().to_str().unwrap().into(),
+        verbosity: 0,
+    };
+    let set_check_updates = |enabled| UpdateConfig {
+        check_only: false,
+        yes: false,
+        set_check_updates: Some(enabled),
+    };
+    let cli = Cli {
+        config: Vec::new(),
+        verbose: 0,
+        env: None,
+        copy: CopyArgs {
+            paths: vec![PathBuf::from("src/")],
+            ..Default::default()
+        },
+        command: None,
+    };
+
+    update::run(&context, set_check_updates(false)).unwrap();
+    let disabled = config::load(&cli).unwrap();
+    let written = fs::read_to_string(&user_config).unwrap();
+
+    update::run(&context, set_check_updates(true)).unwrap();
+    let enabled = config::load(&cli).unwrap();
+
+    match original_xdg {
+        Some(value) => unsafe { env::set_var("XDG_CONFIG_HOME", value) },
+        None => unsafe { env::remove_var("XDG_CONFIG_HOME") },
+    }
+    env::set_current_dir(original_dir).unwrap();
+
+    assert!(!disabled.check_updates);
+    // Only check_updates is read from the user config
+    assert_eq!(disabled.context.verbosity, 0);
+    assert!(written.starts_with("# my settings\n"));
+    assert!(written.contains("check_updates = false"));
+    assert!(enabled.check_updates);
+}
+
+#[test]
+fn test_since_tag_value_requires_equals() {
+    let cli = Cli::try_parse_from(["quickctx", "--since-tag", "src/"]).unwrap();
+    assert_eq!(cli.copy.since_tag, Some(None));
+    assert_eq!(cli.copy.paths, vec![PathBuf::from("src/")]);
+
+    let cli = Cli::try_parse_from(["quickctx", "--since-tag=v1.0", "src/"]).unwrap();
+    assert_eq!(cli.copy.since_tag, Some(Some("v1.0".to_string())));
+}
+
+#[test]
+fn test_analyze_config_applies_env_overlay() {
+    let temp = TempDir::new();
+    let config_path = temp.path().join("quickctx.toml");
+    fs::write(
+        &config_path,
+        r#"
+[analyze]
+skip = ["vendor/**"]
+lsp_readiness_timeout_secs = 30
+
+[env.ci.analyze]
+skip = ["generated/**"]
+lsp_readiness_timeout_secs = 120
+"#,
+    )
+    .unwrap();
+
+    let base = config::load_analyze_config(Some(&config_path), None).unwrap();
+    assert_eq!(base.skip, vec!["vendor/**"]);
+    assert_eq!(base.lsp_readiness_timeout_secs, Some(30));
+
+    let ci = config::load_analyze_config(Some(&config_path), Some("ci")).unwrap();
+    assert_eq!(ci.skip, vec!["vendor/**", "generated/**"]);
+    assert_eq!(ci.lsp_readiness_timeout_secs, Some(120));
+
+    assert!(config::load_analyze_config(Some(&config_path), Some("prod")).is_err());
+}
+
+#[test]
+fn test_dirs_first_is_not_a_copy_option() {
+    assert!(Cli::try_parse_from(["quickctx", "--dirs-first", "src/"]).is_err());
+}
+
+#[test]
+fn test_max_total_bytes_accepts_size_suffixes() {
+    let cli = Cli::try_parse_from(["quickctx", "--max-total-bytes", "2k", "src/"]).unwrap();
+    assert_eq!(cli.copy.max_total_bytes, Some(ByteSize(2048)));
+    assert!(Cli::try_parse_from(["quickctx", "--max-total-bytes", "2x", "src/"]).is_err());
+}
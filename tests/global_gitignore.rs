@@ -0,0 +1,60 @@
+// Tests for honouring (or skipping) the user's global gitignore.
+//
+// The global gitignore is located through HOME and XDG_CONFIG_HOME, so these
+// tests live in their own binary where changing the environment cannot leak
+// into tests running in parallel.
+
+#![cfg(unix)]
+
+use std::env;
+use std::fs;
+
+use camino::Utf8PathBuf;
+use tempfile::TempDir;
+
+use quickctx::config::{AppContext, CopyConfig};
+use quickctx::copy;
+
+#[test]
+fn no_global_gitignore_includes_globally_ignored_files() {
+    let home = TempDir::new().unwrap();
+    let xdg = home.path().join(".config");
+    fs::create_dir_all(xdg.join("git")).unwrap();
+    fs::write(xdg.join("git/ignore"), "*.scratch\n").unwrap();
+    // SAFETY: this is the only test in this binary, so nothing reads the environment concurrently
+    unsafe {
+        env::set_var("HOME", home.path());
+        env::set_var("XDG_CONFIG_HOME", &xdg);
+    }
+
+    let project = TempDir::new().unwrap();
+    fs::create_dir_all(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(project.path().join("src/notes.scratch"), "scratch notes\n").unwrap();
+
+    let cwd = Utf8PathBuf::from_path_buf(project.path().to_path_buf()).unwrap();
+    let context = AppContext {
+        cwd: cwd.clone(),
+        verbosity: 0,
+    };
+
+    let render = |respect_global_gitignore: bool| {
+        let output = cwd.join("out.md");
+        let config = CopyConfig {
+            inputs: vec!["src/".to_string()],
+            output: Some(output.clone()),
+            respect_global_gitignore,
+            ..Default::default()
+        };
+        copy::run(&context, config).unwrap();
+        fs::read_to_string(output.as_std_path()).unwrap()
+    };
+
+    let respected = render(true);
+    assert!(respected.contains("main.rs"));
+    assert!(!respected.contains("notes.scratch"));
+
+    let skipped = render(false);
+    assert!(skipped.contains("main.rs"));
+    assert!(skipped.contains("notes.scratch"));
+}
@@ -121,6 +121,7 @@ version = "0.1.0"
             respect_gitignore: true,
             ignore_files: Vec::new(),
             excludes: Vec::new(),
+            ..Default::default()
         };
 
         copy::run(&context, aggregate_config).unwrap();
@@ -132,6 +133,7 @@ version = "0.1.0"
             source: InputSource::File(aggregate_output.clone()),
             output_dir: extract_output.clone(),
             conflict: ConflictStrategy::Overwrite,
+            ..Default::default()
         };
 
         paste::run(&context, extract_config).unwrap();
@@ -181,6 +183,7 @@ fn integration_nested_directory_structure() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     copy::run(&context, aggregate_config).unwrap();
@@ -191,6 +194,7 @@ fn integration_nested_directory_structure() {
         source: InputSource::File(aggregate_output),
         output_dir: extract_output.clone(),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -251,6 +255,7 @@ fn integration_mixed_content_types() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: Vec::new(),
+        ..Default::default()
     };
 
     copy::run(&context, aggregate_config).unwrap();
@@ -275,6 +280,7 @@ fn integration_mixed_content_types() {
         source: InputSource::File(temp.path().join("mixed.md")),
         output_dir: extract_output.clone(),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
@@ -336,6 +342,7 @@ fn integration_glob_patterns_with_excludes() {
         respect_gitignore: true,
         ignore_files: Vec::new(),
         excludes: vec!["**/target/**".to_string(), "**/*_test.rs".to_string()],
+        ..Default::default()
     };
 
     copy::run(&context, aggregate_config).unwrap();
@@ -357,6 +364,7 @@ fn integration_glob_patterns_with_excludes() {
         source: InputSource::File(aggregate_output),
         output_dir: extract_output.clone(),
         conflict: ConflictStrategy::Overwrite,
+        ..Default::default()
     };
 
     paste::run(&context, extract_config).unwrap();
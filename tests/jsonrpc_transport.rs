@@ -0,0 +1,58 @@
+// Lifetime tests for the JSON-RPC transport's background reader thread.
+//
+// These live in their own test binary so the process-wide thread count is not
+// disturbed by other tests running in parallel.
+
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use quickctx::analyze::jsonrpc::JsonRpcTransport;
+
+/// Read the current thread count of this process from /proc
+fn thread_count() -> usize {
+    let status = fs::read_to_string("/proc/self/status").unwrap();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap()
+}
+
+#[test]
+fn dropping_transports_does_not_leak_reader_threads() {
+    let baseline = thread_count();
+
+    for _ in 0..20 {
+        // `cat` stands in for an LSP server: it exits once its stdin is closed
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to spawn cat");
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let transport = JsonRpcTransport::new(stdin, stdout);
+
+        // Give the reader thread time to block on the pipe, so drop has to
+        // unblock it via EOF rather than the shutdown flag alone
+        std::thread::sleep(Duration::from_millis(20));
+        drop(transport);
+
+        // `cat` only exits once it sees EOF on stdin, so this hangs if the
+        // transport kept the pipe open
+        assert!(child.wait().unwrap().success());
+    }
+
+    // Allow a short grace period for the OS to reap exited threads
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while thread_count() > baseline && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    assert_eq!(thread_count(), baseline);
+}
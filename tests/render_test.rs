@@ -1,4 +1,6 @@
-use quickctx::config::{CopyConfig, FencePreference, OutputFormat};
+use quickctx::config::{
+    BudgetStrategy, ChunkSize, CopyConfig, FenceLangMap, FencePreference, OutputFormat,
+};
 use quickctx::copy::FileEntry;
 use quickctx::render;
 
@@ -8,6 +10,8 @@ fn make_entry(relative: &str, contents: &str, language: Option<&str>) -> FileEnt
         relative: relative.into(),
         contents: contents.to_string(),
         language: language.map(String::from),
+        source: None,
+        git_info: None,
     }
 }
 
@@ -20,6 +24,7 @@ fn make_config(format: OutputFormat, fence: FencePreference) -> CopyConfig {
         respect_gitignore: true,
         ignore_files: vec![],
         excludes: vec![],
+        ..Default::default()
     }
 }
 
@@ -302,3 +307,339 @@ fn test_render_multiline_content() {
     let output = result.unwrap();
     assert!(output.contains("line 1\nline 2\nline 3\n"));
 }
+
+#[test]
+fn test_render_redact_paths_under_home() {
+    let Some(home) = dirs::home_dir() else {
+        return;
+    };
+    let absolute = home.join("notes").join("todo.md");
+    let entry = make_entry(absolute.to_str().unwrap(), "- item", Some("markdown"));
+
+    let mut config = make_config(OutputFormat::Heading, FencePreference::Auto);
+    config.redact_paths = true;
+    let output = render::render_entries(std::slice::from_ref(&entry), &config).unwrap();
+    assert!(output.contains("## `~/notes/todo.md`"));
+    assert!(!output.contains(absolute.to_str().unwrap()));
+
+    config.redact_paths = false;
+    let output = render::render_entries(&[entry], &config).unwrap();
+    assert!(output.contains(absolute.to_str().unwrap()));
+}
+
+#[test]
+fn test_render_prefix_paths() {
+    let entry = make_entry("src/x.rs", "fn x() {}", Some("rust"));
+
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    config.path_prefix = Some("repoA".into());
+    let output = render::render_entries(std::slice::from_ref(&entry), &config).unwrap();
+    assert!(output.starts_with("repoA/src/x.rs\n"));
+
+    config.format = OutputFormat::Heredoc;
+    let output = render::render_entries(&[entry], &config).unwrap();
+    assert!(output.contains("mkdir -p 'repoA/src'"));
+    assert!(output.contains("cat > 'repoA/src/x.rs'"));
+}
+
+#[test]
+fn test_render_short_fence_language_tags() {
+    let entry = make_entry("src/app.ts", "export const app = 1;", Some("typescript"));
+
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    config.fence_lang_map = FenceLangMap::Short;
+    let output = render::render_entries(std::slice::from_ref(&entry), &config).unwrap();
+    assert!(output.contains("```ts\n"));
+
+    config
+        .fence_lang_overrides
+        .insert("typescript".to_string(), "tsx".to_string());
+    let output = render::render_entries(&[entry], &config).unwrap();
+    assert!(output.contains("```tsx\n"));
+}
+
+#[test]
+fn test_render_max_total_bytes_truncates_remaining_files() {
+    let entries = vec![
+        make_entry("a.rs", "fn a() {}", Some("rust")),
+        make_entry("b.rs", "fn b() {}", Some("rust")),
+        make_entry("c.rs", &"fn c() {}\n".repeat(8), Some("rust")),
+    ];
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    let first_two = render::render_entries(&entries[..2], &config).unwrap();
+
+    // Exactly the first two files and the marker after them, which counts too
+    let marker = |cap: usize| format!("\n\n<!-- truncated at {cap} bytes -->");
+    let cap = first_two.len() + marker(first_two.len()).len();
+    config.max_total_bytes = Some(cap as u64);
+    let output = render::render_entries(&entries, &config).unwrap();
+
+    assert_eq!(output.len(), cap);
+    assert!(output.starts_with(first_two.trim_end()));
+    assert!(!output.contains("fn c() {}"));
+    assert!(output.ends_with(&format!("{}\n", marker(cap))));
+
+    // One byte less leaves no room for the marker after the second file
+    config.max_total_bytes = Some(cap as u64 - 1);
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(output.len() < cap);
+    assert!(output.contains("fn a() {}") && !output.contains("fn b() {}"));
+
+    // A cap that fits everything leaves the document untouched
+    config.max_total_bytes = Some(u64::MAX);
+    let full = render::render_entries(&entries, &config).unwrap();
+    assert!(full.contains("fn c() {}"));
+    assert!(!full.contains("truncated"));
+}
+
+#[test]
+fn test_render_max_tokens_stops_at_the_budget() {
+    let entries = vec![
+        make_entry("a.rs", &"a".repeat(400), None),
+        make_entry("b.rs", &"b".repeat(400), None),
+        make_entry("c.rs", &"c".repeat(400), None),
+    ];
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    let first_two = render::render_entries(&entries[..2], &config).unwrap();
+    let two_files = quickctx::utils::estimate_tokens(&first_two) as u64;
+
+    // Ten tokens of room covers the marker, but not the third file
+    let budget = two_files + 10;
+    config.max_tokens = Some(budget);
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(quickctx::utils::estimate_tokens(&output) as u64 <= budget);
+    assert!(output.starts_with(first_two.trim_end()));
+    assert!(!output.contains("c.rs"));
+    assert!(output.ends_with(&format!("\n\n<!-- truncated at ~{budget} tokens -->\n")));
+
+    // Without room for the marker after the second file, only the first is kept
+    config.max_tokens = Some(two_files);
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(output.contains("a.rs") && !output.contains("b.rs"));
+}
+
+#[test]
+fn test_render_budget_strategies_pick_different_files() {
+    // Collection order differs from path order, as with a manifest
+    let entries = vec![
+        make_entry("c.txt", &"c".repeat(200), None),
+        make_entry("a.txt", &"a".repeat(300), None),
+        make_entry("b.txt", &"b".repeat(100), None),
+    ];
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    let first_two = render::render_entries(&entries[..2], &config).unwrap();
+    // Room for the marker after two files, but not for the third file
+    let cap = first_two.len() as u64 + 40;
+    config.max_total_bytes = Some(cap);
+    let marker = format!("<!-- truncated at {cap} bytes -->\n");
+
+    let kept = |config: &CopyConfig| {
+        let output = render::render_entries(&entries, config).unwrap();
+        assert!(output.len() as u64 <= cap, "{output}");
+        assert!(output.ends_with(&marker), "{output}");
+        ["a.txt", "b.txt", "c.txt"]
+            .into_iter()
+            .filter(|path| output.contains(*path))
+            .collect::<Vec<_>>()
+    };
+
+    // by-order cuts at b.txt, the first file that no longer fits
+    assert_eq!(config.budget_strategy, BudgetStrategy::ByOrder);
+    assert_eq!(kept(&config), ["a.txt", "c.txt"]);
+
+    // last-first drops c.txt, the last path, and the rest fits
+    config.budget_strategy = BudgetStrategy::LastFirst;
+    assert_eq!(kept(&config), ["a.txt", "b.txt"]);
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(output.find("a.txt") < output.find("b.txt"));
+
+    // largest-first drops a.txt
+    config.budget_strategy = BudgetStrategy::LargestFirst;
+    assert_eq!(kept(&config), ["b.txt", "c.txt"]);
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(output.find("c.txt") < output.find("b.txt"));
+}
+
+#[test]
+fn test_render_budget_strategies_with_max_tokens() {
+    let entries = vec![
+        make_entry("c.txt", &"c".repeat(200), None),
+        make_entry("a.txt", &"a".repeat(300), None),
+        make_entry("b.txt", &"b".repeat(100), None),
+    ];
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    let first_two = render::render_entries(&entries[..2], &config).unwrap();
+    // Room for the marker after two files, but not for the third file
+    let budget = quickctx::utils::estimate_tokens(&first_two) as u64 + 10;
+    config.max_tokens = Some(budget);
+    let marker = format!("<!-- truncated at ~{budget} tokens -->\n");
+
+    for (strategy, expected) in [
+        (BudgetStrategy::ByOrder, ["a.txt", "c.txt"]),
+        (BudgetStrategy::LastFirst, ["a.txt", "b.txt"]),
+        (BudgetStrategy::LargestFirst, ["b.txt", "c.txt"]),
+    ] {
+        config.budget_strategy = strategy;
+        let output = render::render_entries(&entries, &config).unwrap();
+        assert!(output.ends_with(&marker), "{strategy}: {output}");
+        assert!(quickctx::utils::estimate_tokens(&output) as u64 <= budget);
+        let kept: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+            .into_iter()
+            .filter(|path| output.contains(*path))
+            .collect();
+        assert_eq!(kept, expected, "{strategy}");
+    }
+}
+
+#[test]
+fn test_render_annotate_sizes_in_headers() {
+    let entries = vec![
+        make_entry("small.txt", &"x".repeat(12), None),
+        make_entry("big.rs", &"y".repeat(2355), Some("rust")),
+    ];
+
+    let mut config = make_config(OutputFormat::Heading, FencePreference::Auto);
+    config.annotate_sizes = true;
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(output.contains("## `small.txt` (size: 12 B)\n"));
+    assert!(output.contains("## `big.rs` (size: 2.3 KB)\n"));
+
+    config.format = OutputFormat::Comment;
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(output.contains("// big.rs (size: 2.3 KB)\n"));
+
+    config.format = OutputFormat::Heredoc;
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(!output.contains("KB"));
+}
+
+#[test]
+fn test_render_parallel_matches_serial() {
+    let entries: Vec<FileEntry> = (0..23)
+        .map(|i| {
+            // Some blocks hold backtick runs so fences differ between entries
+            let contents = format!("fn f{i}() {{}}\n{}\n", "`".repeat(i % 5));
+            make_entry(&format!("src/f{i}.rs"), &contents, Some("rust"))
+        })
+        .collect();
+
+    for format in [
+        OutputFormat::Simple,
+        OutputFormat::Heading,
+        OutputFormat::Heredoc,
+    ] {
+        let mut config = make_config(format, FencePreference::Auto);
+        let serial = render::render_entries(&entries, &config).unwrap();
+        for workers in [2, 4, 64] {
+            config.rendering_workers = workers;
+            assert_eq!(render::render_entries(&entries, &config).unwrap(), serial);
+        }
+        config.rendering_workers = 1;
+
+        // The byte cap cuts at the same entry either way
+        config.max_total_bytes = Some(serial.len() as u64 / 2);
+        let serial = render::render_entries(&entries, &config).unwrap();
+        config.rendering_workers = 3;
+        assert_eq!(render::render_entries(&entries, &config).unwrap(), serial);
+    }
+}
+
+#[test]
+fn test_render_normalizes_backslash_separators() {
+    let entry = make_entry("src\\bin\\main.rs", "fn main() {}", Some("rust"));
+
+    for format in [
+        OutputFormat::Simple,
+        OutputFormat::Comment,
+        OutputFormat::Heading,
+        OutputFormat::Heredoc,
+    ] {
+        let mut config = make_config(format, FencePreference::Auto);
+        let output = render::render_entries(std::slice::from_ref(&entry), &config).unwrap();
+        assert!(output.contains("src/bin/main.rs"), "{output}");
+        assert!(!output.contains('\\'), "{output}");
+
+        config.normalize_paths = false;
+        let output = render::render_entries(std::slice::from_ref(&entry), &config).unwrap();
+        assert!(output.contains("src\\bin\\main.rs"), "{output}");
+    }
+}
+
+#[test]
+fn test_render_header_template_replaces_path_line() {
+    let entries = [
+        make_entry("a.txt", "first", None),
+        make_entry("src/main.rs", "fn main() {}", Some("rust")),
+    ];
+    let mut config = make_config(OutputFormat::Comment, FencePreference::Backtick);
+    config.header_template = Some("File {index}: {path} [{lang}, {size}]".parse().unwrap());
+
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(
+        output.contains("File 2: src/main.rs [rust, 12 B]\n\n```rust\nfn main() {}\n```"),
+        "{output}"
+    );
+    assert!(!output.contains("// src/main.rs"), "{output}");
+}
+
+#[test]
+fn test_render_line_numbers_restart_per_file() {
+    let long: String = (1..=10).map(|n| format!("line {n}\n")).collect();
+    let entries = [
+        make_entry("notes.md", "intro\n```sh\nls\n```", Some("markdown")),
+        make_entry("long.txt", &long, None),
+    ];
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    config.line_numbers = true;
+
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(
+        output.contains("~~~markdown\n1 | intro\n2 | ```sh\n3 | ls\n4 | ```\n~~~"),
+        "{output}"
+    );
+    assert!(
+        output.contains("```\n 1 | line 1\n 2 | line 2\n"),
+        "{output}"
+    );
+    assert!(output.contains("10 | line 10\n```"), "{output}");
+}
+
+#[test]
+fn test_render_line_numbers_keep_crlf_endings() {
+    let entries = [make_entry("dos.txt", "one\r\ntwo\r\nthree", None)];
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    config.line_numbers = true;
+
+    let output = render::render_entries(&entries, &config).unwrap();
+    assert!(
+        output.contains("```\n1 | one\r\n2 | two\r\n3 | three\n```"),
+        "{output:?}"
+    );
+}
+
+#[test]
+fn test_render_dedup_references_stay_within_each_document() {
+    let stub = "pub struct Stub;\n";
+    let entries = [
+        make_entry("a.rs", stub, Some("rust")),
+        make_entry("b.rs", stub, Some("rust")),
+        make_entry("c.rs", &"c".repeat(200), None),
+        make_entry("d.rs", stub, Some("rust")),
+    ];
+    let mut config = make_config(OutputFormat::Simple, FencePreference::Auto);
+    config.dedup_contents = true;
+
+    let single = render::render_entries(&entries, &config).unwrap();
+    assert!(single.contains("b.rs\n\n(identical to a.rs)\n"), "{single}");
+    assert!(single.contains("d.rs\n\n(identical to a.rs)\n"), "{single}");
+
+    // c.rs fills the second chunk, so d.rs starts a third one and keeps its contents
+    let chunks = render::render_chunks(&entries, &config, ChunkSize::Bytes(100)).unwrap();
+    assert_eq!(chunks.len(), 3, "{chunks:?}");
+    assert!(chunks[0].contains("(identical to a.rs)"));
+    assert!(chunks[2].contains(stub) && !chunks[2].contains("identical"));
+
+    // The two stubs land in different parts, and each keeps its contents
+    let parts = render::render_parts(&entries[..2], &config, 2).unwrap();
+    assert!(parts.iter().all(|part| part.contains(stub)), "{parts:?}");
+}
@@ -1,5 +1,8 @@
 use camino::{Utf8Path, Utf8PathBuf};
-use quickctx::utils::{is_probably_binary, language_for_path, looks_like_glob, relative_to};
+use quickctx::utils::{
+    estimate_tokens, human_size, is_probably_binary, language_for_path, looks_like_glob,
+    parse_size, redact_path, relative_path_from, relative_to,
+};
 
 #[test]
 fn test_language_for_path_by_extension() {
@@ -232,3 +235,110 @@ fn test_language_for_web_files() {
         Some("typescript")
     );
 }
+
+#[cfg(unix)]
+#[test]
+fn test_relative_path_from_climbs_out_of_base() {
+    let base = Utf8Path::new("/work/project");
+    assert_eq!(
+        relative_path_from(Utf8Path::new("/work/project/src/lib.rs"), base),
+        Utf8PathBuf::from("src/lib.rs")
+    );
+    assert_eq!(
+        relative_path_from(Utf8Path::new("/opt/vendor/lib.rs"), base),
+        Utf8PathBuf::from("../../opt/vendor/lib.rs")
+    );
+    assert_eq!(
+        relative_path_from(Utf8Path::new("/work/shared/mod.rs"), base),
+        Utf8PathBuf::from("../shared/mod.rs")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_redact_path() {
+    let home = Utf8Path::new("/home/alice");
+    assert_eq!(
+        redact_path(Utf8Path::new("/home/alice/notes/todo.md"), Some(home)),
+        "~/notes/todo.md"
+    );
+    assert_eq!(
+        redact_path(Utf8Path::new("/etc/hosts"), Some(home)),
+        "<root>/etc/hosts"
+    );
+    assert_eq!(
+        redact_path(Utf8Path::new("src/lib.rs"), Some(home)),
+        "src/lib.rs"
+    );
+}
+
+#[test]
+fn test_estimate_tokens() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("abcd"), 1);
+    assert_eq!(estimate_tokens("abcde"), 2);
+    // Counted in characters, not bytes
+    assert_eq!(estimate_tokens("éééé"), 1);
+}
+
+#[test]
+fn test_human_size_thresholds() {
+    assert_eq!(human_size(0), "0 B");
+    assert_eq!(human_size(1023), "1023 B");
+    assert_eq!(human_size(1024), "1.0 KB");
+    assert_eq!(human_size(2355), "2.3 KB");
+    assert_eq!(human_size(1024 * 1024), "1.0 MB");
+    assert_eq!(human_size(5 * 1024 * 1024 * 1024), "5.0 GB");
+    // Just under a unit rounds up into it
+    assert_eq!(human_size(1024 * 1024 - 1), "1.0 MB");
+    assert_eq!(human_size(1024 * 1024 * 1024 - 1), "1.0 GB");
+}
+
+#[test]
+fn test_parse_size_suffixes() {
+    assert_eq!(parse_size("0").unwrap(), 0);
+    assert_eq!(parse_size("512").unwrap(), 512);
+    assert_eq!(parse_size("512b").unwrap(), 512);
+    assert_eq!(parse_size("10k").unwrap(), 10 * 1024);
+    assert_eq!(parse_size("10K").unwrap(), 10 * 1024);
+    assert_eq!(parse_size("10 KB").unwrap(), 10 * 1024);
+    assert_eq!(parse_size("10KiB").unwrap(), 10 * 1024);
+    assert_eq!(parse_size("1.5M").unwrap(), 1536 * 1024);
+    assert_eq!(parse_size("2G").unwrap(), 2 << 30);
+    assert_eq!(parse_size(" 3m ").unwrap(), 3 << 20);
+    // Fractions round down to whole bytes
+    assert_eq!(parse_size("0.5").unwrap(), 0);
+    assert_eq!(parse_size("1.0001k").unwrap(), 1024);
+}
+
+#[test]
+fn test_parse_size_rejects_invalid_input() {
+    for raw in [
+        "",
+        "k",
+        "-1",
+        "1.2.3",
+        "10x",
+        "10kk",
+        "10 tb",
+        "ib",
+        "1e3",
+        "18446744073709551616",
+        "17179869184g",
+    ] {
+        assert!(parse_size(raw).is_err(), "{raw:?} should not parse");
+    }
+    assert_eq!(parse_size("18446744073709551615").unwrap(), u64::MAX);
+}
+
+#[test]
+fn test_parse_size_round_trips_human_size() {
+    for bytes in [0, 1, 1023, 1024, 1536, 10 * 1024, 3 << 20, 5 << 30] {
+        assert_eq!(parse_size(&human_size(bytes)).unwrap(), bytes);
+    }
+    // human_size keeps one decimal, so other sizes come back within that rounding
+    for bytes in [2355u64, 1_000_000, 123_456_789] {
+        let parsed = parse_size(&human_size(bytes)).unwrap();
+        assert!(parsed.abs_diff(bytes) * 20 <= bytes, "{bytes} -> {parsed}");
+    }
+}